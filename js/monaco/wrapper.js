@@ -0,0 +1,42 @@
+// A wrapper to show the validation results of YAML models, using monaco's own API
+//
+// Monaco only ships a tokenizer for YAML, no language service. The content is validated on the Rust side, against
+// the JSON schema of the settings and by deserializing it, and the results are shown as model markers.
+//
+// We need the global "monaco" API for this. It gets exposed by setting "MonacoEnvironment.globalAPI" before monaco
+// is loaded (see index.html). If it isn't present, we simply skip the validation, which leaves the editor working.
+
+const MARKER_OWNER = "json-schema";
+
+function api() {
+    if (typeof globalThis.monaco === "undefined") {
+        console.log("Monaco API not available, skipping validation");
+        return null;
+    }
+    return globalThis.monaco;
+}
+
+// Validate the model on every change, using the provided validator, returning a list of markers.
+export function watchYamlModel(model, validator) {
+    const monaco = api();
+    if (monaco === null) {
+        return null;
+    }
+
+    const validate = () => {
+        monaco.editor.setModelMarkers(model, MARKER_OWNER, validator(model.getValue()));
+    };
+
+    validate();
+    return model.onDidChangeContent(validate);
+}
+
+export function unwatchYamlModel(model, subscription) {
+    if (subscription) {
+        subscription.dispose();
+    }
+    const monaco = api();
+    if (monaco !== null && !model.isDisposed()) {
+        monaco.editor.setModelMarkers(model, MARKER_OWNER, []);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::data::{SharedDataBridge, SharedDataOps};
-use anyhow::anyhow;
+use crate::share::{self, Shared, Verification, SHARE_KEY};
+use gloo_storage::{LocalStorage, Storage};
 use gloo_utils::window;
 use patternfly_yew::*;
 use url::Url;
@@ -29,6 +30,8 @@ pub enum AppRoute {
     State,
     #[to = "/claims"]
     Claims,
+    #[to = "/cloud"]
+    Cloud,
     #[to = "/add"]
     Add,
     #[to = "/simulation/{id}/{*:details}"]
@@ -66,7 +69,7 @@ pub enum Msg {
     InitError(Toast),
     InitNotice(Toast),
 
-    Settings(Settings),
+    Settings(Box<Settings>),
     Simulator(SimulatorState),
 
     Start,
@@ -87,12 +90,20 @@ impl Component for ApplicationView {
     fn create(ctx: &Context<Self>) -> Self {
         let cfg = find_config();
 
-        let mut _settings_agent = SharedDataBridge::from(ctx.link(), Msg::Settings);
+        let mut _settings_agent =
+            SharedDataBridge::from(ctx.link(), |settings| Msg::Settings(Box::new(settings)));
 
         match cfg {
-            Ok(Some((cfg, source))) => {
+            Ok(Some((mut cfg, source))) => {
                 match source {
-                    Source::External => {
+                    Source::External(verification) => {
+                        // don't connect to an endpoint the signer didn't choose
+                        if verification == Verification::Failed {
+                            cfg.auto_connect = false;
+                        }
+                        if let Some(toast) = verification_toast(verification) {
+                            ctx.link().send_message(Msg::InitError(toast));
+                        }
                         if let Some(import) = &cfg.import {
                             if import.hint_connection {
                                 let text = import.hint_text.as_deref().unwrap_or("The configuration was imported from the URL linking to this instance. Please check that the connection parameters are valid or update them accordingly.");
@@ -139,7 +150,7 @@ impl Component for ApplicationView {
                 )));
             }
             Msg::Settings(settings) => {
-                self.settings = settings;
+                self.settings = *settings;
             }
             Msg::Simulator(state) => {
                 self.simulator_state = state;
@@ -180,6 +191,7 @@ impl Component for ApplicationView {
                             <NavRouterItem<AppRoute> to={AppRoute::Events}>{"Events"}</NavRouterItem<AppRoute>>
                             <NavRouterItem<AppRoute> to={AppRoute::Publish}>{"Publish"}</NavRouterItem<AppRoute>>
                             <NavRouterItem<AppRoute> to={AppRoute::Commands}>{"Received Commands"}</NavRouterItem<AppRoute>>
+                            <NavRouterItem<AppRoute> to={AppRoute::Cloud}>{"Cloud side"}</NavRouterItem<AppRoute>>
                         </NavRouterExpandable<AppRoute>>
                         <NavRouterExpandable<AppRoute> title="Simulations" expanded=true>
                             <NavRouterItem<AppRoute> to={AppRoute::Add}>{ Icon::PlusCircleIcon} <span class="pf-u-px-sm">{ "Add" }</span> </NavRouterItem<AppRoute>>
@@ -238,6 +250,7 @@ impl Component for ApplicationView {
                                     AppRoute::Commands => html!{<pages::AppPage<pages::Commands>/>},
                                     AppRoute::State => html!{<pages::AppPage<pages::State>/>},
                                     AppRoute::Claims => html!{<pages::AppPage<pages::InternalClaims>/>},
+                                    AppRoute::Cloud => html!{<pages::AppPage<pages::Cloud>/>},
                                     AppRoute::Events => html!{<pages::AppPage<pages::Events>/>},
                                     AppRoute::Configuration => html!{<pages::AppPage<pages::Configuration>/>},
                                     AppRoute::Add => html!{<pages::AppPage<pages::Add>/>},
@@ -252,39 +265,38 @@ impl Component for ApplicationView {
 
 pub enum Source {
     Storage,
-    External,
+    External(Verification),
 }
 
 fn find_config() -> Result<Option<(Settings, Source)>, Toast> {
-    if let Some(cfg) = find_config_str() {
-        log::info!("Found provided settings");
-        match base64::decode_config(&cfg, base64::URL_SAFE)
-            .map_err(|err| anyhow!("Failed to decode base64 encoding: {err} was: {cfg}"))
-            .and_then(|cfg| {
-                serde_json::from_slice(&cfg).map_err(|err| {
-                    anyhow!(
-                        "Failed to parse provided configuration: {err} was: {:?}",
-                        String::from_utf8(cfg)
-                    )
-                })
-            }) {
-            Ok(settings) => Ok(Some((settings, Source::External))),
-            Err(err) => Err(Toast {
-                title: "Failed to load configuration".to_string(),
-                r#type: Type::Danger,
-                timeout: None,
-                body: html!(
-                    <Content>
-                        <p>
-                            {"The simulator was opened with a provided configuration. However, that configuration could not be loaded due to the following error: "}
-                        </p>
-                        <p>{err}</p>
-                    </Content>
-                ),
-                actions: vec![],
-            }),
+    match find_provided_config() {
+        Ok(Some(Shared {
+            settings,
+            verification,
+        })) => {
+            log::info!("Found provided settings");
+            Ok(Some((settings, Source::External(verification))))
         }
-    } else if let Some(settings) = Settings::load() {
+        Err(err) => Err(Toast {
+            title: "Failed to load configuration".to_string(),
+            r#type: Type::Danger,
+            timeout: None,
+            body: html!(
+                <Content>
+                    <p>
+                        {"The simulator was opened with a provided configuration. However, that configuration could not be loaded due to the following error: "}
+                    </p>
+                    <p>{err}</p>
+                </Content>
+            ),
+            actions: vec![],
+        }),
+        Ok(None) => find_stored_config(),
+    }
+}
+
+fn find_stored_config() -> Result<Option<(Settings, Source)>, Toast> {
+    if let Some(settings) = Settings::load() {
         log::info!("Found default settings");
         match settings {
             Ok(settings) => Ok(Some((settings, Source::Storage))),
@@ -309,15 +321,36 @@ fn find_config() -> Result<Option<(Settings, Source)>, Toast> {
     }
 }
 
-fn find_config_str() -> Option<String> {
+fn find_provided_config() -> anyhow::Result<Option<Shared>> {
     if let Ok(href) = window().location().href() {
         if let Ok(url) = Url::parse(&href) {
-            for q in url.query_pairs() {
-                if q.0 == "c" {
-                    return Some(q.1.to_string());
-                }
-            }
+            let key: Option<String> = LocalStorage::get(SHARE_KEY).ok();
+            return share::decode(url.query_pairs(), key.as_deref());
         }
     }
-    None
+    Ok(None)
+}
+
+fn verification_toast(verification: Verification) -> Option<Toast> {
+    let (r#type, title, text) = match verification {
+        Verification::Unsigned | Verification::Verified => return None,
+        Verification::NoKey => (
+            Type::Warning,
+            "Unverified configuration",
+            "The provided configuration is signed, but no key is configured to verify it.",
+        ),
+        Verification::Failed => (
+            Type::Danger,
+            "Invalid configuration signature",
+            "The signature of the provided configuration does not match. The configuration might have been tampered with, please check it carefully before connecting. Connecting automatically has been disabled.",
+        ),
+    };
+
+    Some(Toast {
+        title: title.to_string(),
+        r#type,
+        timeout: None,
+        body: html!( <Content> { text } </Content> ),
+        actions: vec![],
+    })
 }
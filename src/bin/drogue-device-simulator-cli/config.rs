@@ -0,0 +1,165 @@
+//! Loading the settings, as used by the web UI.
+
+use anyhow::{bail, Context};
+use drogue_device_simulator::{
+    settings::Settings,
+    share::{self, Verification},
+};
+use std::io::Read;
+
+/// Load the settings from a YAML or JSON file, or `-` for stdin.
+///
+/// Instead of a file name, or as content of the file, a share link (or just its query, `?c=…`)
+/// may be used too.
+pub fn load(source: &str, key: Option<&str>) -> anyhow::Result<Settings> {
+    let settings = match from_share(source, key)? {
+        Some(settings) => settings,
+        None => {
+            let content = if source == "-" {
+                let mut content = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut content)
+                    .context("Failed to read settings from stdin")?;
+                content
+            } else {
+                std::fs::read_to_string(source)
+                    .with_context(|| format!("Failed to read settings from '{source}'"))?
+            };
+            parse(&content, key)?
+        }
+    };
+
+    validate(&settings)?;
+
+    Ok(settings)
+}
+
+/// Parse settings, which are either YAML, JSON, or a share link.
+pub fn parse(content: &str, key: Option<&str>) -> anyhow::Result<Settings> {
+    if let Some(settings) = from_share(content.trim(), key)? {
+        return Ok(settings);
+    }
+
+    // YAML is a superset of JSON
+    serde_yaml::from_str(content).context("Failed to parse settings")
+}
+
+/// Decode a share link, or return `None` if the value isn't one.
+fn from_share(value: &str, key: Option<&str>) -> anyhow::Result<Option<Settings>> {
+    let query = match value.split_once('?') {
+        // a share link is a single line
+        Some((_, query)) if !query.contains(char::is_whitespace) => query,
+        _ => return Ok(None),
+    };
+
+    let shared = match share::decode(url::form_urlencoded::parse(query.as_bytes()), key)? {
+        Some(shared) => shared,
+        None => return Ok(None),
+    };
+
+    match shared.verification {
+        Verification::Failed => bail!("The signature of the shared settings doesn't match"),
+        Verification::NoKey => log::warn!("Shared settings are signed, but no key was provided"),
+        Verification::Unsigned | Verification::Verified => {}
+    }
+
+    Ok(Some(shared.settings))
+}
+
+/// Ensure all simulations can run, outside the browser.
+pub fn validate(settings: &Settings) -> anyhow::Result<()> {
+    settings
+        .payload
+        .validate()
+        .context("Invalid payload settings")?;
+
+    for (id, entry) in &settings.simulations {
+        if entry.simulation.requires_browser() {
+            bail!("Simulation '{id}' requires a browser");
+        }
+        entry
+            .simulation
+            .validate()
+            .with_context(|| format!("Invalid simulation '{id}'"))?;
+        if let Some(schedule) = &entry.schedule {
+            schedule
+                .validate()
+                .with_context(|| format!("Invalid schedule of '{id}'"))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use drogue_device_simulator::share::ShareOptions;
+
+    const YAML: &str = r#"
+autoConnect: false
+target:
+  mqtt:
+    url: mqtt://localhost:1883
+    credentials: none
+application: app
+device: device
+simulations:
+  sine1:
+    sine:
+      amplitude: 100
+      length: 1m
+"#;
+
+    #[test]
+    fn test_parse_yaml() {
+        let settings = parse(YAML, None).unwrap();
+        assert_eq!("app", settings.application);
+        assert!(settings.simulations.contains_key("sine1"));
+        assert!(validate(&settings).is_ok());
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let json = serde_json::to_string(&parse(YAML, None).unwrap()).unwrap();
+        assert_eq!(parse(YAML, None).unwrap(), parse(&json, None).unwrap());
+    }
+
+    #[test]
+    fn test_parse_share() {
+        let settings = parse(YAML, None).unwrap();
+        let params = share::encode(
+            &settings,
+            &ShareOptions {
+                signing_key: Some("key".into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(params)
+            .finish();
+
+        let link = format!("https://device-simulator.com/?{query}");
+        assert_eq!(settings, parse(&link, Some("key")).unwrap());
+        assert_eq!(settings, parse(&format!("?{query}\n"), None).unwrap());
+        assert!(parse(&link, Some("other")).is_err());
+    }
+
+    #[test]
+    fn test_requires_browser() {
+        let mut settings = parse(YAML, None).unwrap();
+        let yaml = r#"
+accelerometer:
+  target:
+    channel: state
+    feature: motion
+  source:
+    type: browser
+"#;
+        settings
+            .simulations
+            .insert("accel".into(), serde_yaml::from_str(yaml).unwrap());
+        assert!(validate(&settings).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+//! Run the simulations of a device natively, without a browser.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod config;
+#[cfg(not(target_arch = "wasm32"))]
+mod mqtt;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> anyhow::Result<()> {
+    native::main()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use crate::{config, mqtt};
+    use anyhow::bail;
+    use chrono::Utc;
+    use clap::Parser;
+    use drogue_device_simulator::utils::handler::Handler;
+    use drogue_device_simulator::{
+        settings::{Settings, Target},
+        simulator::{
+            engine::{Engine, Output},
+            State,
+        },
+        utils::hex,
+    };
+    use futures::{channel::mpsc, future, FutureExt, StreamExt};
+    use std::time::Duration;
+    use tokio::task::LocalSet;
+
+    /// Number of bytes shown of binary payloads.
+    const MAX_PREVIEW: usize = 32;
+
+    /// Run simulated devices, without a browser.
+    ///
+    /// Events published by the simulations, and commands received from the cloud, are printed to
+    /// stdout. Logs go to stderr.
+    #[derive(Debug, Parser)]
+    #[command(version)]
+    struct Args {
+        /// The settings: a YAML or JSON file, `-` for stdin, or a share link.
+        settings: String,
+
+        /// Override the URL of the MQTT endpoint.
+        #[arg(long)]
+        url: Option<String>,
+
+        /// The key to verify signed share links.
+        #[arg(long)]
+        share_key: Option<String>,
+
+        /// Stop after this duration (e.g. `30s`), instead of running until interrupted.
+        #[arg(long)]
+        duration: Option<humantime::Duration>,
+    }
+
+    pub fn main() -> anyhow::Result<()> {
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+        let args = Args::parse();
+
+        let mut settings = config::load(&args.settings, args.share_key.as_deref())?;
+        if let Some(url) = args.url {
+            match &mut settings.target {
+                Target::Mqtt { url: target, .. } => *target = url,
+                Target::Http { .. }
+                | Target::Coap { .. }
+                | Target::WebSocket { .. }
+                | Target::ServerSentEvents { .. }
+                | Target::Local => {
+                    bail!("The URL can only be overridden for MQTT targets")
+                }
+            }
+        }
+
+        // fail early, with a proper message
+        mqtt::options(&settings)?;
+        mqtt::topics(&settings)?;
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+
+        let local = LocalSet::new();
+        let result = local.block_on(&runtime, run(settings, args.duration.map(Into::into)));
+
+        // give the connector time to flush its disconnect, before dropping the remaining tasks
+        let _ = runtime.block_on(tokio::time::timeout(mqtt::DISCONNECT_TIMEOUT * 2, local));
+
+        result
+    }
+
+    async fn run(settings: Settings, duration: Option<Duration>) -> anyhow::Result<()> {
+        let (tx, mut rx) = mpsc::unbounded();
+        let (output_tx, mut output_rx) = mpsc::unbounded();
+
+        let mut engine = Engine::new(
+            Handler::from(move |msg| {
+                tx.unbounded_send(msg).ok();
+            }),
+            Handler::from(move |output| {
+                output_tx.unbounded_send(output).ok();
+            }),
+            mqtt::MqttConnectors,
+        );
+
+        engine.update_settings(settings);
+        engine.start();
+
+        let deadline = match duration {
+            Some(duration) => tokio::time::sleep(duration).boxed_local(),
+            None => future::pending().boxed_local(),
+        };
+        tokio::pin!(deadline);
+
+        let mut state = String::new();
+
+        loop {
+            tokio::select! {
+                Some(msg) = rx.next() => engine.update(msg),
+                Some(output) = output_rx.next() => match output {
+                    Output::State(current) => {
+                        if let State::Failed(err) = &current.state {
+                            bail!("Connection failed: {err}");
+                        }
+                        let current = current.state.to_string();
+                        if current != state {
+                            log::info!("{current}");
+                            state = current;
+                        }
+                    }
+                    Output::Event(event) => println!(
+                        "{} event {} {}",
+                        event.timestamp.to_rfc3339(),
+                        event.channel,
+                        payload(&event.payload),
+                    ),
+                    Output::Command(command) => println!(
+                        "{} command {} {}",
+                        Utc::now().to_rfc3339(),
+                        command.name,
+                        command.payload.as_deref().map(payload).unwrap_or_default(),
+                    ),
+                    Output::SimulationState(..) | Output::InternalState(_) => {}
+                },
+                () = &mut deadline => break,
+                _ = tokio::signal::ctrl_c() => break,
+            }
+        }
+
+        engine.stop();
+
+        Ok(())
+    }
+
+    /// Format a payload for printing, on a single line.
+    fn payload(data: &[u8]) -> String {
+        match std::str::from_utf8(data) {
+            Ok(str) => str.replace('\n', " "),
+            Err(_) => format!("{} bytes: {}", data.len(), hex::preview(data, MAX_PREVIEW)),
+        }
+    }
+}
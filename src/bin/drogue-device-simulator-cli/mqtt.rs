@@ -0,0 +1,438 @@
+//! Native MQTT connector, using rumqttc.
+
+use anyhow::{anyhow, bail};
+use drogue_device_simulator::utils::handler::Handler;
+use drogue_device_simulator::{
+    connector::mqtt::QoS,
+    runtime,
+    settings::{Settings, Target},
+    simulator::{
+        mqtt::Topics, Command, ConnectOptions, Connector, ConnectorFactory, SubscribeOptions,
+    },
+};
+use futures::channel::oneshot;
+use rumqttc::{AsyncClient, Event, MqttOptions, Outgoing, Packet, Transport};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::Duration,
+};
+use url::Url;
+
+/// Maximum size of a packet, allowing binary payloads like images.
+const MAX_PACKET_SIZE: usize = 1024 * 1024;
+
+/// Time to flush the disconnect, after a connector got dropped.
+pub const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Create the MQTT options, from the target of the settings.
+///
+/// Supports `mqtt://` (TCP), `mqtts://` (TLS), `ws://` and `wss://` (WebSocket) URLs.
+pub fn options(settings: &Settings) -> anyhow::Result<MqttOptions> {
+    let (url, credentials) = match &settings.target {
+        Target::Mqtt {
+            url, credentials, ..
+        } => (url, credentials),
+        target => bail!("Unsupported target: {}", target.as_protocol()),
+    };
+
+    let parsed = Url::parse(url).map_err(|err| anyhow!("Invalid URL '{url}': {err}"))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| anyhow!("Missing host in URL: {url}"))?;
+    let client_id = uuid::Uuid::new_v4().to_string();
+
+    let (mut options, transport) = match parsed.scheme() {
+        "mqtt" | "tcp" => (
+            MqttOptions::new(client_id, host, parsed.port().unwrap_or(1883)),
+            Transport::tcp(),
+        ),
+        "mqtts" | "ssl" => (
+            MqttOptions::new(client_id, host, parsed.port().unwrap_or(8883)),
+            Transport::tls_with_default_config(),
+        ),
+        // websockets use the full URL as address
+        "ws" => (
+            MqttOptions::new(client_id, url, parsed.port_or_known_default().unwrap_or(80)),
+            Transport::ws(),
+        ),
+        "wss" => (
+            MqttOptions::new(
+                client_id,
+                url,
+                parsed.port_or_known_default().unwrap_or(443),
+            ),
+            Transport::wss_with_default_config(),
+        ),
+        scheme => bail!("Unsupported URL scheme: {scheme}"),
+    };
+
+    options
+        .set_transport(transport)
+        .set_clean_session(true)
+        .set_keep_alive(Duration::from_secs(5))
+        .set_max_packet_size(MAX_PACKET_SIZE, MAX_PACKET_SIZE);
+
+    if let (Some(username), Some(password)) = credentials.to_username_password(settings) {
+        options.set_credentials(username, password);
+    }
+
+    Ok(options)
+}
+
+/// Create the topics, from the templates of the target.
+pub fn topics(settings: &Settings) -> anyhow::Result<Topics> {
+    match &settings.target {
+        Target::Mqtt { topics, .. } => Topics::new(topics, settings),
+        target => bail!("Unsupported target: {}", target.as_protocol()),
+    }
+}
+
+/// Creates MQTT connectors, the only target supported natively.
+pub struct MqttConnectors;
+
+impl ConnectorFactory for MqttConnectors {
+    fn create(
+        &self,
+        settings: &Settings,
+        on_command: Handler<Command>,
+        on_connection_lost: Handler<String>,
+    ) -> anyhow::Result<Box<dyn Connector>> {
+        Ok(Box::new(MqttConnector::new(
+            options(settings)?,
+            topics(settings)?,
+            on_command,
+            on_connection_lost,
+        )))
+    }
+}
+
+pub struct MqttConnector {
+    options: Option<MqttOptions>,
+    topics: Rc<Topics>,
+    client: Option<AsyncClient>,
+    on_command: Handler<Command>,
+    on_connection_lost: Handler<String>,
+    /// A subscription waiting for its acknowledgement.
+    subscription: Rc<RefCell<Option<SubscribeOptions>>>,
+    /// Set when dropped, the event loop then only flushes the disconnect.
+    closing: Rc<Cell<bool>>,
+    /// Starts the deadline of the event loop, when dropped.
+    closed: Option<oneshot::Sender<()>>,
+}
+
+impl MqttConnector {
+    pub fn new(
+        options: MqttOptions,
+        topics: Topics,
+        on_command: Handler<Command>,
+        on_connection_lost: Handler<String>,
+    ) -> Self {
+        Self {
+            options: Some(options),
+            topics: Rc::new(topics),
+            client: None,
+            on_command,
+            on_connection_lost,
+            subscription: Default::default(),
+            closing: Default::default(),
+            closed: None,
+        }
+    }
+}
+
+impl Connector for MqttConnector {
+    fn connect(&mut self, opts: ConnectOptions) -> anyhow::Result<()> {
+        let options = self
+            .options
+            .take()
+            .ok_or_else(|| anyhow!("Already connected"))?;
+        let (client, mut event_loop) = AsyncClient::new(options, 100);
+
+        let topics = self.topics.clone();
+        let on_command = self.on_command.clone();
+        let on_connection_lost = self.on_connection_lost.clone();
+        let subscription = self.subscription.clone();
+        let closing = self.closing.clone();
+
+        let task = async move {
+            let mut connected = false;
+            loop {
+                match event_loop.poll().await {
+                    Ok(Event::Outgoing(Outgoing::Disconnect)) => break,
+                    Ok(_) if closing.get() => {}
+                    Err(_) if closing.get() => break,
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        connected = true;
+                        opts.on_success.emit(());
+                    }
+                    Ok(Event::Incoming(Packet::SubAck(_))) => {
+                        if let Some(subscription) = subscription.borrow_mut().take() {
+                            subscription.on_success.emit(());
+                        }
+                    }
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        if let Some(command) =
+                            topics.parse_command(&publish.topic, publish.payload.to_vec())
+                        {
+                            on_command.emit(command);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(err) => {
+                        match connected {
+                            true => on_connection_lost.emit(err.to_string()),
+                            false => opts.on_failure.emit(err.to_string()),
+                        }
+                        break;
+                    }
+                }
+            }
+        };
+
+        // keep polling when the connector got dropped, until the disconnect is sent or the
+        // deadline expires
+        let (closed, deadline) = oneshot::channel::<()>();
+        runtime::spawn(async move {
+            tokio::select! {
+                _ = task => {}
+                _ = async {
+                    let _ = deadline.await;
+                    tokio::time::sleep(DISCONNECT_TIMEOUT).await;
+                } => log::info!("Timeout flushing the disconnect"),
+            }
+        });
+
+        self.client = Some(client);
+        self.closed = Some(closed);
+
+        Ok(())
+    }
+
+    fn subscribe(&mut self, opts: SubscribeOptions) -> anyhow::Result<()> {
+        let client = self
+            .client
+            .as_ref()
+            .ok_or_else(|| anyhow!("Not connected"))?;
+        client.try_subscribe(self.topics.command_filter(), rumqttc::QoS::AtMostOnce)?;
+        *self.subscription.borrow_mut() = Some(opts);
+        Ok(())
+    }
+
+    fn publish(&mut self, channel: &str, payload: Vec<u8>, qos: QoS) {
+        if let Some(client) = &self.client {
+            if let Err(err) =
+                client.try_publish(self.topics.publish(channel), to_qos(qos), false, payload)
+            {
+                log::info!("Failed to publish: {err}");
+            }
+        }
+    }
+
+    fn publish_retained(&mut self, topic: &str, payload: Vec<u8>, qos: QoS) -> anyhow::Result<()> {
+        let client = self
+            .client
+            .as_ref()
+            .ok_or_else(|| anyhow!("Not connected"))?;
+        client.try_publish(topic, to_qos(qos), true, payload)?;
+        Ok(())
+    }
+}
+
+fn to_qos(qos: QoS) -> rumqttc::QoS {
+    match qos {
+        QoS::QoS0 => rumqttc::QoS::AtMostOnce,
+        QoS::QoS1 => rumqttc::QoS::AtLeastOnce,
+        QoS::QoS2 => rumqttc::QoS::ExactlyOnce,
+    }
+}
+
+impl Drop for MqttConnector {
+    fn drop(&mut self) {
+        self.closing.set(true);
+        if let Some(client) = &self.client {
+            let _ = client.try_disconnect();
+        }
+        // dropping the sender starts the deadline
+        self.closed.take();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use drogue_device_simulator::settings::Credentials;
+    use futures::{
+        channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
+        StreamExt,
+    };
+    use rumqttc::LastWill;
+    use tokio::task::LocalSet;
+
+    fn settings(url: &str, credentials: Credentials) -> Settings {
+        Settings {
+            target: Target::Mqtt {
+                url: url.into(),
+                credentials,
+                topics: Default::default(),
+                home_assistant: None,
+            },
+            application: "app".into(),
+            device: "device".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_tcp() {
+        let options = options(&settings("mqtt://localhost", Credentials::None)).unwrap();
+        assert_eq!(("localhost".to_string(), 1883), options.broker_address());
+        assert!(matches!(options.transport(), Transport::Tcp));
+        assert_eq!(None, options.credentials());
+    }
+
+    #[test]
+    fn test_tls() {
+        let options = options(&settings(
+            "mqtts://mqtt.example.com:8443",
+            Credentials::Password("secret".into()),
+        ))
+        .unwrap();
+        assert_eq!(
+            ("mqtt.example.com".to_string(), 8443),
+            options.broker_address()
+        );
+        assert!(matches!(options.transport(), Transport::Tls(_)));
+        assert_eq!(
+            Some(("device@app".to_string(), "secret".to_string())),
+            options.credentials()
+        );
+    }
+
+    #[test]
+    fn test_websocket() {
+        let options = options(&settings("ws://localhost:8080/mqtt", Credentials::None)).unwrap();
+        assert_eq!(
+            ("ws://localhost:8080/mqtt".to_string(), 8080),
+            options.broker_address()
+        );
+        assert!(matches!(options.transport(), Transport::Ws));
+    }
+
+    #[test]
+    fn test_topics() {
+        let mut settings = settings("mqtt://localhost", Credentials::None);
+        let defaults = topics(&settings).unwrap();
+        assert_eq!(defaults.publish("state"), "state");
+        assert_eq!(defaults.command_filter(), "command/inbox//#");
+
+        if let Target::Mqtt { topics, .. } = &mut settings.target {
+            topics.command = "devices/{device}/commands".into();
+        }
+        assert!(topics(&settings).is_err());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(options(&settings("http://localhost", Credentials::None)).is_err());
+        assert!(options(&settings("localhost:1883", Credentials::None)).is_err());
+    }
+
+    /// Wait for the next value, failing after a few seconds.
+    async fn next<T>(rx: &mut UnboundedReceiver<T>) -> T {
+        tokio::time::timeout(Duration::from_secs(5), rx.next())
+            .await
+            .expect("timeout")
+            .expect("closed")
+    }
+
+    fn sender<T: 'static>(tx: &UnboundedSender<T>) -> Handler<T> {
+        let tx = tx.clone();
+        Handler::from(move |value| {
+            tx.unbounded_send(value).ok();
+        })
+    }
+
+    /// Run against a local broker, like mosquitto. Set `MQTT_URL` to use a different one.
+    #[tokio::test]
+    #[ignore]
+    async fn test_broker() {
+        let url = std::env::var("MQTT_URL").unwrap_or_else(|_| "mqtt://localhost:1883".into());
+        let mut settings = settings(&url, Credentials::None);
+        settings.device = uuid::Uuid::new_v4().to_string();
+        let topics = topics(&settings).unwrap();
+        let will = format!("{}/will", settings.device);
+
+        LocalSet::new()
+            .run_until(async {
+                // the other side, receiving events and sending commands
+                let (observer, mut observer_loop) = AsyncClient::new(options(&settings).unwrap(), 10);
+                let (received_tx, mut received) = mpsc::unbounded();
+                tokio::task::spawn_local(async move {
+                    while let Ok(event) = observer_loop.poll().await {
+                        received_tx.unbounded_send(event).ok();
+                    }
+                });
+                for topic in [topics.publish("state"), will.clone()] {
+                    observer.subscribe(topic, rumqttc::QoS::AtLeastOnce).await.unwrap();
+                    while !matches!(next(&mut received).await, Event::Incoming(Packet::SubAck(_))) {}
+                }
+
+                // the device, which must not leave a last will when dropped
+                let mut options = options(&settings).unwrap();
+                options.set_last_will(LastWill::new(&will, "gone", rumqttc::QoS::AtLeastOnce, false));
+                let (commands_tx, mut commands) = mpsc::unbounded();
+                let mut connector = MqttConnector::new(
+                    options,
+                    topics.clone(),
+                    sender(&commands_tx),
+                    Handler::noop(),
+                );
+
+                let (tx, mut rx) = mpsc::unbounded();
+                connector
+                    .connect(ConnectOptions {
+                        on_success: sender(&tx).reform(|_| Ok(())),
+                        on_failure: sender(&tx).reform(Err),
+                    })
+                    .unwrap();
+                next(&mut rx).await.expect("connect");
+                connector
+                    .subscribe(SubscribeOptions {
+                        on_success: sender(&tx).reform(|_| Ok(())),
+                        on_failure: sender(&tx).reform(Err),
+                    })
+                    .unwrap();
+                next(&mut rx).await.expect("subscribe");
+
+                connector.publish("state", b"{}".to_vec(), QoS::QoS0);
+                loop {
+                    if let Event::Incoming(Packet::Publish(publish)) = next(&mut received).await {
+                        assert_eq!(publish.topic, topics.publish("state"));
+                        assert_eq!(&publish.payload[..], b"{}");
+                        break;
+                    }
+                }
+
+                let command = topics.command("set-value").unwrap();
+                observer
+                    .publish(command, rumqttc::QoS::AtLeastOnce, false, "{}")
+                    .await
+                    .unwrap();
+                let command = next(&mut commands).await;
+                assert_eq!(command.name, "set-value");
+                assert_eq!(command.payload.as_deref(), Some(&b"{}"[..]));
+
+                // a clean disconnect discards the last will
+                drop(connector);
+                tokio::time::sleep(DISCONNECT_TIMEOUT * 2).await;
+                while let Ok(Some(event)) = received.try_next() {
+                    assert!(
+                        !matches!(&event, Event::Incoming(Packet::Publish(publish)) if publish.topic == will),
+                        "Last will was published"
+                    );
+                }
+            })
+            .await;
+    }
+}
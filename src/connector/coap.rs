@@ -0,0 +1,376 @@
+//! CoAP messages, framed for WebSockets (RFC 8323).
+//!
+//! Over WebSockets, messages don't have a type or message ID, and the length is implied by the
+//! WebSocket message. So the length nibble is always zero.
+
+use anyhow::{anyhow, bail};
+use std::fmt::{Display, Formatter};
+
+/// The subprotocol to request when connecting.
+pub const SUBPROTOCOL: &str = "coap";
+
+/// Marker between the options and the payload.
+const PAYLOAD_MARKER: u8 = 0xFF;
+
+/// Maximum length of a token.
+const MAX_TOKEN_LENGTH: usize = 8;
+
+/// A message code, consisting of a class (3 bits) and a detail (5 bits).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Code(pub u8);
+
+impl Code {
+    pub const GET: Code = Code::new(0, 1);
+    pub const POST: Code = Code::new(0, 2);
+
+    pub const CREATED: Code = Code::new(2, 1);
+    pub const CHANGED: Code = Code::new(2, 4);
+    pub const CONTENT: Code = Code::new(2, 5);
+    pub const BAD_REQUEST: Code = Code::new(4, 0);
+    pub const UNAUTHORIZED: Code = Code::new(4, 1);
+    pub const NOT_FOUND: Code = Code::new(4, 4);
+
+    /// Capabilities and Settings Message, which both sides must send first.
+    pub const CSM: Code = Code::new(7, 1);
+    pub const PING: Code = Code::new(7, 2);
+    pub const PONG: Code = Code::new(7, 3);
+    pub const RELEASE: Code = Code::new(7, 4);
+    pub const ABORT: Code = Code::new(7, 5);
+
+    pub const fn new(class: u8, detail: u8) -> Self {
+        Self((class << 5) | (detail & 0x1F))
+    }
+
+    pub fn class(&self) -> u8 {
+        self.0 >> 5
+    }
+
+    pub fn detail(&self) -> u8 {
+        self.0 & 0x1F
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.class() == 2
+    }
+
+    pub fn is_signaling(&self) -> bool {
+        self.class() == 7
+    }
+}
+
+impl Display for Code {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:02}", self.class(), self.detail())
+    }
+}
+
+/// Option numbers.
+pub mod option {
+    pub const OBSERVE: u16 = 6;
+    pub const URI_PATH: u16 = 11;
+    pub const CONTENT_FORMAT: u16 = 12;
+    pub const URI_QUERY: u16 = 15;
+    /// Carries the credentials, as done by Drogue Cloud.
+    pub const AUTHORIZATION: u16 = 4209;
+}
+
+/// Content formats.
+pub mod content_format {
+    pub const TEXT: u32 = 0;
+    pub const OCTET_STREAM: u32 = 42;
+    pub const JSON: u32 = 50;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Message {
+    pub code: Code,
+    pub token: Vec<u8>,
+    /// Options, by number. Options with the same number keep their order.
+    pub options: Vec<(u16, Vec<u8>)>,
+    pub payload: Vec<u8>,
+}
+
+impl Message {
+    pub fn new(code: Code, token: Vec<u8>) -> Self {
+        Self {
+            code,
+            token,
+            options: vec![],
+            payload: vec![],
+        }
+    }
+
+    pub fn add_option(&mut self, number: u16, value: impl Into<Vec<u8>>) {
+        self.options.push((number, value.into()));
+    }
+
+    pub fn add_uint_option(&mut self, number: u16, value: u32) {
+        self.add_option(number, encode_uint(value));
+    }
+
+    pub fn with_option(mut self, number: u16, value: impl Into<Vec<u8>>) -> Self {
+        self.add_option(number, value);
+        self
+    }
+
+    pub fn with_uint_option(mut self, number: u16, value: u32) -> Self {
+        self.add_uint_option(number, value);
+        self
+    }
+
+    /// Add one Uri-Path option per segment.
+    pub fn with_path(mut self, path: &str) -> Self {
+        for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+            self.add_option(option::URI_PATH, segment);
+        }
+        self
+    }
+
+    pub fn with_payload(mut self, payload: impl Into<Vec<u8>>) -> Self {
+        self.payload = payload.into();
+        self
+    }
+
+    /// All values of an option.
+    pub fn option_values(&self, number: u16) -> impl Iterator<Item = &[u8]> {
+        self.options
+            .iter()
+            .filter(move |(n, _)| *n == number)
+            .map(|(_, value)| value.as_slice())
+    }
+
+    /// The first value of an option, as unsigned integer.
+    pub fn uint_option(&self, number: u16) -> Option<u32> {
+        self.option_values(number).next().map(decode_uint)
+    }
+
+    /// The path, from the Uri-Path options.
+    pub fn path(&self) -> String {
+        self.option_values(option::URI_PATH)
+            .map(String::from_utf8_lossy)
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Encode for sending as a WebSocket message.
+    pub fn encode(&self) -> anyhow::Result<Vec<u8>> {
+        if self.token.len() > MAX_TOKEN_LENGTH {
+            bail!("Token too long: {} bytes", self.token.len());
+        }
+
+        let mut data = Vec::with_capacity(2 + self.token.len() + self.payload.len());
+        data.push(self.token.len() as u8);
+        data.push(self.code.0);
+        data.extend_from_slice(&self.token);
+
+        let mut options: Vec<_> = self.options.iter().collect();
+        // stable, so that repeated options keep their order
+        options.sort_by_key(|(number, _)| *number);
+
+        let mut last = 0;
+        for (number, value) in options {
+            if value.len() > u16::MAX as usize + 269 {
+                bail!("Option {number} too long: {} bytes", value.len());
+            }
+            let (delta, delta_ext) = encode_option_nibble(u32::from(number - last));
+            let (length, length_ext) = encode_option_nibble(value.len() as u32);
+            data.push((delta << 4) | length);
+            data.extend_from_slice(&delta_ext);
+            data.extend_from_slice(&length_ext);
+            data.extend_from_slice(value);
+            last = *number;
+        }
+
+        if !self.payload.is_empty() {
+            data.push(PAYLOAD_MARKER);
+            data.extend_from_slice(&self.payload);
+        }
+
+        Ok(data)
+    }
+
+    /// Decode a received WebSocket message.
+    pub fn decode(data: &[u8]) -> anyhow::Result<Self> {
+        let mut reader = Reader(data);
+
+        let first = reader.byte()?;
+        if first >> 4 != 0 {
+            bail!("Length must be zero for WebSockets, was: {}", first >> 4);
+        }
+        let token_length = (first & 0x0F) as usize;
+        if token_length > MAX_TOKEN_LENGTH {
+            bail!("Invalid token length: {token_length}");
+        }
+
+        let code = Code(reader.byte()?);
+        let token = reader.bytes(token_length)?.to_vec();
+
+        let mut options = vec![];
+        let mut number = 0u32;
+        let mut payload = vec![];
+
+        while let Ok(header) = reader.byte() {
+            if header == PAYLOAD_MARKER {
+                if reader.0.is_empty() {
+                    bail!("Empty payload after payload marker");
+                }
+                payload = reader.0.to_vec();
+                break;
+            }
+
+            number += decode_option_nibble(header >> 4, &mut reader)?;
+            let length = decode_option_nibble(header & 0x0F, &mut reader)? as usize;
+            let number =
+                u16::try_from(number).map_err(|_| anyhow!("Invalid option number: {number}"))?;
+            options.push((number, reader.bytes(length)?.to_vec()));
+        }
+
+        Ok(Self {
+            code,
+            token,
+            options,
+            payload,
+        })
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> anyhow::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn bytes(&mut self, n: usize) -> anyhow::Result<&'a [u8]> {
+        if self.0.len() < n {
+            bail!("Message too short");
+        }
+        let (result, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(result)
+    }
+}
+
+/// Encode an option delta or length, into the nibble and its extended bytes.
+fn encode_option_nibble(value: u32) -> (u8, Vec<u8>) {
+    match value {
+        0..=12 => (value as u8, vec![]),
+        13..=268 => (13, vec![(value - 13) as u8]),
+        _ => (14, ((value - 269) as u16).to_be_bytes().to_vec()),
+    }
+}
+
+fn decode_option_nibble(nibble: u8, reader: &mut Reader) -> anyhow::Result<u32> {
+    Ok(match nibble {
+        0..=12 => nibble as u32,
+        13 => reader.byte()? as u32 + 13,
+        14 => {
+            let ext = reader.bytes(2)?;
+            u16::from_be_bytes([ext[0], ext[1]]) as u32 + 269
+        }
+        _ => bail!("Invalid option nibble: {nibble}"),
+    })
+}
+
+/// Encode an unsigned integer option value, using as few bytes as possible.
+pub fn encode_uint(value: u32) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|b| **b == 0).count();
+    bytes[skip..].to_vec()
+}
+
+pub fn decode_uint(value: &[u8]) -> u32 {
+    value
+        .iter()
+        .take(4)
+        .fold(0, |result, b| (result << 8) | *b as u32)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_code() {
+        assert_eq!(Code::CONTENT.0, 69);
+        assert_eq!(Code::CSM.0, 225);
+        assert_eq!(Code::CONTENT.to_string(), "2.05");
+        assert_eq!(Code::NOT_FOUND.to_string(), "4.04");
+        assert!(Code::CHANGED.is_success());
+        assert!(!Code::BAD_REQUEST.is_success());
+        assert!(Code::PING.is_signaling());
+    }
+
+    #[test]
+    fn test_uint() {
+        assert_eq!(encode_uint(0), Vec::<u8>::new());
+        assert_eq!(encode_uint(50), vec![50]);
+        assert_eq!(encode_uint(0x1234), vec![0x12, 0x34]);
+        assert_eq!(decode_uint(&[]), 0);
+        assert_eq!(decode_uint(&[0x12, 0x34]), 0x1234);
+    }
+
+    #[test]
+    fn test_encode() {
+        let message = Message::new(Code::POST, vec![0x01])
+            .with_path("/v1/state")
+            .with_uint_option(option::CONTENT_FORMAT, content_format::JSON)
+            .with_payload(b"{}".to_vec());
+
+        assert_eq!(
+            message.encode().unwrap(),
+            vec![
+                0x01, // length 0, token length 1
+                0x02, // POST
+                0x01, // token
+                0xB2, b'v', b'1', // Uri-Path (11)
+                0x05, b's', b't', b'a', b't', b'e', // Uri-Path (11 + 0)
+                0x11, 50, // Content-Format (11 + 1)
+                0xFF, b'{', b'}', // payload
+            ]
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let message = Message::new(Code::GET, vec![1, 2, 3, 4])
+            .with_uint_option(option::OBSERVE, 0)
+            .with_path("v1/commands")
+            .with_option(option::AUTHORIZATION, "Basic Zm9vOmJhcg==")
+            .with_option(option::URI_QUERY, vec![b'x'; 300]);
+
+        let decoded = Message::decode(&message.encode().unwrap()).unwrap();
+
+        // options are ordered by number when encoding
+        assert_eq!(decoded.code, Code::GET);
+        assert_eq!(decoded.token, vec![1, 2, 3, 4]);
+        assert_eq!(decoded.path(), "v1/commands");
+        assert_eq!(decoded.uint_option(option::OBSERVE), Some(0));
+        assert_eq!(
+            decoded.option_values(option::AUTHORIZATION).next(),
+            Some(b"Basic Zm9vOmJhcg==".as_slice())
+        );
+        assert_eq!(
+            decoded
+                .option_values(option::URI_QUERY)
+                .next()
+                .unwrap()
+                .len(),
+            300
+        );
+        assert!(decoded.payload.is_empty());
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(Message::decode(&[]).is_err());
+        // length nibble must be zero
+        assert!(Message::decode(&[0x10, 0x45]).is_err());
+        // token missing
+        assert!(Message::decode(&[0x02, 0x45, 0x01]).is_err());
+        // payload marker without payload
+        assert!(Message::decode(&[0x00, 0x45, 0xFF]).is_err());
+        // option value missing
+        assert!(Message::decode(&[0x00, 0x45, 0xB2, b'v']).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+//! An in-browser loopback bus, connecting the simulated device with a "cloud side".
+//!
+//! This allows demonstrating the full loop between device and cloud, without any service: the
+//! cloud side receives everything the device publishes, and can send commands back to it.
+
+use crate::utils::handler::Handler;
+use chrono::{DateTime, Utc};
+use std::{cell::RefCell, collections::BTreeMap};
+
+/// A message published by the device.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Message {
+    pub timestamp: DateTime<Utc>,
+    pub channel: String,
+    pub payload: Vec<u8>,
+}
+
+/// Messages received on a channel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChannelSummary {
+    pub received: usize,
+    pub last: Message,
+}
+
+/// Handles commands, by name and payload.
+type CommandHandler = Handler<(String, Vec<u8>)>;
+
+#[derive(Default)]
+struct Bus {
+    next_id: usize,
+    /// The command handler of the connected device.
+    device: Option<(usize, CommandHandler)>,
+    /// The subscribers of the cloud side.
+    subscribers: BTreeMap<usize, Handler<Message>>,
+    channels: BTreeMap<String, ChannelSummary>,
+}
+
+impl Bus {
+    fn next_id(&mut self) -> usize {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+thread_local! {
+    static BUS: RefCell<Bus> = RefCell::new(Bus::default());
+}
+
+/// The connection of the device to the bus, disconnecting when dropped.
+pub struct DeviceConnection {
+    id: usize,
+}
+
+/// Connect the device to the bus, replacing a previously connected device.
+pub fn connect_device(on_command: CommandHandler) -> DeviceConnection {
+    BUS.with(|bus| {
+        let mut bus = bus.borrow_mut();
+        let id = bus.next_id();
+        bus.device = Some((id, on_command));
+        DeviceConnection { id }
+    })
+}
+
+impl DeviceConnection {
+    /// Publish a message to the cloud side.
+    pub fn publish(&self, channel: &str, payload: Vec<u8>) {
+        let message = Message {
+            timestamp: Utc::now(),
+            channel: channel.to_string(),
+            payload,
+        };
+
+        let subscribers: Vec<_> = BUS.with(|bus| {
+            let mut bus = bus.borrow_mut();
+            match bus.channels.get_mut(channel) {
+                Some(summary) => {
+                    summary.received += 1;
+                    summary.last = message.clone();
+                }
+                None => {
+                    bus.channels.insert(
+                        channel.to_string(),
+                        ChannelSummary {
+                            received: 1,
+                            last: message.clone(),
+                        },
+                    );
+                }
+            }
+            bus.subscribers.values().cloned().collect()
+        });
+
+        // emit outside the borrow, as subscribers may access the bus
+        for subscriber in subscribers {
+            subscriber.emit(message.clone());
+        }
+    }
+}
+
+impl Drop for DeviceConnection {
+    fn drop(&mut self) {
+        BUS.with(|bus| {
+            let mut bus = bus.borrow_mut();
+            if matches!(bus.device, Some((id, _)) if id == self.id) {
+                bus.device = None;
+            }
+        });
+    }
+}
+
+/// A subscription of the cloud side to the messages of the device, unsubscribing when dropped.
+pub struct CloudSubscription {
+    id: usize,
+}
+
+/// Subscribe to all messages the device publishes from now on.
+pub fn subscribe(on_message: Handler<Message>) -> CloudSubscription {
+    BUS.with(|bus| {
+        let mut bus = bus.borrow_mut();
+        let id = bus.next_id();
+        bus.subscribers.insert(id, on_message);
+        CloudSubscription { id }
+    })
+}
+
+impl Drop for CloudSubscription {
+    fn drop(&mut self) {
+        BUS.with(|bus| {
+            bus.borrow_mut().subscribers.remove(&self.id);
+        });
+    }
+}
+
+/// The messages received so far, per channel.
+pub fn channels() -> BTreeMap<String, ChannelSummary> {
+    BUS.with(|bus| bus.borrow().channels.clone())
+}
+
+/// Forget the messages received so far.
+pub fn clear() {
+    BUS.with(|bus| bus.borrow_mut().channels.clear());
+}
+
+pub fn is_device_connected() -> bool {
+    BUS.with(|bus| bus.borrow().device.is_some())
+}
+
+/// Send a command to the device, returns `false` if no device is connected.
+pub fn send_command(name: &str, payload: Vec<u8>) -> bool {
+    let device = BUS.with(|bus| {
+        bus.borrow()
+            .device
+            .as_ref()
+            .map(|(_, device)| device.clone())
+    });
+    match device {
+        Some(device) => {
+            device.emit((name.to_string(), payload));
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_loop() {
+        let received = Rc::new(RefCell::new(vec![]));
+        let commands = Rc::new(RefCell::new(vec![]));
+
+        let subscription = subscribe({
+            let received = received.clone();
+            Handler::from(move |message: Message| received.borrow_mut().push(message.channel))
+        });
+
+        assert!(!send_command("set", vec![]));
+
+        let device = connect_device({
+            let commands = commands.clone();
+            Handler::from(move |command| commands.borrow_mut().push(command))
+        });
+        assert!(is_device_connected());
+
+        device.publish("state", b"1".to_vec());
+        device.publish("state", b"2".to_vec());
+        device.publish("alarm", b"3".to_vec());
+
+        assert_eq!(*received.borrow(), vec!["state", "state", "alarm"]);
+        let summary = channels();
+        assert_eq!(summary["state"].received, 2);
+        assert_eq!(summary["state"].last.payload, b"2");
+        assert_eq!(summary["alarm"].received, 1);
+
+        assert!(send_command("set", b"on".to_vec()));
+        assert_eq!(*commands.borrow(), vec![("set".into(), b"on".to_vec())]);
+
+        // no more updates after unsubscribing
+        drop(subscription);
+        device.publish("state", b"4".to_vec());
+        assert_eq!(received.borrow().len(), 3);
+        assert_eq!(channels()["state"].received, 3);
+
+        drop(device);
+        assert!(!is_device_connected());
+        assert!(!send_command("set", vec![]));
+
+        clear();
+        assert!(channels().is_empty());
+    }
+
+    #[test]
+    fn test_replace_device() {
+        let first = connect_device(Handler::noop());
+        let second = connect_device(Handler::noop());
+
+        // dropping the replaced device must not disconnect the current one
+        drop(first);
+        assert!(is_device_connected());
+        drop(second);
+        assert!(!is_device_connected());
+    }
+}
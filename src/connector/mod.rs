@@ -1 +1,5 @@
+pub mod coap;
+pub mod local;
 pub mod mqtt;
+#[cfg(target_arch = "wasm32")]
+pub mod socket;
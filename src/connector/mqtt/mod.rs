@@ -0,0 +1,24 @@
+//! MQTT, using the Paho JavaScript client in the browser.
+
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+
+#[cfg(target_arch = "wasm32")]
+pub use wasm::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QoS {
+    QoS0,
+    QoS1,
+    QoS2,
+}
+
+impl From<QoS> for i32 {
+    fn from(qos: QoS) -> Self {
+        match qos {
+            QoS::QoS0 => 0,
+            QoS::QoS1 => 1,
+            QoS::QoS2 => 2,
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use super::QoS;
+use crate::utils::handler::Handler;
 use anyhow::{anyhow, Context};
 use gloo_utils::format::JsValueSerdeExt;
 use serde::{Deserialize, Serialize};
@@ -5,7 +7,6 @@ use serde_json::Value;
 use std::time::Duration;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use yew::Callback;
 
 #[wasm_bindgen(module = "/js/paho/wrapper.js")]
 extern "C" {
@@ -51,22 +52,6 @@ extern "C" {
     fn payload_bytes(this: &Message) -> Vec<u8>;
 }
 
-pub enum QoS {
-    QoS0,
-    QoS1,
-    QoS2,
-}
-
-impl From<QoS> for i32 {
-    fn from(qos: QoS) -> Self {
-        match qos {
-            QoS::QoS0 => 0,
-            QoS::QoS1 => 1,
-            QoS::QoS2 => 2,
-        }
-    }
-}
-
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct MqttMessage {
     pub topic: String,
@@ -145,8 +130,8 @@ impl MqttClient {
     pub fn connect(
         &mut self,
         options: MqttConnectOptions,
-        on_success: Callback<()>,
-        on_failure: Callback<String>,
+        on_success: Handler<()>,
+        on_failure: Handler<String>,
     ) -> anyhow::Result<()> {
         self.inner.connect(options, on_success, on_failure)
     }
@@ -161,8 +146,8 @@ impl MqttClient {
         filter: S,
         qos: QoS,
         timeout: Duration,
-        on_success: Callback<()>,
-        on_failure: Callback<String>,
+        on_success: Handler<()>,
+        on_failure: Handler<String>,
     ) -> anyhow::Result<()>
     where
         S: AsRef<str>,
@@ -179,11 +164,11 @@ impl MqttClient {
         self.inner.publish(topic, payload, qos, retain)
     }
 
-    pub fn set_on_connection_lost(&mut self, callback: Callback<String>) {
+    pub fn set_on_connection_lost(&mut self, callback: Handler<String>) {
         self.inner.set_on_connection_lost(callback)
     }
 
-    pub fn set_on_message_arrived(&mut self, callback: Callback<MqttMessage>) {
+    pub fn set_on_message_arrived(&mut self, callback: Handler<MqttMessage>) {
         self.inner.set_on_message_arrived(callback)
     }
 }
@@ -206,8 +191,8 @@ impl Inner {
     fn connect(
         &mut self,
         options: MqttConnectOptions,
-        on_success: Callback<()>,
-        on_failure: Callback<String>,
+        on_success: Handler<()>,
+        on_failure: Handler<String>,
     ) -> anyhow::Result<()> {
         let MqttConnectOptions {
             username,
@@ -273,8 +258,8 @@ impl Inner {
         filter: S,
         qos: QoS,
         timeout: Duration,
-        on_success: Callback<()>,
-        on_failure: Callback<String>,
+        on_success: Handler<()>,
+        on_failure: Handler<String>,
     ) -> anyhow::Result<()>
     where
         S: AsRef<str>,
@@ -329,7 +314,7 @@ impl Inner {
             .map_err(str_err)
     }
 
-    pub fn set_on_message_arrived(&mut self, callback: Callback<MqttMessage>) {
+    pub fn set_on_message_arrived(&mut self, callback: Handler<MqttMessage>) {
         let on_message_arrived = Closure::wrap(Box::new(move |msg| match convert_message(msg) {
             Ok(msg) => callback.emit(msg),
             Err(err) => {
@@ -341,7 +326,7 @@ impl Inner {
         self._on_message_arrived = Some(on_message_arrived);
     }
 
-    pub fn set_on_connection_lost(&mut self, callback: Callback<String>) {
+    pub fn set_on_connection_lost(&mut self, callback: Handler<String>) {
         let on_connection_lost =
             Closure::wrap(
                 Box::new(move |err| callback.emit(convert_error(err))) as Box<dyn Fn(JsValue)>
@@ -0,0 +1,71 @@
+//! A browser WebSocket, shared by the connectors using one.
+
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{CloseEvent, MessageEvent, WebSocket};
+
+/// A WebSocket, keeping its event handlers alive.
+///
+/// Dropping it unregisters the handlers, and closes the socket.
+pub struct Socket {
+    socket: WebSocket,
+    _on_open: Closure<dyn Fn()>,
+    _on_message: Closure<dyn Fn(MessageEvent)>,
+    _on_close: Closure<dyn Fn(CloseEvent)>,
+}
+
+impl Socket {
+    pub fn new<O, M, C>(socket: WebSocket, on_open: O, on_message: M, on_close: C) -> Self
+    where
+        O: Fn() + 'static,
+        M: Fn(MessageEvent) + 'static,
+        C: Fn(CloseEvent) + 'static,
+    {
+        let on_open = Closure::wrap(Box::new(on_open) as Box<dyn Fn()>);
+        let on_message = Closure::wrap(Box::new(on_message) as Box<dyn Fn(MessageEvent)>);
+        let on_close = Closure::wrap(Box::new(on_close) as Box<dyn Fn(CloseEvent)>);
+
+        socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+
+        Self {
+            socket,
+            _on_open: on_open,
+            _on_message: on_message,
+            _on_close: on_close,
+        }
+    }
+
+    /// The socket, if it is open.
+    pub fn open(&self) -> Option<&WebSocket> {
+        match self.socket.ready_state() {
+            WebSocket::OPEN => Some(&self.socket),
+            _ => None,
+        }
+    }
+
+    pub fn socket(&self) -> &WebSocket {
+        &self.socket
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        self.socket.set_onopen(None);
+        self.socket.set_onmessage(None);
+        self.socket.set_onclose(None);
+        let _ = self.socket.close();
+    }
+}
+
+/// A human readable reason of a close event.
+pub fn close_reason(event: &CloseEvent) -> String {
+    match event.reason() {
+        reason if reason.is_empty() => format!("Closed ({})", event.code()),
+        reason => format!("Closed ({}): {}", event.code(), reason),
+    }
+}
+
+pub fn str_err(err: JsValue) -> anyhow::Error {
+    anyhow::anyhow!("{:?}", err)
+}
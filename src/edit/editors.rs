@@ -1,9 +1,13 @@
 use super::*;
-use crate::simulator::simulations::led_matrix;
+use crate::simulator::simulations::{
+    actuator, battery, binary, control_panel, counter, event, formula, led_matrix, location,
+    thermostat,
+};
 use crate::{
     edit::Setter,
     settings::Simulation,
     simulator::simulations::{accelerometer, sawtooth, sine, slider, wave},
+    utils::random::Distribution,
 };
 use humantime_serde::Serde;
 use patternfly_yew::*;
@@ -58,6 +62,83 @@ where
             }),
             props,
         ),
+        Simulation::Location(props) => render_location_editor(
+            &setter.map_or(|state| match state {
+                Simulation::Location(props) => Some(props.as_mut()),
+                _ => None,
+            }),
+            props,
+        ),
+        Simulation::Battery(props) => render_battery_editor(
+            &setter.map_or(|state| match state {
+                Simulation::Battery(props) => Some(props.as_mut()),
+                _ => None,
+            }),
+            props,
+        ),
+        Simulation::Thermostat(props) => render_thermostat_editor(
+            &setter.map_or(|state| match state {
+                Simulation::Thermostat(props) => Some(props.as_mut()),
+                _ => None,
+            }),
+            props,
+        ),
+        Simulation::Switch(props) => render_switch_editor(
+            &setter.map_or(|state| match state {
+                Simulation::Switch(props) => Some(props.as_mut()),
+                _ => None,
+            }),
+            props,
+        ),
+        Simulation::Dimmer(props) => render_dimmer_editor(
+            &setter.map_or(|state| match state {
+                Simulation::Dimmer(props) => Some(props.as_mut()),
+                _ => None,
+            }),
+            props,
+        ),
+        Simulation::ModeSelector(props) => render_mode_selector_editor(
+            &setter.map_or(|state| match state {
+                Simulation::ModeSelector(props) => Some(props.as_mut()),
+                _ => None,
+            }),
+            props,
+        ),
+        Simulation::Counter(props) => render_counter_editor(
+            &setter.map_or(|state| match state {
+                Simulation::Counter(props) => Some(props.as_mut()),
+                _ => None,
+            }),
+            props,
+        ),
+        Simulation::Event(props) => render_event_editor(
+            &setter.map_or(|state| match state {
+                Simulation::Event(props) => Some(props.as_mut()),
+                _ => None,
+            }),
+            props,
+        ),
+        Simulation::Formula(props) => render_formula_editor(
+            &setter.map_or(|state| match state {
+                Simulation::Formula(props) => Some(props.as_mut()),
+                _ => None,
+            }),
+            props,
+        ),
+        Simulation::ControlPanel(props) => render_control_panel_editor(
+            &setter.map_or(|state| match state {
+                Simulation::ControlPanel(props) => Some(props.as_mut()),
+                _ => None,
+            }),
+            props,
+        ),
+        Simulation::Binary(props) => render_binary_editor(
+            &setter.map_or(|state| match state {
+                Simulation::Binary(props) => Some(props.as_mut()),
+                _ => None,
+            }),
+            props,
+        ),
     }
 }
 
@@ -120,11 +201,34 @@ where
     html!(<>
         <FormSection title="Parameters">
             { setter_field(setter, "Delay", humantime::Duration::from(props.delay), | state, v| state.delay = v.into() )}
+            { render_accelerometer_source(&setter.map(|props|&mut props.source), &props.source) }
         </FormSection>
         { edit_feature_target(&setter.map(|props|&mut props.target), &props.target) }
     </>)
 }
 
+fn render_accelerometer_source<S>(setter: &S, source: &accelerometer::Source) -> Html
+where
+    S: Setter<accelerometer::Source>,
+{
+    use accelerometer::Source;
+
+    match source {
+        Source::Browser | Source::Idle | Source::Vehicle => html!(),
+        Source::Walking { cadence } => html!({
+            setter_field(setter, "Cadence (steps/min)", cadence.0, |state, v| {
+                if let Source::Walking { cadence } = state {
+                    *cadence = v.into()
+                }
+            })
+        }),
+        Source::Shock { rate, magnitude } => html!(<>
+            { setter_field(setter, "Rate (per minute)", rate.0, |state, v| if let Source::Shock { rate, .. } = state { *rate = v.into() } ) }
+            { setter_field(setter, "Magnitude (g)", magnitude.0, |state, v| if let Source::Shock { magnitude, .. } = state { *magnitude = v.into() } ) }
+        </>),
+    }
+}
+
 pub fn render_slider_editor<S>(setter: &S, props: &slider::Properties) -> Html
 where
     S: Setter<slider::Properties>,
@@ -149,7 +253,297 @@ where
         <FormSection title="Parameters">
             { setter_field(setter, "Color (On)", props.color.clone(), | state, v| state.color = v.into() ) }
             { setter_field(setter, "Color (Off)", props.color_off.clone(), | state, v| state.color_off = v.into() ) }
+            { setter_field(setter, "Rows", props.rows, | state, v| state.rows = v ) }
+            { setter_field(setter, "Columns", props.columns, | state, v| state.columns = v ) }
         </FormSection>
         { edit_single_target(&setter.map(|props|&mut props.target), &props.target) }
     </>)
 }
+
+pub fn render_location_editor<S>(setter: &S, props: &location::Properties) -> Html
+where
+    S: Setter<location::Properties>,
+{
+    let source = match &props.source {
+        location::Source::Waypoints { speed, .. } | location::Source::GeoJson { speed, .. } => {
+            html!({
+                setter_field(
+                    setter,
+                    "Speed (m/s)",
+                    speed.0,
+                    |state, v| match &mut state.source {
+                        location::Source::Waypoints { speed, .. }
+                        | location::Source::GeoJson { speed, .. } => *speed = v.into(),
+                        _ => {}
+                    },
+                )
+            })
+        }
+        location::Source::Drift {
+            center,
+            radius,
+            speed,
+        } => html!(<>
+            { setter_field(setter, "Latitude", center.lat.0, |state, v| if let location::Source::Drift { center, .. } = &mut state.source { center.lat = v.into() } ) }
+            { setter_field(setter, "Longitude", center.lon.0, |state, v| if let location::Source::Drift { center, .. } = &mut state.source { center.lon = v.into() } ) }
+            { setter_field(setter, "Radius (m)", radius.0, |state, v| if let location::Source::Drift { radius, .. } = &mut state.source { *radius = v.into() } ) }
+            { setter_field(setter, "Speed (m/s)", speed.0, |state, v| if let location::Source::Drift { speed, .. } = &mut state.source { *speed = v.into() } ) }
+        </>),
+        location::Source::Browser => html!(),
+    };
+
+    html!(<>
+        <FormSection title="Parameters">
+            { setter_field(setter, "Period", humantime::Duration::from(props.period), | state, v| state.period = v.into() )}
+            { source }
+        </FormSection>
+        { edit_feature_target(&setter.map(|props|&mut props.target), &props.target) }
+    </>)
+}
+
+pub fn render_battery_editor<S>(setter: &S, props: &battery::Properties) -> Html
+where
+    S: Setter<battery::Properties>,
+{
+    html!(<>
+        <FormSection title="Parameters">
+            { setter_field(setter, "Period", humantime::Duration::from(props.period), | state, v| state.period = v.into() )}
+            { setter_field(setter, "Capacity (mAh)", props.capacity.0, | state, v| state.capacity = v.into() )}
+            { setter_field(setter, "Initial level (%)", props.initial_level.0, | state, v| state.initial_level = v.into() )}
+            { setter_field(setter, "Idle current (mA)", props.idle_current.0, | state, v| state.idle_current = v.into() )}
+            { setter_field(setter, "Charge per message (mAh)", props.publish_charge.0, | state, v| state.publish_charge = v.into() )}
+            { setter_field(setter, "Charge current (mA)", props.charge_current.0, | state, v| state.charge_current = v.into() )}
+            { setter_field(setter, "Acceleration", props.acceleration.0, | state, v| state.acceleration = v.into() )}
+        </FormSection>
+        { edit_feature_target(&setter.map(|props|&mut props.target), &props.target) }
+    </>)
+}
+
+pub fn render_thermostat_editor<S>(setter: &S, props: &thermostat::Properties) -> Html
+where
+    S: Setter<thermostat::Properties>,
+{
+    html!(<>
+        <FormSection title="Parameters">
+            { setter_field(setter, "Period", humantime::Duration::from(props.period), | state, v| state.period = v.into() )}
+            { setter_field(setter, "Ambient (°C)", props.ambient.0, | state, v| state.ambient = v.into() )}
+            { setter_field(setter, "Setpoint (°C)", props.setpoint.0, | state, v| state.setpoint = v.into() )}
+            { setter_field(setter, "Heating rate (°C/min)", props.heating_rate.0, | state, v| state.heating_rate = v.into() )}
+            { setter_field(setter, "Cooling rate (°C/min)", props.cooling_rate.0, | state, v| state.cooling_rate = v.into() )}
+            { setter_field(setter, "Loss (1/min)", props.loss.0, | state, v| state.loss = v.into() )}
+            { setter_field(setter, "Hysteresis (°C)", props.hysteresis.0, | state, v| state.hysteresis = v.into() )}
+            { setter_field(setter, "Acceleration", props.acceleration.0, | state, v| state.acceleration = v.into() )}
+        </FormSection>
+        { edit_feature_target(&setter.map(|props|&mut props.target), &props.target) }
+    </>)
+}
+
+fn render_behavior<S>(setter: &S, behavior: &actuator::Behavior) -> Html
+where
+    S: Setter<actuator::Behavior>,
+{
+    html!(<>
+        { setter_field(setter, "Latency", humantime::Duration::from(behavior.latency), | state, v| state.latency = v.into() )}
+        { setter_field(setter, "Failure probability", behavior.failure_probability.0, | state, v| state.failure_probability = v.into() )}
+    </>)
+}
+
+pub fn render_switch_editor<S>(setter: &S, props: &actuator::SwitchProperties) -> Html
+where
+    S: Setter<actuator::SwitchProperties>,
+{
+    html!(<>
+        <FormSection title="Parameters">
+            <FormGroup label="Initial state">
+                <Switch
+                    checked={props.initial}
+                    on_change={setter.setter(|state: &mut actuator::SwitchProperties, v| state.initial = v)}
+                    />
+            </FormGroup>
+            { render_behavior(&setter.map(|props|&mut props.behavior), &props.behavior) }
+        </FormSection>
+        { edit_single_target(&setter.map(|props|&mut props.target), &props.target) }
+    </>)
+}
+
+pub fn render_dimmer_editor<S>(setter: &S, props: &actuator::DimmerProperties) -> Html
+where
+    S: Setter<actuator::DimmerProperties>,
+{
+    html!(<>
+        <FormSection title="Parameters">
+            { setter_field(setter, "Minimum", props.min.0, | state, v| state.min = v.into() )}
+            { setter_field(setter, "Maximum", props.max.0, | state, v| state.max = v.into() )}
+            { setter_field(setter, "Initial value", props.initial.0, | state, v| state.initial = v.into() )}
+            { setter_field(setter, "Ramp time", humantime::Duration::from(props.ramp), | state, v| state.ramp = v.into() )}
+            { render_behavior(&setter.map(|props|&mut props.behavior), &props.behavior) }
+        </FormSection>
+        { edit_single_target(&setter.map(|props|&mut props.target), &props.target) }
+    </>)
+}
+
+pub fn render_mode_selector_editor<S>(setter: &S, props: &actuator::ModeSelectorProperties) -> Html
+where
+    S: Setter<actuator::ModeSelectorProperties>,
+{
+    html!(<>
+        <FormSection title="Parameters">
+            { setter_field(setter, "Modes", props.modes.clone(), | state, v| state.modes = v )}
+            { setter_field(setter, "Initial mode", props.initial.clone(), | state, v| state.initial = v )}
+            { render_behavior(&setter.map(|props|&mut props.behavior), &props.behavior) }
+        </FormSection>
+        { edit_single_target(&setter.map(|props|&mut props.target), &props.target) }
+    </>)
+}
+
+fn render_distribution<S>(setter: &S, label: &str, distribution: &Distribution) -> Html
+where
+    S: Setter<Distribution>,
+{
+    match distribution {
+        Distribution::Constant { value } => html!({
+            setter_field(setter, label, value.0, |state, v| {
+                if let Distribution::Constant { value } = state {
+                    *value = v.into()
+                }
+            })
+        }),
+        Distribution::Uniform { min, max } => html!(<>
+            { setter_field(setter, &format!("{label} (min)"), min.0, |state, v| if let Distribution::Uniform { min, .. } = state { *min = v.into() } ) }
+            { setter_field(setter, &format!("{label} (max)"), max.0, |state, v| if let Distribution::Uniform { max, .. } = state { *max = v.into() } ) }
+        </>),
+        Distribution::Normal { mean, std_dev } => html!(<>
+            { setter_field(setter, &format!("{label} (mean)"), mean.0, |state, v| if let Distribution::Normal { mean, .. } = state { *mean = v.into() } ) }
+            { setter_field(setter, &format!("{label} (std. deviation)"), std_dev.0, |state, v| if let Distribution::Normal { std_dev, .. } = state { *std_dev = v.into() } ) }
+        </>),
+        Distribution::Exponential { mean } | Distribution::Poisson { mean } => html!({
+            setter_field(
+                setter,
+                &format!("{label} (mean)"),
+                mean.0,
+                |state, v| match state {
+                    Distribution::Exponential { mean } | Distribution::Poisson { mean } => {
+                        *mean = v.into()
+                    }
+                    _ => {}
+                },
+            )
+        }),
+    }
+}
+
+pub fn render_counter_editor<S>(setter: &S, props: &counter::Properties) -> Html
+where
+    S: Setter<counter::Properties>,
+{
+    html!(<>
+        <FormSection title="Parameters">
+            { setter_field(setter, "Period", humantime::Duration::from(props.period), | state, v| state.period = v.into() )}
+            { setter_field(setter, "Initial value", props.initial.0, | state, v| state.initial = v.into() )}
+            { render_distribution(&setter.map(|props|&mut props.increment), "Increment", &props.increment) }
+            { setter_field(setter, "Rollover", props.rollover, | state, v| state.rollover = v )}
+        </FormSection>
+        { edit_single_target(&setter.map(|props|&mut props.target), &props.target) }
+    </>)
+}
+
+pub fn render_event_editor<S>(setter: &S, props: &event::Properties) -> Html
+where
+    S: Setter<event::Properties>,
+{
+    html!(<>
+        <FormSection title="Parameters">
+            { setter_field(setter, "Period", humantime::Duration::from(props.period), | state, v| state.period = v.into() )}
+            { setter_field(setter, "Rate (1/min)", props.rate.0, | state, v| state.rate = v.into() )}
+            { setter_field(setter, "Event", props.event.clone(), | state, v| state.event = v )}
+        </FormSection>
+        <FormSection title="Target">
+            { setter_field(setter, "Channel", props.channel.clone(), | state, v| state.channel = v )}
+        </FormSection>
+    </>)
+}
+
+pub fn render_formula_editor<S>(setter: &S, props: &formula::Properties) -> Html
+where
+    S: Setter<formula::Properties>,
+{
+    html!(<>
+        <FormSection title="Parameters">
+            { setter_field(setter, "Expression", props.expression.clone(), | state, v| state.expression = v )}
+            <FormGroup label="Inputs">
+                <ul>
+                    { for props.inputs.iter().map(|(name, input)| html!(
+                        <li><code>{ name }</code>{ format!(" ← {}/{}/{}", input.channel, input.feature, input.property) }</li>
+                    )) }
+                </ul>
+            </FormGroup>
+        </FormSection>
+        { edit_single_target(&setter.map(|props|&mut props.target), &props.target) }
+    </>)
+}
+
+pub fn render_control_panel_editor<S>(setter: &S, props: &control_panel::Properties) -> Html
+where
+    S: Setter<control_panel::Properties>,
+{
+    use control_panel::ControlType;
+
+    html!(<>
+        <FormSection title="Parameters">
+            { setter_field(setter, "Delay", humantime::Duration::from(props.delay), | state, v| state.delay = v.into() )}
+            <FormGroup label="Controls">
+                <ul>
+                    { for props.controls.iter().map(|control| {
+                        let r#type = match control.r#type {
+                            ControlType::Slider { .. } => "slider",
+                            ControlType::Toggle { .. } => "toggle",
+                            ControlType::Number { .. } => "number",
+                            ControlType::Dropdown { .. } => "dropdown",
+                            ControlType::Button { .. } => "button",
+                        };
+                        html!(
+                            <li>{ control.label() }{ format!(" ({type}) → ") }<code>{ &control.property }</code></li>
+                        )
+                    }) }
+                </ul>
+            </FormGroup>
+        </FormSection>
+        { edit_feature_target(&setter.map(|props|&mut props.target), &props.target) }
+    </>)
+}
+
+pub fn render_binary_editor<S>(setter: &S, props: &binary::Properties) -> Html
+where
+    S: Setter<binary::Properties>,
+{
+    use binary::Source;
+
+    let source = match &props.source {
+        Source::Random { size } => html!({
+            setter_field(setter, "Size (bytes)", *size, |state, v| {
+                if let Source::Random { size } = &mut state.source {
+                    *size = v
+                }
+            })
+        }),
+        Source::Pattern { width, height } | Source::Camera { width, height } => html!(<>
+            { setter_field(setter, "Width", *width, |state, v| match &mut state.source { Source::Pattern { width, .. } | Source::Camera { width, .. } => *width = v, _ => {} }) }
+            { setter_field(setter, "Height", *height, |state, v| match &mut state.source { Source::Pattern { height, .. } | Source::Camera { height, .. } => *height = v, _ => {} }) }
+        </>),
+        Source::File { chunk_size } => html!({
+            setter_field(setter, "Chunk size (bytes)", *chunk_size, |state, v| {
+                if let Source::File { chunk_size } = &mut state.source {
+                    *chunk_size = v
+                }
+            })
+        }),
+    };
+
+    html!(<>
+        <FormSection title="Parameters">
+            { setter_field(setter, "Period", humantime::Duration::from(props.period), | state, v| state.period = v.into() )}
+            { setter_field(setter, "Channel", props.channel.clone(), | state, v| state.channel = v )}
+            { source }
+        </FormSection>
+    </>)
+}
@@ -1,7 +1,10 @@
 use crate::utils::float::{ApproxF64, Epsilon64};
 use itertools::Itertools;
 use patternfly_yew::*;
-use std::{convert::Infallible, num::ParseFloatError};
+use std::{
+    convert::Infallible,
+    num::{ParseFloatError, ParseIntError},
+};
 
 pub trait FieldType: Sized {
     type ParseError: std::error::Error;
@@ -72,6 +75,38 @@ impl FieldType for f64 {
     }
 }
 
+impl FieldType for u8 {
+    type ParseError = ParseIntError;
+
+    fn required() -> bool {
+        true
+    }
+
+    fn parse(value: &str) -> Result<Self, Self::ParseError> {
+        value.parse()
+    }
+
+    fn to_string(&self) -> String {
+        ToString::to_string(self)
+    }
+}
+
+impl FieldType for u32 {
+    type ParseError = ParseIntError;
+
+    fn required() -> bool {
+        true
+    }
+
+    fn parse(value: &str) -> Result<Self, Self::ParseError> {
+        value.parse()
+    }
+
+    fn to_string(&self) -> String {
+        ToString::to_string(self)
+    }
+}
+
 impl FieldType for humantime::Duration {
     type ParseError = humantime::DurationError;
 
@@ -0,0 +1,11 @@
+#![recursion_limit = "1024"]
+#![allow(clippy::needless_return)]
+
+pub mod connector;
+#[cfg(target_arch = "wasm32")]
+pub mod data;
+pub mod runtime;
+pub mod settings;
+pub mod share;
+pub mod simulator;
+pub mod utils;
@@ -1,26 +1,35 @@
 #![recursion_limit = "1024"]
 #![allow(clippy::needless_return)]
 
+#[cfg(target_arch = "wasm32")]
 mod app;
-mod connector;
-mod data;
+#[cfg(target_arch = "wasm32")]
 mod edit;
+#[cfg(target_arch = "wasm32")]
 mod pages;
-mod settings;
-mod simulator;
-mod utils;
+#[cfg(target_arch = "wasm32")]
+mod view;
 
+#[cfg(target_arch = "wasm32")]
 use crate::app::Application;
+#[cfg(target_arch = "wasm32")]
+use drogue_device_simulator::{connector, data, settings, share, simulator, utils};
+#[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
-#[cfg(not(debug_assertions))]
+#[cfg(all(target_arch = "wasm32", not(debug_assertions)))]
 const LOG_LEVEL: log::Level = log::Level::Info;
-#[cfg(debug_assertions)]
+#[cfg(all(target_arch = "wasm32", debug_assertions))]
 const LOG_LEVEL: log::Level = log::Level::Trace;
 
+#[cfg(target_arch = "wasm32")]
 pub fn main() -> Result<(), JsValue> {
     wasm_logger::init(wasm_logger::Config::new(LOG_LEVEL));
     log::info!("Getting ready...");
     yew::start_app::<Application>();
     Ok(())
 }
+
+/// The web application only runs in the browser, see the CLI for running natively.
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {}
@@ -164,7 +164,7 @@ impl Add {
         let cfg = self.content.clone();
 
         self.settings_agent.update(|settings| {
-            settings.simulations.insert(id, cfg);
+            settings.simulations.insert(id, cfg.into());
         });
 
         let route = Route::<()>::from(AppRoute::Simulation {
@@ -230,7 +230,22 @@ impl Add {
 
     fn validate(&mut self) {
         let claims = self.content.to_claims();
-        self.validation_result = if self.simulator_state.claims.is_claimed_any(claims, None) {
+        let conflicts = self.content.to_conflicting_inputs().iter().join(", ");
+        self.validation_result = if let Err(err) = self.content.validate() {
+            Some(FormAlert {
+                r#type: Type::Danger,
+                title: "Invalid simulation".into(),
+                children: html!({ err.to_string() }),
+            })
+        } else if !conflicts.is_empty() {
+            Some(FormAlert {
+                r#type: Type::Danger,
+                title: "Conflicting claims".into(),
+                children: html!({
+                    format!("The simulation must not target its own inputs: {conflicts}")
+                }),
+            })
+        } else if self.simulator_state.claims.is_claimed_any(claims, None) {
             Some(FormAlert {
                 r#type: Type::Warning,
                 title: "Conflicting claims".into(),
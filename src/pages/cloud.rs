@@ -0,0 +1,214 @@
+use crate::connector::local::{self, ChannelSummary, CloudSubscription};
+use crate::pages::ApplicationPage;
+use crate::utils::{handler::Handler, ui::render_payload};
+use chrono::Local;
+use patternfly_yew::*;
+use std::time::Duration;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry(String, ChannelSummary);
+
+impl TableRenderer for Entry {
+    fn render(&self, column: ColumnIndex) -> Html {
+        match column.index {
+            0 => html!(<code>{&self.0}</code>),
+            1 => html!({ self.1.received }),
+            2 => {
+                let timestamp = self
+                    .1
+                    .last
+                    .timestamp
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S%.3f");
+
+                timestamp.into()
+            }
+            3 => render_payload(&self.1.last.payload, false),
+            _ => html!(),
+        }
+    }
+
+    fn render_details(&self) -> Vec<Span> {
+        vec![Span::max(render_payload(&self.1.last.payload, true)).truncate()]
+    }
+}
+
+/// The cloud side of the local loopback bus.
+pub struct Cloud {
+    channels: SharedTableModel<Entry>,
+    _subscription: CloudSubscription,
+    refs: Refs,
+}
+
+#[derive(Default)]
+struct Refs {
+    command: NodeRef,
+    payload: NodeRef,
+}
+
+impl ApplicationPage for Cloud {
+    fn title() -> String {
+        "Cloud side".into()
+    }
+
+    fn help() -> Option<Html> {
+        Some(html!(
+            <Content>
+                { "Select the " }<q>{ "Local" }</q>{ " connection type, to connect the simulator to this page instead of a remote endpoint." }
+            </Content>
+        ))
+    }
+}
+
+pub enum Msg {
+    Received,
+    Clear,
+    Send,
+}
+
+impl Component for Cloud {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let subscription = local::subscribe({
+            let link = ctx.link().clone();
+            Handler::from(move |_| link.send_message(Msg::Received))
+        });
+
+        Self {
+            channels: Self::load(),
+            _subscription: subscription,
+            refs: Default::default(),
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Received => {
+                self.channels = Self::load();
+            }
+            Msg::Clear => {
+                local::clear();
+                self.channels = Self::load();
+            }
+            Msg::Send => {
+                self.send();
+                return false;
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let header = html_nested! {
+            <TableHeader>
+                <TableColumn label="Channel"/>
+                <TableColumn label="Received"/>
+                <TableColumn label="Last received"/>
+                <TableColumn label="Last payload"/>
+            </TableHeader>
+        };
+
+        html!(
+            <>
+                <PageSection variant={PageSectionVariant::Light} limit_width=true>
+                    <Content>
+                        { "Messages received from the device on the local loopback bus, by channel. Commands sent from here are delivered to the device, without any service involved." }
+                    </Content>
+                </PageSection>
+                <PageSection variant={PageSectionVariant::Light}>
+                    <Toolbar>
+                        <ToolbarGroup>
+                            <ToolbarItem>
+                                <Button
+                                    label="Clear"
+                                    icon={Icon::Times}
+                                    variant={Variant::Secondary}
+                                    onclick={ctx.link().callback(|_|Msg::Clear)}
+                                    />
+                            </ToolbarItem>
+                        </ToolbarGroup>
+                    </Toolbar>
+
+                    <Table<SharedTableModel<Entry>>
+                        entries={self.channels.clone()}
+                        mode={TableMode::CompactExpandable}
+                        header={header}
+                        >
+                    </Table<SharedTableModel<Entry>>>
+                </PageSection>
+                <PageSection variant={PageSectionVariant::Light} fill={true}>
+                    <Flex>
+                        <FlexItem modifiers={[FlexModifier::Grow]}>
+                            <Form horizontal={[FormHorizontal]} >
+                                <FormSection title="Send command">
+                                    <FormGroup
+                                        required=true
+                                        label="Command"
+                                        >
+                                        <TextInput
+                                            value="set-value"
+                                            ref={self.refs.command.clone()}
+                                        />
+                                    </FormGroup>
+
+                                    <FormGroup
+                                        label="Payload"
+                                        >
+                                        <TextArea
+                                            value=""
+                                            resize={ResizeOrientation::Vertical}
+                                            spellcheck=false
+                                            wrap={Wrap::Off}
+                                            rows=8
+                                            ref={self.refs.payload.clone()}
+                                        />
+                                    </FormGroup>
+                                </FormSection>
+
+                                <ActionGroup>
+                                    <Button label={"Send"} variant={Variant::Primary} onclick={ctx.link().callback(|_|Msg::Send)}/>
+                                </ActionGroup>
+                            </Form>
+                        </FlexItem>
+                        <FlexItem modifiers={[FlexModifier::Grow]}></FlexItem>
+                    </Flex>
+                </PageSection>
+            </>
+        )
+    }
+}
+
+impl Cloud {
+    fn load() -> SharedTableModel<Entry> {
+        local::channels()
+            .into_iter()
+            .map(|(channel, summary)| Entry(channel, summary))
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn send(&self) {
+        if let (Some(command), Some(payload)) = (
+            self.refs.command.cast::<HtmlInputElement>(),
+            self.refs.payload.cast::<HtmlInputElement>(),
+        ) {
+            if !local::send_command(&command.value(), payload.value().into_bytes()) {
+                ToastDispatcher::new().toast(Toast {
+                    title: "Device not connected".to_string(),
+                    r#type: Type::Warning,
+                    timeout: Some(Duration::from_secs(5)),
+                    body: html!(
+                        <Content>
+                            { "The simulator isn't connected to the local bus. Select the \"Local\" connection type, and start the simulator." }
+                        </Content>
+                    ),
+                    actions: vec![],
+                });
+            }
+        }
+    }
+}
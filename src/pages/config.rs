@@ -1,7 +1,8 @@
 use crate::data::{SharedDataBridge, SharedDataOps};
 use crate::pages::ApplicationPage;
 use crate::settings::{Settings, DEFAULT_CONFIG_KEY};
-use crate::utils::monaco::{to_model, to_yaml_model};
+use crate::share::{self, ShareOptions, SHARE_KEY};
+use crate::utils::monaco::{to_model, to_yaml_model, YamlValidation, YamlValidator};
 use anyhow::anyhow;
 use gloo_storage::{LocalStorage, Storage};
 use gloo_utils::window;
@@ -21,6 +22,12 @@ pub struct Configuration {
     settings_agent: SharedDataBridge<Settings>,
 
     yaml: Option<TextModel>,
+    // validates models against the settings schema
+    validator: Option<Rc<YamlValidator<Settings>>>,
+    // validates the current model, until dropped
+    validation: Option<YamlValidation>,
+
+    share_options: ShareOptions,
 }
 
 impl ApplicationPage for Configuration {
@@ -30,12 +37,14 @@ impl ApplicationPage for Configuration {
 }
 
 pub enum Msg {
-    Settings(Settings),
+    Settings(Box<Settings>),
 
     Apply,
     Share,
     Store,
     Recover,
+
+    SetShareOptions(Box<dyn FnOnce(&mut ShareOptions)>),
 }
 
 impl Component for Configuration {
@@ -43,36 +52,58 @@ impl Component for Configuration {
     type Properties = ();
 
     fn create(ctx: &Context<Self>) -> Self {
-        let mut settings_agent = SharedDataBridge::from(ctx.link(), Msg::Settings);
+        let mut settings_agent =
+            SharedDataBridge::from(ctx.link(), |settings| Msg::Settings(Box::new(settings)));
         settings_agent.request_state();
 
+        let validator = serde_json::to_value(Settings::schema())
+            .map_err(anyhow::Error::from)
+            .and_then(|schema| YamlValidator::new(&schema));
+        let validator = match validator {
+            Ok(validator) => Some(Rc::new(validator)),
+            Err(err) => {
+                log::warn!("Failed to create settings validator: {err}");
+                None
+            }
+        };
+
         Self {
             settings: Default::default(),
             settings_agent,
 
             yaml: Default::default(),
+            validator,
+            validation: None,
+
+            share_options: ShareOptions {
+                signing_key: LocalStorage::get(SHARE_KEY).ok(),
+                ..Default::default()
+            },
         }
     }
 
     fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::Settings(settings) => {
-                self.settings = settings;
-                self.yaml = to_yaml_model(&self.settings).ok();
+                self.settings = *settings;
+                self.set_model(to_yaml_model(&self.settings).ok());
             }
 
             Msg::Apply => {
                 if let Some(yaml) = &self.yaml {
                     let yaml = yaml.get_value();
                     match serde_yaml::from_str::<Settings>(&yaml) {
-                        Ok(settings) => {
-                            log::info!("Apply settings");
-                            self.settings_agent.set(settings);
-                            toast_success(
-                                "Applied configuration",
-                                "Configuration has been applied to the simulator.",
-                            );
-                        }
+                        Ok(settings) => match settings.payload.validate() {
+                            Ok(()) => {
+                                log::info!("Apply settings");
+                                self.settings_agent.set(settings);
+                                toast_success(
+                                    "Applied configuration",
+                                    "Configuration has been applied to the simulator.",
+                                );
+                            }
+                            Err(err) => toast_err("Invalid settings", err),
+                        },
                         Err(err) => toast_err("Failed to parse settings", err),
                     }
                 }
@@ -89,6 +120,8 @@ impl Component for Configuration {
             Msg::Recover => {
                 self.recover();
             }
+
+            Msg::SetShareOptions(mutator) => mutator(&mut self.share_options),
         }
         true
     }
@@ -108,7 +141,31 @@ impl Component for Configuration {
                         <CodeEditor model={self.yaml.clone()} options={options}/>
                     </StackItem>
                     <StackItem>
-                        <Form>
+                        <Form horizontal={[FormHorizontal.xl()]}>
+                            <FormSection title="Share">
+                                <FormGroup label="Compress">
+                                    <Switch
+                                        checked={self.share_options.compress}
+                                        on_change={ctx.link().callback(|v| Msg::SetShareOptions(Box::new(move |o| o.compress = v)))}
+                                    />
+                                </FormGroup>
+                                <FormGroup label="Strip credentials">
+                                    <Switch
+                                        checked={self.share_options.strip_credentials}
+                                        on_change={ctx.link().callback(|v| Msg::SetShareOptions(Box::new(move |o| o.strip_credentials = v)))}
+                                    />
+                                </FormGroup>
+                                <FormGroup label="Signing key">
+                                    <TextInput
+                                        r#type="password"
+                                        placeholder="Leave empty to share unsigned"
+                                        value={self.share_options.signing_key.clone().unwrap_or_default()}
+                                        onchange={ctx.link().callback(|v: String| Msg::SetShareOptions(Box::new(move |o| {
+                                            o.signing_key = if v.is_empty() { None } else { Some(v) };
+                                        })))}
+                                    />
+                                </FormGroup>
+                            </FormSection>
                             <ActionGroup>
                                 <Button
                                     label="Apply"
@@ -153,6 +210,17 @@ impl Configuration {
         Ok(json.to_string())
     }
 
+    /// Get the current (editor) configuration as settings
+    fn as_settings(&self) -> anyhow::Result<Settings> {
+        Ok(serde_yaml::from_str(
+            &self
+                .yaml
+                .as_ref()
+                .ok_or_else(|| anyhow!("No content"))?
+                .get_value(),
+        )?)
+    }
+
     fn share(&self) {
         if let Err(err) = self.do_share() {
             toast_err("Failed to share settings", err);
@@ -160,9 +228,9 @@ impl Configuration {
     }
 
     fn do_share(&self) -> anyhow::Result<()> {
-        let json = self.as_json_str()?;
+        let settings = self.as_settings()?;
 
-        log::debug!("Settings: {}", json);
+        log::debug!("Settings: {:?}", settings);
 
         let loc = window()
             .location()
@@ -172,8 +240,15 @@ impl Configuration {
         let mut url = Url::parse(&loc)?;
         url.set_path("");
 
-        let cfg = base64::encode_config(json, base64::URL_SAFE);
-        url.query_pairs_mut().append_pair("c", &cfg);
+        for (key, value) in share::encode(&settings, &self.share_options)? {
+            url.query_pairs_mut().append_pair(key, &value);
+        }
+
+        // remember the key, so that we can verify our own links
+        match &self.share_options.signing_key {
+            Some(key) => LocalStorage::set(SHARE_KEY, key)?,
+            None => LocalStorage::delete(SHARE_KEY),
+        }
 
         log::debug!("Location: {url}");
 
@@ -211,7 +286,7 @@ impl Configuration {
 
     fn recover(&mut self) {
         if let Some(cfg) = Settings::load_raw() {
-            self.yaml = match serde_json::from_str::<Value>(&cfg) {
+            let model = match serde_json::from_str::<Value>(&cfg) {
                 Ok(value) => {
                     ToastDispatcher::new().toast(Toast {
                         title: "Configuration recovered".to_string(),
@@ -244,9 +319,20 @@ impl Configuration {
                     to_model(Some("json"), cfg).ok()
                 }
             };
+            self.set_model(model);
         }
     }
 
+    fn set_model(&mut self, model: Option<TextModel>) {
+        // drop the old validation first, clearing its markers
+        self.validation = None;
+        self.validation = model
+            .clone()
+            .zip(self.validator.clone())
+            .map(|(model, validator)| YamlValidation::new(model, validator));
+        self.yaml = model;
+    }
+
     fn do_store(&self) -> anyhow::Result<()> {
         LocalStorage::set(DEFAULT_CONFIG_KEY, self.as_json_str()?)?;
 
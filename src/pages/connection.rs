@@ -1,8 +1,7 @@
-use crate::settings::Payload;
 use crate::{
     data::{SharedDataBridge, SharedDataOps},
     pages::ApplicationPage,
-    settings::{Credentials, PayloadFormat, Protocol, Settings, Target},
+    settings::{Credentials, MqttTopics, PayloadFormat, Protocol, Settings, Target},
 };
 use patternfly_yew::*;
 use std::fmt::{Display, Formatter};
@@ -20,12 +19,18 @@ pub struct Connection {
     auto_connect: bool,
     protocol: Protocol,
     url: String,
+    commands_url: String,
+    subprotocol: String,
+    publish_topic: String,
+    command_topic: String,
+    home_assistant: bool,
     credentials: CredentialsType,
     username: String,
     password: String,
     application: String,
     device: String,
     payload: PayloadFormatType,
+    cloud_events: bool,
 
     // refs
     refs: Refs,
@@ -70,7 +75,7 @@ impl ApplicationPage for Connection {
 }
 
 pub enum Msg {
-    Settings(Settings),
+    Settings(Box<Settings>),
 
     Set(Box<dyn FnOnce(&mut Connection)>),
 
@@ -83,7 +88,8 @@ impl Component for Connection {
     type Properties = ();
 
     fn create(ctx: &Context<Self>) -> Self {
-        let mut settings_agent = SharedDataBridge::from(ctx.link(), Msg::Settings);
+        let mut settings_agent =
+            SharedDataBridge::from(ctx.link(), |settings| Msg::Settings(Box::new(settings)));
         settings_agent.request_state();
 
         Self {
@@ -93,12 +99,18 @@ impl Component for Connection {
             auto_connect: true,
             protocol: Protocol::Mqtt,
             url: Default::default(),
+            commands_url: Default::default(),
+            subprotocol: Default::default(),
+            publish_topic: Default::default(),
+            command_topic: Default::default(),
+            home_assistant: false,
             credentials: CredentialsType::None,
             username: Default::default(),
             password: Default::default(),
             application: Default::default(),
             device: Default::default(),
             payload: Default::default(),
+            cloud_events: false,
 
             refs: Default::default(),
         }
@@ -107,7 +119,7 @@ impl Component for Connection {
     fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::Settings(settings) => {
-                self.settings = settings;
+                self.settings = *settings;
                 self.sync();
             }
 
@@ -146,7 +158,11 @@ impl Component for Connection {
             .link()
             .callback(|v| Msg::Set(Box::new(move |c| c.payload = v)));
 
+        // the local bus doesn't need a URL or credentials
+        let local = self.protocol == Protocol::Local;
+
         let (username_disabled, password_disabled) = match self.credentials {
+            _ if local => (true, true),
             CredentialsType::None => (true, true),
             CredentialsType::Password => (true, false),
             CredentialsType::UsernamePassword => (false, false),
@@ -164,6 +180,10 @@ impl Component for Connection {
                                     label={"Connection type"}>
                                     <FormSelect<Protocol> variant={SelectVariant::Single(set_protocol)} ref={self.refs.protocol.clone()}>
                                         <FormSelectOption<Protocol> selected={selected_protocol(Protocol::Mqtt)} value={Protocol::Mqtt} description="MQTT over WebSocket"  />
+                                        <FormSelectOption<Protocol> selected={selected_protocol(Protocol::Coap)} value={Protocol::Coap} description="CoAP over WebSocket"  />
+                                        <FormSelectOption<Protocol> selected={selected_protocol(Protocol::WebSocket)} value={Protocol::WebSocket} description="JSON over a plain WebSocket"  />
+                                        <FormSelectOption<Protocol> selected={selected_protocol(Protocol::ServerSentEvents)} value={Protocol::ServerSentEvents} description="JSON posted via HTTP, commands as Server-Sent Events"  />
+                                        <FormSelectOption<Protocol> selected={selected_protocol(Protocol::Local)} value={Protocol::Local} description="In-browser loopback, see the cloud side page"  />
                                     </FormSelect<Protocol>>
                                 </FormGroup>
 
@@ -172,12 +192,64 @@ impl Component for Connection {
                                     label="URL"
                                     >
                                     <TextInput
+                                        disabled={local}
                                         r#type="url"
                                         onchange={ctx.link().callback(|v| Msg::Set(Box::new(|c|c.url = v)))}
                                         value={self.url.clone()}
                                     />
                                 </FormGroup>
 
+                                <FormGroup
+                                    label="Command stream URL"
+                                    >
+                                    <TextInput
+                                        disabled={self.protocol != Protocol::ServerSentEvents}
+                                        r#type="url"
+                                        onchange={ctx.link().callback(|v| Msg::Set(Box::new(|c|c.commands_url = v)))}
+                                        value={self.commands_url.clone()}
+                                    />
+                                </FormGroup>
+
+                                <FormGroup
+                                    label="Subprotocol"
+                                    >
+                                    <TextInput
+                                        disabled={self.protocol != Protocol::WebSocket}
+                                        onchange={ctx.link().callback(|v| Msg::Set(Box::new(|c|c.subprotocol = v)))}
+                                        value={self.subprotocol.clone()}
+                                    />
+                                </FormGroup>
+
+                                <FormGroup
+                                    label="Publish topic"
+                                    >
+                                    <TextInput
+                                        disabled={self.protocol != Protocol::Mqtt}
+                                        onchange={ctx.link().callback(|v| Msg::Set(Box::new(|c|c.publish_topic = v)))}
+                                        value={self.publish_topic.clone()}
+                                    />
+                                </FormGroup>
+
+                                <FormGroup
+                                    label="Command topic"
+                                    >
+                                    <TextInput
+                                        disabled={self.protocol != Protocol::Mqtt}
+                                        onchange={ctx.link().callback(|v| Msg::Set(Box::new(|c|c.command_topic = v)))}
+                                        value={self.command_topic.clone()}
+                                    />
+                                </FormGroup>
+
+                                <FormGroup
+                                    label="Home Assistant discovery"
+                                    >
+                                    <Switch
+                                        disabled={self.protocol != Protocol::Mqtt}
+                                        checked={self.home_assistant}
+                                        on_change={ctx.link().callback(|v| Msg::Set(Box::new(move |c|c.home_assistant = v)))}
+                                    />
+                                </FormGroup>
+
                                 <FormGroup
                                     label="Application"
                                     >
@@ -201,7 +273,7 @@ impl Component for Connection {
                                 <FormGroup
                                     required=true
                                     label={"Credentials type"}>
-                                    <FormSelect<CredentialsType> variant={SelectVariant::Single(set_credentials)} ref={self.refs.credentials.clone()}>
+                                    <FormSelect<CredentialsType> disabled={local} variant={SelectVariant::Single(set_credentials)} ref={self.refs.credentials.clone()}>
                                         <FormSelectOption<CredentialsType> selected={selected_credentials(CredentialsType::None)} value={CredentialsType::None} />
                                         <FormSelectOption<CredentialsType> selected={selected_credentials(CredentialsType::Password)} value={CredentialsType::Password} />
                                         <FormSelectOption<CredentialsType> selected={selected_credentials(CredentialsType::UsernamePassword)} value={CredentialsType::UsernamePassword} />
@@ -248,6 +320,16 @@ impl Component for Connection {
                                         <FormSelectOption<PayloadFormatType> description="Doppelgänger" selected={selected_payload(PayloadFormatType::Doppelgaenger)} value={PayloadFormatType::Doppelgaenger} />
                                     </FormSelect<PayloadFormatType>>
                                 </FormGroup>
+
+                                <FormGroup
+                                    label="CloudEvents"
+                                    >
+                                    <Switch
+                                        checked={self.cloud_events}
+                                        disabled={self.settings.payload.lorawan.is_some()}
+                                        on_change={ctx.link().callback(|v| Msg::Set(Box::new(move |c|c.cloud_events = v)))}
+                                    />
+                                </FormGroup>
                             </FormSection>
 
                             <ActionGroup>
@@ -271,12 +353,48 @@ impl Connection {
         if let Some(input) = self.refs.protocol.cast::<HtmlInputElement>() {
             input.set_value(&self.protocol.to_string());
         }
-        let (url, credentials) = match &self.settings.target {
-            Target::Http { url, credentials } | Target::Mqtt { url, credentials } => {
-                (url, credentials)
+        let default_topics = MqttTopics::default();
+        let (url, credentials, subprotocol, topics) = match &self.settings.target {
+            Target::Mqtt {
+                url,
+                credentials,
+                topics,
+                ..
+            } => (url.as_str(), credentials, None, topics),
+            Target::Http { url, credentials } | Target::Coap { url, credentials } => {
+                (url.as_str(), credentials, None, &default_topics)
             }
+            Target::WebSocket {
+                url,
+                credentials,
+                protocol,
+                ..
+            } => (
+                url.as_str(),
+                credentials,
+                protocol.as_deref(),
+                &default_topics,
+            ),
+            Target::ServerSentEvents {
+                url, credentials, ..
+            } => (url.as_str(), credentials, None, &default_topics),
+            Target::Local => ("", &Credentials::None, None, &default_topics),
+        };
+        self.url = url.to_string();
+        self.commands_url = match &self.settings.target {
+            Target::ServerSentEvents { commands, .. } => commands.clone(),
+            _ => Default::default(),
         };
-        self.url = url.clone();
+        self.subprotocol = subprotocol.unwrap_or_default().to_string();
+        self.publish_topic = topics.publish.clone();
+        self.command_topic = topics.command.clone();
+        self.home_assistant = matches!(
+            self.settings.target,
+            Target::Mqtt {
+                home_assistant: Some(_),
+                ..
+            }
+        );
         self.application = self.settings.application.clone();
         self.device = self.settings.device.clone();
         match credentials {
@@ -302,6 +420,7 @@ impl Connection {
         if let Some(input) = self.refs.payload.cast::<HtmlSelectElement>() {
             input.set_value(&self.payload.to_string());
         }
+        self.cloud_events = self.settings.payload.cloud_events;
     }
 
     /// update the settings from the form
@@ -318,30 +437,91 @@ impl Connection {
         };
 
         let url = self.url.clone();
+        let commands_url = self.commands_url.clone();
+        let subprotocol = Some(self.subprotocol.trim())
+            .filter(|subprotocol| !subprotocol.is_empty())
+            .map(ToString::to_string);
+        // empty topics fall back to the defaults
+        let defaults = MqttTopics::default();
+        let topic = |topic: &str, default: String| match topic.trim() {
+            "" => default,
+            topic => topic.to_string(),
+        };
+        let topics = MqttTopics {
+            publish: topic(&self.publish_topic, defaults.publish),
+            command: topic(&self.command_topic, defaults.command),
+        };
+        let home_assistant = self.home_assistant;
         let auto_connect = self.auto_connect;
 
         let application = self.application.clone();
         let device = self.device.clone();
 
-        let payload = Payload {
-            format: match self.payload {
-                PayloadFormatType::Doppelgaenger => PayloadFormat::Doppelgaenger,
-                PayloadFormatType::JsonCompact => PayloadFormat::JsonCompact,
-            },
+        let format = match self.payload {
+            PayloadFormatType::Doppelgaenger => PayloadFormat::Doppelgaenger,
+            PayloadFormatType::JsonCompact => PayloadFormat::JsonCompact,
         };
+        let cloud_events = self.cloud_events;
 
         self.settings_agent.update(move |settings| {
             settings.auto_connect = auto_connect;
             settings.application = application;
             settings.device = device;
-            settings.payload = payload;
+            settings.payload.format = format;
+            settings.payload.cloud_events = cloud_events;
 
             match protocol {
                 Protocol::Http => {
                     settings.target = Target::Http { url, credentials };
                 }
                 Protocol::Mqtt => {
-                    settings.target = Target::Mqtt { url, credentials };
+                    // keep the existing configuration, like the discovery prefix
+                    let home_assistant = match &settings.target {
+                        _ if !home_assistant => None,
+                        Target::Mqtt {
+                            home_assistant: Some(existing),
+                            ..
+                        } => Some(existing.clone()),
+                        _ => Some(Default::default()),
+                    };
+                    settings.target = Target::Mqtt {
+                        url,
+                        credentials,
+                        topics,
+                        home_assistant,
+                    };
+                }
+                Protocol::Coap => {
+                    settings.target = Target::Coap { url, credentials };
+                }
+                Protocol::WebSocket => {
+                    // the framing is only configurable in the configuration editor
+                    let framing = match &settings.target {
+                        Target::WebSocket { framing, .. } => framing.clone(),
+                        _ => Default::default(),
+                    };
+                    settings.target = Target::WebSocket {
+                        url,
+                        credentials,
+                        protocol: subprotocol,
+                        framing,
+                    };
+                }
+                Protocol::ServerSentEvents => {
+                    let framing = match &settings.target {
+                        Target::ServerSentEvents { framing, .. }
+                        | Target::WebSocket { framing, .. } => framing.clone(),
+                        _ => Default::default(),
+                    };
+                    settings.target = Target::ServerSentEvents {
+                        url,
+                        commands: commands_url,
+                        credentials,
+                        framing,
+                    };
+                }
+                Protocol::Local => {
+                    settings.target = Target::Local;
                 }
             }
         })
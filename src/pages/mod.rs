@@ -1,5 +1,6 @@
 mod add;
 mod claims;
+mod cloud;
 mod commands;
 mod config;
 mod connection;
@@ -11,6 +12,7 @@ mod state;
 
 pub use add::*;
 pub use claims::*;
+pub use cloud::*;
 pub use commands::*;
 pub use config::*;
 pub use connection::*;
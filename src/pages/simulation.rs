@@ -7,7 +7,9 @@ use crate::{
         simulations::{SimulationFactory, SimulationState},
         Response, SimulatorBridge, SimulatorState,
     },
+    view::render_view,
 };
+use chrono::Local;
 use patternfly_yew::*;
 use yew::prelude::*;
 use yew_router::{agent::RouteRequest, prelude::*};
@@ -43,11 +45,14 @@ pub struct Simulation {
 pub enum Msg {
     State(SimulationState),
     SimulatorState(SimulatorState),
-    Settings(Settings),
+    Settings(Box<Settings>),
     Set(Box<dyn FnOnce(&mut settings::Simulation)>),
     ValidationState(InputState),
     Apply,
     Delete,
+    Pause,
+    Resume,
+    Step,
 }
 
 impl Component for Simulation {
@@ -64,7 +69,8 @@ impl Component for Simulation {
 
         simulator.subscribe_simulation(ctx.props().id.clone());
 
-        let mut settings_agent = SharedDataBridge::from(ctx.link(), Msg::Settings);
+        let mut settings_agent =
+            SharedDataBridge::from(ctx.link(), |settings| Msg::Settings(Box::new(settings)));
         settings_agent.request_state();
 
         Self {
@@ -89,13 +95,13 @@ impl Component for Simulation {
                 self.validate();
             }
             Msg::Settings(settings) => {
-                self.settings = settings;
+                self.settings = *settings;
                 self.validate();
             }
             Msg::Set(setter) => {
-                let sim = self.settings.simulations.get_mut(&self.simulation_id);
-                if let Some(sim) = sim {
-                    setter(sim);
+                let entry = self.settings.simulations.get_mut(&self.simulation_id);
+                if let Some(entry) = entry {
+                    setter(&mut entry.simulation);
                 }
                 self.validate();
             }
@@ -103,11 +109,11 @@ impl Component for Simulation {
                 self.validation_state = state;
             }
             Msg::Apply => {
-                if let Some(sim) = self.settings.simulations.get(&self.simulation_id) {
+                if let Some(entry) = self.settings.simulations.get(&self.simulation_id) {
                     let id = self.simulation_id.clone();
-                    let sim = sim.clone();
+                    let entry = entry.clone();
                     self.settings_agent.update(move |settings| {
-                        settings.simulations.insert(id, sim);
+                        settings.simulations.insert(id, entry);
                     });
                 }
             }
@@ -120,6 +126,15 @@ impl Component for Simulation {
                 let route = Route::<()>::from(AppRoute::Overview);
                 RouteAgentDispatcher::new().send(RouteRequest::ChangeRoute(route));
             }
+            Msg::Pause => {
+                self.simulator.pause_simulation(self.simulation_id.clone());
+            }
+            Msg::Resume => {
+                self.simulator.resume_simulation(self.simulation_id.clone());
+            }
+            Msg::Step => {
+                self.simulator.step_simulation(self.simulation_id.clone());
+            }
         }
         true
     }
@@ -172,7 +187,10 @@ impl Component for Simulation {
                 {
                     match ctx.props().details {
                         SimulationDetails::Overview => html!(
-                            { self.state.html.clone() }
+                            <>
+                                { self.render_controls(ctx) }
+                                { render_view(&self.state.view) }
+                            </>
                         ),
                         SimulationDetails::Configuration => html!(
                             { self.render_editor(ctx) }
@@ -186,6 +204,73 @@ impl Component for Simulation {
 }
 
 impl Simulation {
+    fn is_paused(&self) -> bool {
+        self.settings
+            .simulations
+            .get(&self.simulation_id)
+            .map(|entry| entry.paused)
+            .unwrap_or_default()
+    }
+
+    fn render_controls(&self, ctx: &Context<Self>) -> Html {
+        let paused = self.is_paused();
+
+        html!(
+            <Toolbar>
+                <ToolbarGroup>
+                    <ToolbarItem>
+                        if paused {
+                            <Button
+                                label="Resume"
+                                icon={Icon::Play}
+                                variant={Variant::Secondary}
+                                onclick={ctx.link().callback(|_|Msg::Resume)}
+                                />
+                        } else {
+                            <Button
+                                label="Pause"
+                                icon={Icon::Pause}
+                                variant={Variant::Secondary}
+                                onclick={ctx.link().callback(|_|Msg::Pause)}
+                                />
+                        }
+                    </ToolbarItem>
+                    <ToolbarItem>
+                        <Button
+                            label="Step"
+                            icon={Icon::ArrowRight}
+                            variant={Variant::Secondary}
+                            disabled={!paused}
+                            onclick={ctx.link().callback(|_|Msg::Step)}
+                            />
+                    </ToolbarItem>
+                    <ToolbarItem>
+                        { self.render_schedule() }
+                    </ToolbarItem>
+                </ToolbarGroup>
+            </Toolbar>
+        )
+    }
+
+    fn render_schedule(&self) -> Html {
+        let text = match self.simulator_state.schedules.get(&self.simulation_id) {
+            Some(Ok(state)) => {
+                let label = if state.active { "Running" } else { "Stopped" };
+                match state.next {
+                    Some(next) => format!(
+                        "{label} until {}",
+                        next.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S")
+                    ),
+                    None => format!("{label} (no further transitions)"),
+                }
+            }
+            Some(Err(err)) => format!("Invalid schedule: {err}"),
+            None => return html!(),
+        };
+
+        html!(<Label label={text} />)
+    }
+
     fn render_editor(&self, ctx: &Context<Self>) -> Html {
         let setter = ContextSetter::from((ctx, Msg::Set));
 
@@ -195,8 +280,8 @@ impl Simulation {
                 horizontal={[FormHorizontal.xl()]}
                 onvalidated={ctx.link().callback(Msg::ValidationState)}
                 >
-                if let Some(sim) = self.settings.simulations.get(&self.simulation_id) {
-                    { render_editor(&sim, setter) }
+                if let Some(entry) = self.settings.simulations.get(&self.simulation_id) {
+                    { render_editor(&entry.simulation, setter) }
                 }
 
                 <ActionGroup>
@@ -220,14 +305,38 @@ impl Simulation {
     }
 
     fn validate(&mut self) {
-        let claims = self
+        let simulation = self
             .settings
             .simulations
             .get(&self.simulation_id)
-            .map(|sim| sim.create().claims().to_vec())
+            .map(|entry| &entry.simulation);
+
+        let claims = simulation
+            .map(|simulation| simulation.create().claims().to_vec())
             .unwrap_or_default();
+        let conflicts = simulation
+            .map(|simulation| simulation.to_conflicting_inputs())
+            .unwrap_or_default()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
 
-        self.validation_result = if self
+        self.validation_result = if let Some(Err(err)) = simulation.map(|s| s.validate()) {
+            Some(FormAlert {
+                r#type: Type::Danger,
+                title: "Invalid simulation".into(),
+                children: html!({ err.to_string() }),
+            })
+        } else if !conflicts.is_empty() {
+            Some(FormAlert {
+                r#type: Type::Danger,
+                title: "Conflicting claims".into(),
+                children: html!({
+                    format!("The simulation must not target its own inputs: {conflicts}")
+                }),
+            })
+        } else if self
             .simulator_state
             .claims
             .is_claimed_any(claims, Some(&self.simulation_id))
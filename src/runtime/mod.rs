@@ -0,0 +1,107 @@
+//! Platform abstraction for time, randomness and spawning tasks.
+//!
+//! The simulation core only uses the functions of this module, so that it can run in the browser
+//! as well as natively. Natively, tasks are spawned on the current thread, which requires running
+//! inside a tokio `LocalSet`.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+
+use futures::future::{abortable, AbortHandle, LocalBoxFuture};
+use std::future::Future;
+
+pub trait Runtime {
+    /// The current time, in milliseconds since the epoch.
+    fn now() -> f64;
+
+    /// A random value, between 0 (inclusive) and 1 (exclusive).
+    fn random() -> f64;
+
+    /// Spawn a task on the current thread.
+    fn spawn<F>(future: F)
+    where
+        F: Future<Output = ()> + 'static;
+
+    /// A future completing after the provided number of milliseconds.
+    fn sleep(millis: u32) -> LocalBoxFuture<'static, ()>;
+}
+
+#[cfg(target_arch = "wasm32")]
+pub type Platform = wasm::Wasm;
+#[cfg(not(target_arch = "wasm32"))]
+pub type Platform = native::Native;
+
+pub fn now() -> f64 {
+    Platform::now()
+}
+
+pub fn random() -> f64 {
+    Platform::random()
+}
+
+pub fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    Platform::spawn(future)
+}
+
+pub fn sleep(millis: u32) -> LocalBoxFuture<'static, ()> {
+    Platform::sleep(millis)
+}
+
+/// Run a function once, after a delay.
+///
+/// Dropping the timeout cancels it.
+pub struct Timeout(AbortHandle);
+
+impl Timeout {
+    pub fn new<F>(millis: u32, f: F) -> Self
+    where
+        F: FnOnce() + 'static,
+    {
+        let (timeout, handle) = abortable(sleep(millis));
+        spawn(async move {
+            if timeout.await.is_ok() {
+                f();
+            }
+        });
+        Self(handle)
+    }
+}
+
+impl Drop for Timeout {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Run a function repeatedly, until dropped.
+pub struct Interval(AbortHandle);
+
+impl Interval {
+    pub fn new<F>(millis: u32, f: F) -> Self
+    where
+        F: FnMut() + 'static,
+    {
+        let mut f = f;
+        let (interval, handle) = abortable(async move {
+            loop {
+                sleep(millis).await;
+                f();
+            }
+        });
+        spawn(async move {
+            let _ = interval.await;
+        });
+        Self(handle)
+    }
+}
+
+impl Drop for Interval {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
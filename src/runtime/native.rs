@@ -0,0 +1,34 @@
+use super::Runtime;
+use futures::{future::LocalBoxFuture, FutureExt};
+use std::{
+    future::Future,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Native, using the tokio runtime.
+pub struct Native;
+
+impl Runtime for Native {
+    fn now() -> f64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64()
+            * 1000.0
+    }
+
+    fn random() -> f64 {
+        rand::random()
+    }
+
+    fn spawn<F>(future: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        tokio::task::spawn_local(future);
+    }
+
+    fn sleep(millis: u32) -> LocalBoxFuture<'static, ()> {
+        tokio::time::sleep(Duration::from_millis(millis as u64)).boxed_local()
+    }
+}
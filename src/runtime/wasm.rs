@@ -0,0 +1,29 @@
+use super::Runtime;
+use futures::{future::LocalBoxFuture, FutureExt};
+use gloo_timers::future::TimeoutFuture;
+use std::future::Future;
+
+/// The browser, using the JavaScript event loop.
+pub struct Wasm;
+
+impl Runtime for Wasm {
+    fn now() -> f64 {
+        js_sys::Date::now()
+    }
+
+    fn random() -> f64 {
+        js_sys::Math::random()
+    }
+
+    fn spawn<F>(future: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        wasm_bindgen_futures::spawn_local(future)
+    }
+
+    fn sleep(millis: u32) -> LocalBoxFuture<'static, ()> {
+        // internally this is an i32
+        TimeoutFuture::new(millis.min(i32::MAX as u32)).boxed_local()
+    }
+}
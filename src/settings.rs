@@ -1,14 +1,26 @@
 use crate::simulator::simulations::led_matrix;
 use crate::simulator::{
+    conflicting_inputs,
+    homeassistant::HomeAssistant,
+    lorawan::LoRaWan,
     simulations::{
-        self, accelerometer, default_channel, default_feature, default_value_property, sawtooth,
-        sine, slider, slider::Step, wave, SimulationFactory, SingleTarget,
+        self, accelerometer, actuator, battery, binary, control_panel, counter, default_channel,
+        default_feature, default_value_property, event, formula, location, sawtooth, sine, slider,
+        slider::Step, thermostat, wave, FeatureTarget, SimulationFactory, SingleTarget,
     },
-    Claim,
+    websocket::Framing,
+    Claim, Schedule,
 };
+use crate::utils::random::Distribution;
+use anyhow::bail;
+#[cfg(target_arch = "wasm32")]
 use gloo_storage::{LocalStorage, Storage};
+use schemars::{
+    schema::{RootSchema, Schema},
+    schema_for, JsonSchema,
+};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::{
     collections::BTreeMap,
     fmt::{Display, Formatter},
@@ -18,7 +30,7 @@ use strum::{EnumDiscriminants, EnumIter, EnumMessage, EnumString};
 
 pub const DEFAULT_CONFIG_KEY: &str = "drogue.io/device-simulator/defaultConfiguration";
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Settings {
     pub auto_connect: bool,
@@ -30,19 +42,40 @@ pub struct Settings {
     pub payload: Payload,
 
     #[serde(default)]
-    pub simulations: BTreeMap<String, Simulation>,
+    pub simulations: BTreeMap<String, SimulationEntry>,
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub import: Option<Import>,
 }
 
-#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Payload {
     pub format: PayloadFormat,
+    /// Wrap payloads in structured mode CloudEvents, and unwrap commands received as such.
+    ///
+    /// Can't be combined with `lorawan`.
+    #[serde(default)]
+    pub cloud_events: bool,
+    /// Emulate a LoRaWAN device, wrapping the payloads in the uplinks of a network server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lorawan: Option<LoRaWan>,
 }
 
-#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+impl Payload {
+    /// Validate the payload settings, beyond what the deserializer checks.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.cloud_events && self.lorawan.is_some() {
+            bail!("CloudEvents can't be combined with LoRaWAN payloads");
+        }
+        if let Some(lorawan) = &self.lorawan {
+            lorawan.validate()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum PayloadFormat {
     #[default]
@@ -50,7 +83,7 @@ pub enum PayloadFormat {
     Doppelgaenger,
 }
 
-#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Import {
     /// Give a hint to the user to tweak the connection settings.
@@ -65,7 +98,33 @@ fn is_default<T: Default + Eq>(value: &T) -> bool {
     value == &T::default()
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, EnumDiscriminants)]
+/// A simulation, along with the settings common to all simulations.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationEntry {
+    #[serde(flatten)]
+    pub simulation: Simulation,
+
+    /// Pause the simulation, keeping its state, but not publishing.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub paused: bool,
+
+    /// Only run the simulation during the windows of the schedule.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Schedule>,
+}
+
+impl From<Simulation> for SimulationEntry {
+    fn from(simulation: Simulation) -> Self {
+        Self {
+            simulation,
+            paused: false,
+            schedule: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema, EnumDiscriminants)]
 #[serde(rename_all = "camelCase")]
 #[strum_discriminants(derive(strum::Display, EnumMessage, EnumIter, EnumString))]
 pub enum Simulation {
@@ -81,6 +140,28 @@ pub enum Simulation {
     Slider(Box<simulations::slider::Properties>),
     #[strum_discriminants(strum(message = "Led matrix",))]
     LedMatrix(Box<simulations::led_matrix::Properties>),
+    #[strum_discriminants(strum(message = "Location (GPS) track",))]
+    Location(Box<simulations::location::Properties>),
+    #[strum_discriminants(strum(message = "Battery",))]
+    Battery(Box<simulations::battery::Properties>),
+    #[strum_discriminants(strum(message = "Thermostat (HVAC), controlled by commands",))]
+    Thermostat(Box<simulations::thermostat::Properties>),
+    #[strum_discriminants(strum(message = "Switch, controlled by commands",))]
+    Switch(Box<simulations::actuator::SwitchProperties>),
+    #[strum_discriminants(strum(message = "Dimmer, controlled by commands",))]
+    Dimmer(Box<simulations::actuator::DimmerProperties>),
+    #[strum_discriminants(strum(message = "Mode selector, controlled by commands",))]
+    ModeSelector(Box<simulations::actuator::ModeSelectorProperties>),
+    #[strum_discriminants(strum(message = "Counter (e.g. energy meter)",))]
+    Counter(Box<simulations::counter::Properties>),
+    #[strum_discriminants(strum(message = "Event emitter (e.g. door opened)",))]
+    Event(Box<simulations::event::Properties>),
+    #[strum_discriminants(strum(message = "Formula, computed from other properties",))]
+    Formula(Box<simulations::formula::Properties>),
+    #[strum_discriminants(strum(message = "Control panel, with multiple inputs",))]
+    ControlPanel(Box<simulations::control_panel::Properties>),
+    #[strum_discriminants(strum(message = "Binary payloads (e.g. camera snapshots)",))]
+    Binary(Box<simulations::binary::Properties>),
 }
 
 impl Simulation {
@@ -91,6 +172,36 @@ impl Simulation {
     pub fn to_claims(&self) -> Vec<Claim> {
         self.create().claims().to_vec()
     }
+
+    /// The inputs of the simulation, which are targeted by its own claims.
+    pub fn to_conflicting_inputs(&self) -> Vec<Claim> {
+        let simulation = self.create();
+        conflicting_inputs(simulation.claims(), simulation.inputs())
+    }
+
+    /// Validate the simulation, beyond what the schema can express.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Formula(props) => props.validate(),
+            Self::ControlPanel(props) => props.validate(),
+            Self::Dimmer(props) => props.validate(),
+            Self::Binary(props) => props.validate(),
+            _ => Ok(()),
+        }
+    }
+
+    /// If the simulation depends on the browser, like its sensors or camera.
+    pub fn requires_browser(&self) -> bool {
+        match self {
+            Self::Accelerometer(props) => matches!(props.source, accelerometer::Source::Browser),
+            Self::Location(props) => matches!(props.source, location::Source::Browser),
+            Self::Binary(props) => matches!(
+                props.source,
+                binary::Source::File { .. } | binary::Source::Camera { .. }
+            ),
+            _ => false,
+        }
+    }
 }
 
 impl Default for Simulation {
@@ -127,6 +238,7 @@ impl SimulationDiscriminants {
             })),
             Self::Accelerometer => Simulation::Accelerometer(Box::new(accelerometer::Properties {
                 delay: default_period(),
+                source: Default::default(),
                 target: Default::default(),
             })),
             Self::Slider => Simulation::Slider(Box::new(slider::Properties {
@@ -145,6 +257,132 @@ impl SimulationDiscriminants {
                 target: Default::default(),
                 color: Default::default(),
                 color_off: Default::default(),
+                rows: 8,
+                columns: 8,
+            })),
+            Self::Location => Simulation::Location(Box::new(location::Properties {
+                period: default_period(),
+                source: location::Source::Waypoints {
+                    waypoints: vec![
+                        location::Position::new(52.5163, 13.3777),
+                        location::Position::new(52.5163, 13.3900),
+                        location::Position::new(52.5100, 13.3900),
+                        location::Position::new(52.5100, 13.3777),
+                        location::Position::new(52.5163, 13.3777),
+                    ],
+                    speed: 10f64.into(),
+                    repeat: true,
+                },
+                target: Default::default(),
+            })),
+            Self::Battery => Simulation::Battery(Box::new(battery::Properties {
+                period: Duration::from_secs(10),
+                capacity: 2000f64.into(),
+                initial_level: 100f64.into(),
+                idle_current: 50f64.into(),
+                publish_charge: 0.01f64.into(),
+                charge_current: 1000f64.into(),
+                charging: None,
+                voltage_curve: battery::default_voltage_curve(),
+                acceleration: 1f64.into(),
+                shutdown: false,
+                target: FeatureTarget::new("state", "battery"),
+            })),
+            Self::Thermostat => Simulation::Thermostat(Box::new(thermostat::Properties {
+                period: default_period(),
+                ambient: 15f64.into(),
+                initial: None,
+                setpoint: 21f64.into(),
+                mode: Default::default(),
+                heating_rate: 0.5f64.into(),
+                cooling_rate: 0.5f64.into(),
+                loss: 0.05f64.into(),
+                hysteresis: 0.5f64.into(),
+                acceleration: 1f64.into(),
+                target: FeatureTarget::new("state", "thermostat"),
+            })),
+            Self::Switch => Simulation::Switch(Box::new(actuator::SwitchProperties {
+                initial: false,
+                behavior: Default::default(),
+                target: SingleTarget::new("state", "switch", "on"),
+            })),
+            Self::Dimmer => Simulation::Dimmer(Box::new(actuator::DimmerProperties {
+                min: 0f64.into(),
+                max: 100f64.into(),
+                initial: 0f64.into(),
+                ramp: Duration::from_secs(2),
+                behavior: Default::default(),
+                target: SingleTarget::new("state", "dimmer", "level"),
+            })),
+            Self::ModeSelector => {
+                Simulation::ModeSelector(Box::new(actuator::ModeSelectorProperties {
+                    modes: vec!["eco".into(), "comfort".into(), "away".into()],
+                    initial: None,
+                    behavior: Default::default(),
+                    target: SingleTarget::new("state", "mode", "value"),
+                }))
+            }
+            Self::Counter => Simulation::Counter(Box::new(counter::Properties {
+                period: default_period(),
+                initial: 0f64.into(),
+                increment: Distribution::Uniform {
+                    min: 0f64.into(),
+                    max: 0.1f64.into(),
+                },
+                rollover: None,
+                target: SingleTarget::new("state", "energy", "kwh"),
+            })),
+            Self::Event => Simulation::Event(Box::new(event::Properties {
+                period: default_period(),
+                rate: 2f64.into(),
+                event: "doorOpened".into(),
+                data: Value::Null,
+                channel: event::default_event_channel(),
+            })),
+            Self::Formula => Simulation::Formula(Box::new(formula::Properties {
+                expression: "voltage * current".into(),
+                inputs: [
+                    ("voltage", SingleTarget::new("state", "power", "voltage")),
+                    ("current", SingleTarget::new("state", "power", "current")),
+                ]
+                .into_iter()
+                .map(|(name, target)| (name.to_string(), target))
+                .collect(),
+                target: SingleTarget::new("state", "power", "watts"),
+            })),
+            Self::ControlPanel => Simulation::ControlPanel(Box::new(control_panel::Properties {
+                delay: default_period(),
+                target: FeatureTarget::new("state", "panel"),
+                controls: vec![
+                    control_panel::Control {
+                        property: "on".into(),
+                        label: Some("Power".into()),
+                        r#type: control_panel::ControlType::Toggle { initial: false },
+                    },
+                    control_panel::Control {
+                        property: "level".into(),
+                        label: Some("Level".into()),
+                        r#type: control_panel::ControlType::Slider {
+                            min: Step::Value(0f64.into()),
+                            max: Step::Value(100f64.into()),
+                        },
+                    },
+                    control_panel::Control {
+                        property: "mode".into(),
+                        label: Some("Mode".into()),
+                        r#type: control_panel::ControlType::Dropdown {
+                            options: vec!["eco".into(), "comfort".into(), "boost".into()],
+                        },
+                    },
+                ],
+            })),
+            Self::Binary => Simulation::Binary(Box::new(binary::Properties {
+                period: default_period(),
+                channel: binary::default_binary_channel(),
+                source: binary::Source::Pattern {
+                    width: 64,
+                    height: 48,
+                },
             })),
         }
     }
@@ -157,6 +395,8 @@ impl Default for Settings {
             target: Target::Mqtt {
                 url: "wss://mqtt-endpoint-ws-browser-drogue-dev.apps.wonderful.iot-playground.org/mqtt".into(),
                 credentials: Credentials::Password("my-password".into()),
+                topics: Default::default(),
+                home_assistant: None,
             },
             application: "my-application".into(),
             device: "my-device".into(),
@@ -172,7 +412,7 @@ impl Default for Settings {
                         feature: default_feature(),
                         property: default_value_property(),
                     }
-                })));
+                })).into());
                 s
             },
             import: None,
@@ -181,27 +421,82 @@ impl Default for Settings {
 }
 
 impl Settings {
+    #[cfg(target_arch = "wasm32")]
     pub fn load() -> Option<anyhow::Result<Self>> {
         let json: Option<String> = LocalStorage::get(DEFAULT_CONFIG_KEY).ok();
         json.map(|json| serde_json::from_str(&json).map_err(|err| anyhow::Error::new(err)))
     }
 
+    #[cfg(target_arch = "wasm32")]
     pub fn load_raw() -> Option<String> {
         LocalStorage::get(DEFAULT_CONFIG_KEY).ok()
     }
+
+    /// The JSON schema of the settings, as used by the configuration editor.
+    pub fn schema() -> RootSchema {
+        let mut schema = schema_for!(Settings);
+
+        // reject enabling CloudEvents together with LoRaWAN, see `Payload::validate`
+        if let Some(Schema::Object(payload)) = schema.definitions.get_mut("Payload") {
+            payload.subschemas().not = serde_json::from_value(json!({
+                "required": ["cloudEvents", "lorawan"],
+                "properties": {
+                    "cloudEvents": { "const": true },
+                },
+            }))
+            .ok();
+        }
+
+        schema
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum Target {
     Mqtt {
         url: String,
         credentials: Credentials,
+        /// Templates of the topics, defaulting to the conventions of Drogue Cloud.
+        #[serde(default, skip_serializing_if = "is_default")]
+        topics: MqttTopics,
+        /// Publish Home Assistant discovery configurations, once subscribed.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        home_assistant: Option<HomeAssistant>,
     },
     Http {
         url: String,
         credentials: Credentials,
     },
+    /// CoAP over WebSockets.
+    Coap {
+        url: String,
+        credentials: Credentials,
+    },
+    /// JSON messages over a plain WebSocket.
+    WebSocket {
+        url: String,
+        credentials: Credentials,
+        /// The subprotocol to request when connecting.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        protocol: Option<String>,
+        /// The framing of events and commands.
+        #[serde(default)]
+        framing: Framing,
+    },
+    /// JSON events posted via HTTP, and commands received as Server-Sent Events.
+    ServerSentEvents {
+        /// The URL events are posted to.
+        url: String,
+        /// The URL of the event stream, carrying the commands.
+        commands: String,
+        credentials: Credentials,
+        /// The framing of events and commands.
+        #[serde(default)]
+        framing: Framing,
+    },
+    /// An in-browser loopback bus, for demonstrating the simulator without any service.
+    Local,
 }
 
 impl Target {
@@ -209,11 +504,47 @@ impl Target {
         match self {
             Self::Mqtt { .. } => Protocol::Mqtt,
             Self::Http { .. } => Protocol::Http,
+            Self::Coap { .. } => Protocol::Coap,
+            Self::WebSocket { .. } => Protocol::WebSocket,
+            Self::ServerSentEvents { .. } => Protocol::ServerSentEvents,
+            Self::Local => Protocol::Local,
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// Templates of MQTT topics.
+///
+/// The placeholders `{application}` and `{device}` are replaced in both templates. When publishing, `{channel}` is
+/// replaced with the channel. When subscribing, the command template is turned into a topic filter, `{command}` (which
+/// must be present exactly once) becoming a wildcard, which the name of the command is extracted from. If it is the last
+/// level, it captures all remaining levels. The wildcards `+` and `#` may be used as well.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MqttTopics {
+    #[serde(default = "default_publish_topic")]
+    pub publish: String,
+    #[serde(default = "default_command_topic")]
+    pub command: String,
+}
+
+impl Default for MqttTopics {
+    fn default() -> Self {
+        Self {
+            publish: default_publish_topic(),
+            command: default_command_topic(),
+        }
+    }
+}
+
+fn default_publish_topic() -> String {
+    "{channel}".into()
+}
+
+fn default_command_topic() -> String {
+    "command/inbox//{command}".into()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum Credentials {
     None,
@@ -221,10 +552,52 @@ pub enum Credentials {
     UsernamePassword { username: String, password: String },
 }
 
+impl Credentials {
+    /// The username and password to authenticate with, when connecting as the device.
+    ///
+    /// A plain password uses `<device>@<application>` as username.
+    pub fn to_username_password(&self, settings: &Settings) -> (Option<String>, Option<String>) {
+        match self {
+            Self::None => (None, None),
+            Self::Password(password) => (
+                Some(format!("{}@{}", settings.device, settings.application)),
+                Some(password.clone()),
+            ),
+            Self::UsernamePassword { username, password } => {
+                (Some(username.clone()), Some(password.clone()))
+            }
+        }
+    }
+
+    /// The value of an `Authorization` header, using basic authentication.
+    pub fn to_authorization(&self, settings: &Settings) -> Option<String> {
+        match self.to_username_password(settings) {
+            (None, None) => None,
+            (username, password) => Some(format!(
+                "Basic {}",
+                base64::encode(format!(
+                    "{}:{}",
+                    username.unwrap_or_default(),
+                    password.unwrap_or_default()
+                ))
+            )),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, EnumString)]
 pub enum Protocol {
+    // the names must match the display value, which is used by the UI
+    #[strum(serialize = "HTTP")]
     Http,
+    #[strum(serialize = "MQTT")]
     Mqtt,
+    #[strum(serialize = "CoAP")]
+    Coap,
+    WebSocket,
+    #[strum(serialize = "Server-Sent Events")]
+    ServerSentEvents,
+    Local,
 }
 
 impl Display for Protocol {
@@ -232,6 +605,59 @@ impl Display for Protocol {
         match self {
             Self::Http => f.write_str("HTTP"),
             Self::Mqtt => f.write_str("MQTT"),
+            Self::Coap => f.write_str("CoAP"),
+            Self::WebSocket => f.write_str("WebSocket"),
+            Self::ServerSentEvents => f.write_str("Server-Sent Events"),
+            Self::Local => f.write_str("Local"),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_schema_default_settings() {
+        let schema = serde_json::to_value(Settings::schema()).unwrap();
+        let properties = &schema["properties"];
+
+        assert!(properties["target"].is_object());
+        assert!(properties["simulations"].is_object());
+        assert!(schema["definitions"]["SimulationEntry"].is_object());
+    }
+
+    #[test]
+    fn test_cloud_events_with_lorawan() {
+        let mut payload: Payload = serde_json::from_value(json!({
+            "format": "jsonCompact",
+            "lorawan": {
+                "network": "ttn",
+                "devEui": "0011223344AABBCC",
+                "encoder": [{"feature": "feature", "type": "bool"}],
+            },
+        }))
+        .unwrap();
+        assert!(payload.validate().is_ok());
+
+        payload.cloud_events = true;
+        assert!(payload.validate().is_err());
+
+        payload.lorawan = None;
+        assert!(payload.validate().is_ok());
+
+        let schema = serde_json::to_value(Settings::schema()).unwrap();
+        let not = &schema["definitions"]["Payload"]["not"];
+        assert_eq!(not["required"], json!(["cloudEvents", "lorawan"]));
+    }
+
+    /// Write the schema to the file provided by `SCHEMA_OUTPUT`, used to publish it during the build.
+    #[test]
+    #[ignore]
+    fn generate_schema() {
+        let path = std::env::var("SCHEMA_OUTPUT")
+            .unwrap_or_else(|_| "target/settings.schema.json".to_string());
+        let schema = serde_json::to_string_pretty(&Settings::schema()).unwrap();
+        std::fs::write(&path, schema).unwrap();
+    }
+}
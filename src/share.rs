@@ -0,0 +1,336 @@
+//! Encoding and decoding of configurations shared via a URL.
+
+use crate::settings::{Credentials, Import, Settings, Target};
+use anyhow::{anyhow, bail, Context};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io::{Read, Write};
+
+/// Query parameter carrying the plain, base64 encoded JSON configuration.
+pub const PARAM_CONFIG: &str = "c";
+/// Query parameter carrying the deflated, base64 encoded JSON configuration.
+pub const PARAM_COMPRESSED: &str = "z";
+/// Query parameter carrying the signature of the configuration parameter.
+pub const PARAM_SIGNATURE: &str = "s";
+
+/// Local storage key of the key used for signing and verifying shared configurations.
+pub const SHARE_KEY: &str = "drogue.io/device-simulator/shareKey";
+
+/// Maximum size of a decompressed configuration.
+const MAX_CONFIG_SIZE: usize = 4 * 1024 * 1024;
+
+const STRIPPED_CREDENTIALS_HINT: &str = "The configuration was shared without credentials. Please re-enter the credentials of the connection before connecting.";
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShareOptions {
+    /// Deflate the configuration before encoding it.
+    pub compress: bool,
+    /// Remove all credentials, asking the user to re-enter them when importing.
+    pub strip_credentials: bool,
+    /// Sign the configuration with this key.
+    pub signing_key: Option<String>,
+}
+
+impl Default for ShareOptions {
+    fn default() -> Self {
+        Self {
+            compress: true,
+            strip_credentials: false,
+            signing_key: None,
+        }
+    }
+}
+
+/// The outcome of verifying the signature of a shared configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verification {
+    /// The configuration was not signed.
+    Unsigned,
+    /// The signature matches the configuration.
+    Verified,
+    /// The configuration was signed, but we don't have a key to verify it.
+    NoKey,
+    /// The signature doesn't match, the configuration might have been tampered with.
+    Failed,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shared {
+    pub settings: Settings,
+    pub verification: Verification,
+}
+
+/// Encode the settings into query parameters.
+pub fn encode(
+    settings: &Settings,
+    options: &ShareOptions,
+) -> anyhow::Result<Vec<(&'static str, String)>> {
+    let mut settings = settings.clone();
+    if options.strip_credentials {
+        strip_credentials(&mut settings);
+    }
+
+    let json = serde_json::to_vec(&settings)?;
+
+    let mut result = if options.compress {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&json)?;
+        let data = encoder.finish()?;
+        vec![(
+            PARAM_COMPRESSED,
+            base64::encode_config(data, base64::URL_SAFE),
+        )]
+    } else {
+        vec![(PARAM_CONFIG, base64::encode_config(json, base64::URL_SAFE))]
+    };
+
+    if let Some(key) = options.signing_key.as_deref().filter(|key| !key.is_empty()) {
+        let signature = mac(key, &result[0].1)?.finalize().into_bytes();
+        result.push((
+            PARAM_SIGNATURE,
+            base64::encode_config(signature, base64::URL_SAFE),
+        ));
+    }
+
+    Ok(result)
+}
+
+/// Decode settings from query parameters, returns `None` if no configuration was present.
+pub fn decode<I, K, V>(params: I, key: Option<&str>) -> anyhow::Result<Option<Shared>>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: Into<String>,
+{
+    let mut config = None;
+    let mut signature = None;
+
+    for (k, v) in params {
+        match k.as_ref() {
+            PARAM_CONFIG => config = Some((false, v.into())),
+            PARAM_COMPRESSED => config = Some((true, v.into())),
+            PARAM_SIGNATURE => signature = Some(v.into()),
+            _ => {}
+        }
+    }
+
+    let (compressed, value) = match config {
+        Some(config) => config,
+        None => return Ok(None),
+    };
+
+    let verification = match (signature, key.filter(|key| !key.is_empty())) {
+        (None, _) => Verification::Unsigned,
+        (Some(_), None) => Verification::NoKey,
+        (Some(signature), Some(key)) => {
+            // a malformed signature is just as bad as a wrong one, we still let the user decide
+            match base64::decode_config(&signature, base64::URL_SAFE) {
+                Ok(signature) => match mac(key, &value)?.verify_slice(&signature) {
+                    Ok(()) => Verification::Verified,
+                    Err(_) => Verification::Failed,
+                },
+                Err(err) => {
+                    log::info!("Failed to decode signature: {err}");
+                    Verification::Failed
+                }
+            }
+        }
+    };
+
+    let data = base64::decode_config(&value, base64::URL_SAFE)
+        .map_err(|err| anyhow!("Failed to decode base64 encoding: {err} was: {value}"))?;
+
+    let json = if compressed {
+        let mut json = Vec::new();
+        DeflateDecoder::new(data.as_slice())
+            .take(MAX_CONFIG_SIZE as u64 + 1)
+            .read_to_end(&mut json)
+            .context("Failed to decompress configuration")?;
+        if json.len() > MAX_CONFIG_SIZE {
+            bail!("Decompressed configuration exceeds {MAX_CONFIG_SIZE} bytes");
+        }
+        json
+    } else {
+        data
+    };
+
+    let settings = serde_json::from_slice(&json).map_err(|err| {
+        anyhow!(
+            "Failed to parse provided configuration: {err} was: {:?}",
+            String::from_utf8(json)
+        )
+    })?;
+
+    Ok(Some(Shared {
+        settings,
+        verification,
+    }))
+}
+
+/// Remove all credentials, and let the importing user know they need to provide them.
+fn strip_credentials(settings: &mut Settings) {
+    let stripped = match &mut settings.target {
+        Target::Mqtt { credentials, .. }
+        | Target::Http { credentials, .. }
+        | Target::Coap { credentials, .. }
+        | Target::WebSocket { credentials, .. }
+        | Target::ServerSentEvents { credentials, .. } => !matches!(
+            std::mem::replace(credentials, Credentials::None),
+            Credentials::None
+        ),
+        Target::Local => false,
+    };
+
+    if stripped {
+        settings.import = Some(Import {
+            hint_connection: true,
+            hint_text: Some(STRIPPED_CREDENTIALS_HINT.to_string()),
+        });
+    }
+}
+
+fn mac(key: &str, value: &str) -> anyhow::Result<HmacSha256> {
+    let mut mac = HmacSha256::new_from_slice(key.as_bytes())
+        .map_err(|err| anyhow!("Invalid signing key: {err}"))?;
+    mac.update(value.as_bytes());
+    Ok(mac)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_roundtrip(options: ShareOptions, key: Option<&str>, verification: Verification) {
+        let settings = Settings::default();
+        let params = encode(&settings, &options).unwrap();
+        let shared = decode(params, key).unwrap().unwrap();
+
+        assert_eq!(shared.settings, settings);
+        assert_eq!(shared.verification, verification);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        assert_roundtrip(
+            ShareOptions {
+                compress: false,
+                ..Default::default()
+            },
+            None,
+            Verification::Unsigned,
+        );
+        assert_roundtrip(Default::default(), None, Verification::Unsigned);
+    }
+
+    #[test]
+    fn test_legacy() {
+        let json = serde_json::to_vec(&Settings::default()).unwrap();
+        let params = vec![("c", base64::encode_config(json, base64::URL_SAFE))];
+
+        let shared = decode(params, None).unwrap().unwrap();
+        assert_eq!(shared.settings, Settings::default());
+    }
+
+    #[test]
+    fn test_none() {
+        let params: Vec<(&str, String)> = vec![("foo", "bar".into())];
+        assert_eq!(decode(params, None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_compressed_is_smaller() {
+        let settings = Settings::default();
+        let plain = encode(
+            &settings,
+            &ShareOptions {
+                compress: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let compressed = encode(&settings, &Default::default()).unwrap();
+
+        assert!(compressed[0].1.len() < plain[0].1.len());
+    }
+
+    #[test]
+    fn test_decompression_limit() {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&vec![b' '; MAX_CONFIG_SIZE + 1]).unwrap();
+        let data = encoder.finish().unwrap();
+        let params = vec![("z", base64::encode_config(data, base64::URL_SAFE))];
+
+        assert!(decode(params, None).is_err());
+    }
+
+    #[test]
+    fn test_signed() {
+        let options = ShareOptions {
+            signing_key: Some("secret".into()),
+            ..Default::default()
+        };
+
+        assert_roundtrip(options.clone(), Some("secret"), Verification::Verified);
+        assert_roundtrip(options.clone(), Some("other"), Verification::Failed);
+        assert_roundtrip(options, None, Verification::NoKey);
+    }
+
+    #[test]
+    fn test_tampered() {
+        let options = ShareOptions {
+            compress: false,
+            signing_key: Some("secret".into()),
+            ..Default::default()
+        };
+
+        let mut params = encode(&Settings::default(), &options).unwrap();
+
+        let mut settings = Settings::default();
+        settings.device = "other-device".into();
+        let json = serde_json::to_vec(&settings).unwrap();
+        params[0].1 = base64::encode_config(json, base64::URL_SAFE);
+
+        let shared = decode(params, Some("secret")).unwrap().unwrap();
+        assert_eq!(shared.settings.device, "other-device");
+        assert_eq!(shared.verification, Verification::Failed);
+    }
+
+    #[test]
+    fn test_malformed_signature() {
+        let options = ShareOptions {
+            signing_key: Some("secret".into()),
+            ..Default::default()
+        };
+
+        let mut params = encode(&Settings::default(), &options).unwrap();
+        params[1].1 = "not*base64!".into();
+
+        let shared = decode(params.clone(), Some("secret")).unwrap().unwrap();
+        assert_eq!(shared.settings, Settings::default());
+        assert_eq!(shared.verification, Verification::Failed);
+
+        // without a key, we can't tell
+        let shared = decode(params, None).unwrap().unwrap();
+        assert_eq!(shared.verification, Verification::NoKey);
+    }
+
+    #[test]
+    fn test_strip_credentials() {
+        let options = ShareOptions {
+            strip_credentials: true,
+            ..Default::default()
+        };
+
+        let params = encode(&Settings::default(), &options).unwrap();
+        let shared = decode(params, None).unwrap().unwrap();
+
+        match shared.settings.target {
+            Target::Mqtt { credentials, .. } => assert_eq!(credentials, Credentials::None),
+            _ => panic!("Unexpected target"),
+        }
+        assert!(shared.settings.import.unwrap().hint_connection);
+    }
+}
@@ -0,0 +1,398 @@
+//! The simulator as a yew agent, running the engine in the browser.
+
+use crate::{
+    data::{self, SharedDataBridge, SharedDataOps},
+    settings::{Settings, Target},
+    simulator::{
+        coap::CoapConnector,
+        engine::{self, Engine, Output},
+        local::LocalConnector,
+        mqtt::{MqttConnector, Topics},
+        simulations::SimulationState,
+        sse::ServerSentEventsConnector,
+        websocket::WebSocketConnector,
+        Command, Connector, ConnectorFactory, ConnectorOptions, Event, InternalState, SimulatorId,
+        SimulatorState,
+    },
+    utils::handler::Handler,
+};
+use anyhow::bail;
+use std::{
+    collections::{btree_map::Entry, BTreeMap, HashSet},
+    ops::{Deref, DerefMut},
+    rc::Rc,
+};
+use yew::{html::Scope, Callback, Component};
+use yew_agent::*;
+
+/// The connectors available in the browser.
+pub struct WebConnectors;
+
+impl ConnectorFactory for WebConnectors {
+    fn create(
+        &self,
+        settings: &Settings,
+        on_command: Handler<Command>,
+        on_connection_lost: Handler<String>,
+    ) -> anyhow::Result<Box<dyn Connector>> {
+        match &settings.target {
+            Target::Mqtt {
+                url,
+                credentials,
+                topics,
+                ..
+            } => Ok(Box::new(MqttConnector::new(
+                ConnectorOptions {
+                    url,
+                    credentials,
+                    settings,
+                    on_command,
+                    on_connection_lost,
+                },
+                Topics::new(topics, settings)?,
+            ))),
+            Target::Coap { url, credentials } => {
+                Ok(Box::new(CoapConnector::new(ConnectorOptions {
+                    url,
+                    credentials,
+                    settings,
+                    on_command,
+                    on_connection_lost,
+                })))
+            }
+            Target::WebSocket {
+                url,
+                credentials,
+                protocol,
+                framing,
+            } => Ok(Box::new(WebSocketConnector::new(
+                ConnectorOptions {
+                    url,
+                    credentials,
+                    settings,
+                    on_command,
+                    on_connection_lost,
+                },
+                protocol.clone(),
+                framing.clone(),
+            ))),
+            Target::ServerSentEvents {
+                url,
+                commands,
+                credentials,
+                framing,
+            } => Ok(Box::new(ServerSentEventsConnector::new(
+                ConnectorOptions {
+                    url,
+                    credentials,
+                    settings,
+                    on_command,
+                    on_connection_lost,
+                },
+                commands,
+                framing.clone(),
+            ))),
+            Target::Local => Ok(Box::new(LocalConnector::new(on_command))),
+            // FIXME: implement HTTP too
+            target => bail!("Unsupported target: {}", target.as_protocol()),
+        }
+    }
+}
+
+pub struct Simulator {
+    link: AgentLink<Self>,
+    subscribers: HashSet<HandlerId>,
+
+    settings_agent: SharedDataBridge<Settings>,
+    engine: Engine,
+
+    sim_subs: BTreeMap<SimulatorId, Vec<HandlerId>>,
+    internal_subs: Vec<HandlerId>,
+}
+
+#[derive(Debug)]
+pub enum Msg {
+    Settings(Box<Settings>),
+    Engine(engine::Msg),
+    Output(Output),
+}
+
+pub enum Request {
+    Start,
+    Stop,
+    Publish { channel: String, payload: Vec<u8> },
+    FetchCommandHistory,
+    FetchEventHistory,
+    SubscribeSimulation(String),
+    UnsubscribeSimulation(String),
+    SubscribeInternalState,
+    UnsubscribeInternalState,
+    PauseSimulation(String),
+    ResumeSimulation(String),
+    StepSimulation(String),
+}
+
+pub enum Response {
+    State(SimulatorState),
+    SimulationState(SimulationState),
+    Command(Rc<Command>),
+    CommandHistory(Vec<Command>),
+    Event(Rc<Event>),
+    EventHistory(Vec<Event>),
+    InternalState(InternalState),
+}
+
+impl Agent for Simulator {
+    type Reach = Context<Self>;
+    type Message = Msg;
+    type Input = Request;
+    type Output = Response;
+
+    fn create(link: AgentLink<Self>) -> Self {
+        log::info!("Created new simulator");
+
+        let mut settings_agent = SharedDataBridge::new(link.callback(|response| match response {
+            data::Response::State(settings) => Msg::Settings(Box::new(settings)),
+        }));
+        settings_agent.request_state();
+
+        let engine = Engine::new(
+            {
+                let link = link.clone();
+                Handler::from(move |msg| link.send_message(Msg::Engine(msg)))
+            },
+            {
+                let link = link.clone();
+                Handler::from(move |output| link.send_message(Msg::Output(output)))
+            },
+            WebConnectors,
+        );
+
+        Self {
+            link,
+            subscribers: HashSet::new(),
+            settings_agent,
+            engine,
+            sim_subs: Default::default(),
+            internal_subs: Default::default(),
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) {
+        match msg {
+            Msg::Settings(settings) => {
+                self.engine.update_settings(*settings);
+            }
+            Msg::Engine(msg) => {
+                self.engine.update(msg);
+            }
+            Msg::Output(output) => {
+                self.output(output);
+            }
+        }
+    }
+
+    fn connected(&mut self, id: HandlerId) {
+        if id.is_respondable() {
+            self.subscribers.insert(id);
+            self.link
+                .respond(id, Response::State(self.engine.state().clone()));
+        }
+    }
+
+    fn handle_input(&mut self, msg: Self::Input, id: HandlerId) {
+        match msg {
+            Request::Start => {
+                self.engine.start();
+            }
+            Request::Stop => {
+                self.engine.stop();
+            }
+            Request::Publish { channel, payload } => {
+                self.engine.publish_raw(&channel, payload);
+            }
+            Request::FetchCommandHistory => {
+                if id.is_respondable() {
+                    self.link.respond(
+                        id,
+                        Response::CommandHistory(self.engine.commands().to_vec()),
+                    );
+                }
+            }
+            Request::FetchEventHistory => {
+                if id.is_respondable() {
+                    self.link
+                        .respond(id, Response::EventHistory(self.engine.events().to_vec()));
+                }
+            }
+            Request::SubscribeSimulation(sim_id) if id.is_respondable() => {
+                if let Some(state) = self.engine.simulation_state(&sim_id) {
+                    self.link
+                        .respond(id, Response::SimulationState(state.clone()));
+                }
+
+                match self.sim_subs.entry(sim_id) {
+                    Entry::Occupied(mut e) => {
+                        e.get_mut().push(id);
+                    }
+                    Entry::Vacant(e) => {
+                        e.insert(vec![id]);
+                    }
+                }
+            }
+            Request::SubscribeSimulation(_) => {}
+            Request::UnsubscribeSimulation(sim_id) => match self.sim_subs.entry(sim_id) {
+                Entry::Occupied(mut e) => {
+                    e.get_mut().retain(|i| i != &id);
+                    if e.get().is_empty() {
+                        e.remove();
+                    }
+                }
+                Entry::Vacant(_) => {}
+            },
+            Request::SubscribeInternalState if id.is_respondable() => {
+                self.internal_subs.push(id);
+                self.link
+                    .respond(id, Response::InternalState(self.engine.data().clone()));
+            }
+            Request::SubscribeInternalState => {}
+            Request::UnsubscribeInternalState => {
+                self.internal_subs.retain(|i| i != &id);
+            }
+            Request::PauseSimulation(sim_id) => {
+                self.set_paused(sim_id, true);
+            }
+            Request::ResumeSimulation(sim_id) => {
+                self.set_paused(sim_id, false);
+            }
+            Request::StepSimulation(sim_id) => {
+                self.engine.step(&sim_id);
+            }
+        }
+    }
+
+    fn disconnected(&mut self, id: HandlerId) {
+        if id.is_respondable() {
+            self.subscribers.remove(&id);
+        }
+    }
+}
+
+impl Simulator {
+    /// Forward the output of the engine to the subscribers.
+    fn output(&self, output: Output) {
+        match output {
+            Output::State(state) => {
+                for id in &self.subscribers {
+                    self.link.respond(*id, Response::State(state.clone()));
+                }
+            }
+            Output::SimulationState(sim_id, state) => {
+                if let Some(subs) = self.sim_subs.get(&sim_id) {
+                    for id in subs {
+                        self.link
+                            .respond(*id, Response::SimulationState(state.clone()));
+                    }
+                }
+            }
+            Output::InternalState(data) => {
+                for id in &self.internal_subs {
+                    self.link
+                        .respond(*id, Response::InternalState(data.clone()));
+                }
+            }
+            Output::Command(command) => {
+                for id in &self.subscribers {
+                    self.link.respond(*id, Response::Command(command.clone()));
+                }
+            }
+            Output::Event(event) => {
+                for id in &self.subscribers {
+                    self.link.respond(*id, Response::Event(event.clone()));
+                }
+            }
+        }
+    }
+
+    /// Persist the paused state of a simulation, which will then be applied with the new settings.
+    fn set_paused(&mut self, id: SimulatorId, paused: bool) {
+        self.settings_agent.update(move |settings| {
+            if let Some(entry) = settings.simulations.get_mut(&id) {
+                entry.paused = paused;
+            }
+        });
+    }
+}
+
+pub struct SimulatorBridge(Box<dyn Bridge<Simulator>>);
+
+impl SimulatorBridge {
+    pub fn new(callback: Callback<Response>) -> SimulatorBridge {
+        Self(Simulator::bridge(callback))
+    }
+
+    pub fn from<C, F>(link: &Scope<C>, f: F) -> Self
+    where
+        C: Component,
+        F: Fn(SimulatorState) -> C::Message + 'static,
+    {
+        let callback = link.batch_callback(move |msg| match msg {
+            Response::State(data) => vec![f(data)],
+            _ => vec![],
+        });
+        Self::new(callback)
+    }
+
+    pub fn subscribe_simulation(&mut self, id: String) {
+        self.send(Request::SubscribeSimulation(id));
+    }
+
+    pub fn unsubscribe_simulation(&mut self, id: String) {
+        self.send(Request::UnsubscribeSimulation(id));
+    }
+
+    pub fn pause_simulation(&mut self, id: String) {
+        self.send(Request::PauseSimulation(id));
+    }
+
+    pub fn resume_simulation(&mut self, id: String) {
+        self.send(Request::ResumeSimulation(id));
+    }
+
+    pub fn step_simulation(&mut self, id: String) {
+        self.send(Request::StepSimulation(id));
+    }
+
+    pub fn start(&mut self) {
+        self.send(Request::Start);
+    }
+
+    pub fn stop(&mut self) {
+        self.send(Request::Stop);
+    }
+
+    pub fn publish<C, P>(&mut self, channel: C, payload: P)
+    where
+        C: Into<String>,
+        P: Into<Vec<u8>>,
+    {
+        self.send(Request::Publish {
+            channel: channel.into(),
+            payload: payload.into(),
+        })
+    }
+}
+
+impl Deref for SimulatorBridge {
+    type Target = Box<dyn Bridge<Simulator>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for SimulatorBridge {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
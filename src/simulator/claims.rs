@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use std::ops::Deref;
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq)]
@@ -98,9 +99,36 @@ impl Claim {
     }
 }
 
+impl Display for Claim {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Channel { channel } => write!(f, "{channel}"),
+            Self::Feature { channel, feature } => write!(f, "{channel}/{feature}"),
+            Self::Property {
+                channel,
+                feature,
+                property,
+            } => write!(f, "{channel}/{feature}/{property}"),
+        }
+    }
+}
+
+/// Get the inputs which are targeted by the claims of the same simulation.
+///
+/// A simulation must not publish to the properties it reads from, as it would feed itself.
+pub fn conflicting_inputs(claims: &[Claim], inputs: &[Claim]) -> Vec<Claim> {
+    inputs
+        .iter()
+        .filter(|input| claims.iter().any(|claim| claim.overlap(input)))
+        .cloned()
+        .collect()
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct Claims {
     claims: HashMap<String, Vec<Claim>>,
+    /// What simulations read from, e.g. the inputs of a formula.
+    inputs: HashMap<String, Vec<Claim>>,
 }
 
 impl Deref for Claims {
@@ -116,8 +144,25 @@ impl Claims {
         self.claims.insert(id, claims);
     }
 
+    pub fn insert_inputs(&mut self, id: String, inputs: Vec<Claim>) {
+        if inputs.is_empty() {
+            self.inputs.remove(&id);
+        } else {
+            self.inputs.insert(id, inputs);
+        }
+    }
+
     pub fn remove(&mut self, id: &str) {
         self.claims.remove(id);
+        self.inputs.remove(id);
+    }
+
+    /// The inputs of a simulation, which are targeted by its own claims.
+    pub fn conflicting_inputs(&self, id: &str) -> Vec<Claim> {
+        match (self.claims.get(id), self.inputs.get(id)) {
+            (Some(claims), Some(inputs)) => conflicting_inputs(claims, inputs),
+            _ => vec![],
+        }
     }
 
     fn contains(&self, check_claim: &Claim, exclude: Option<&str>) -> bool {
@@ -184,4 +229,38 @@ mod test {
         assert!(state.is_claimed_any(claims.clone(), None));
         assert!(!state.is_claimed_any(claims, Some("sim1")));
     }
+
+    #[test]
+    fn test_conflicting_inputs() {
+        let mut state = Claims::default();
+        state.insert(
+            "sim1".to_string(),
+            SingleTarget::new("state", "power", "watts").claims(),
+        );
+        state.insert_inputs(
+            "sim1".to_string(),
+            SingleTarget::new("state", "power", "voltage").claims(),
+        );
+        assert!(state.conflicting_inputs("sim1").is_empty());
+
+        // reading from the own feature
+        state.insert(
+            "sim1".to_string(),
+            FeatureTarget::new("state", "power").claims(),
+        );
+        assert_eq!(
+            state.conflicting_inputs("sim1"),
+            SingleTarget::new("state", "power", "voltage").claims()
+        );
+
+        // reading from others is fine
+        state.insert(
+            "sim2".to_string(),
+            SingleTarget::new("state", "power", "voltage").claims(),
+        );
+        assert!(state.conflicting_inputs("sim2").is_empty());
+
+        state.remove("sim1");
+        assert!(state.conflicting_inputs("sim1").is_empty());
+    }
 }
@@ -0,0 +1,267 @@
+//! Structured mode CloudEvents, using the JSON event format.
+
+use crate::simulator::Command;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const SPEC_VERSION: &str = "1.0";
+
+/// Prefix of the type of events, followed by the channel.
+pub const EVENT_TYPE_PREFIX: &str = "io.drogue.simulator.";
+
+/// Prefix of the type of commands, followed by the command name, if it isn't the subject.
+pub const COMMAND_TYPE_PREFIX: &str = "io.drogue.command.";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CloudEvent {
+    #[serde(rename = "specversion")]
+    pub spec_version: String,
+    pub id: String,
+    pub source: String,
+    pub r#type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    /// The time, as RFC 3339 timestamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+    #[serde(
+        rename = "datacontenttype",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub data_content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_base64: Option<String>,
+}
+
+/// The source of events, identifying the device.
+pub fn source(application: &str, device: &str) -> String {
+    format!(
+        "{}/{}",
+        urlencoding::encode(application),
+        urlencoding::encode(device)
+    )
+}
+
+impl CloudEvent {
+    /// Create an event, published by a device on a channel.
+    pub fn new(
+        application: &str,
+        device: &str,
+        channel: &str,
+        time: DateTime<Utc>,
+        payload: &[u8],
+    ) -> Self {
+        let (data_content_type, data, data_base64) = match serde_json::from_slice(payload) {
+            Ok(json) => ("application/json", Some(json), None),
+            Err(_) => (
+                "application/octet-stream",
+                None,
+                Some(base64::encode(payload)),
+            ),
+        };
+
+        Self {
+            spec_version: SPEC_VERSION.into(),
+            id: uuid::Uuid::new_v4().to_string(),
+            source: source(application, device),
+            r#type: format!("{EVENT_TYPE_PREFIX}{channel}"),
+            subject: Some(channel.into()),
+            time: Some(time.to_rfc3339_opts(SecondsFormat::Millis, true)),
+            data_content_type: Some(data_content_type.into()),
+            data,
+            data_base64,
+        }
+    }
+
+    /// Convert into a command, returns `None` if the data can't be decoded.
+    ///
+    /// The name of the command is the subject, or else the type, stripped of the command prefix.
+    pub fn into_command(self) -> Option<Command> {
+        let name = match self.subject {
+            Some(subject) => subject,
+            None => self
+                .r#type
+                .strip_prefix(COMMAND_TYPE_PREFIX)
+                .map(ToString::to_string)
+                .unwrap_or(self.r#type),
+        };
+
+        let payload = match (self.data, self.data_base64) {
+            (_, Some(data)) => Some(base64::decode(data).ok()?),
+            (Some(Value::String(text)), None) if !is_json(self.data_content_type.as_deref()) => {
+                Some(text.into_bytes())
+            }
+            (Some(json), None) => Some(serde_json::to_vec(&json).ok()?),
+            (None, None) => None,
+        };
+
+        Some(Command { name, payload })
+    }
+}
+
+/// Check if the content type is JSON, which is assumed if it is missing.
+fn is_json(content_type: Option<&str>) -> bool {
+    match content_type {
+        None => true,
+        Some(content_type) => {
+            let content_type = content_type.split(';').next().unwrap_or_default().trim();
+            content_type == "application/json" || content_type.ends_with("+json")
+        }
+    }
+}
+
+/// Wrap the payload of an event into a structured mode CloudEvent.
+pub fn wrap(
+    application: &str,
+    device: &str,
+    channel: &str,
+    time: DateTime<Utc>,
+    payload: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    Ok(serde_json::to_vec(&CloudEvent::new(
+        application,
+        device,
+        channel,
+        time,
+        payload,
+    ))?)
+}
+
+/// Unwrap a command, received as a structured mode CloudEvent.
+///
+/// Returns `None` if the payload isn't a CloudEvent.
+pub fn unwrap(command: &Command) -> Option<Command> {
+    let payload = command.payload.as_deref()?;
+    let event: CloudEvent = serde_json::from_slice(payload).ok()?;
+    if event.spec_version != SPEC_VERSION {
+        return None;
+    }
+    event.into_command()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn command(payload: Value) -> Command {
+        Command {
+            name: "cmd".into(),
+            payload: Some(serde_json::to_vec(&payload).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_wrap() {
+        let time = Utc.ymd(2022, 1, 2).and_hms(3, 4, 5);
+
+        let event: Value = serde_json::from_slice(
+            &wrap("my app", "device1", "state", time, br#"{"features":{}}"#).unwrap(),
+        )
+        .unwrap();
+        assert!(event["id"].as_str().is_some());
+        assert_eq!(
+            event,
+            json!({
+                "specversion": "1.0",
+                "id": event["id"],
+                "source": "my%20app/device1",
+                "type": "io.drogue.simulator.state",
+                "subject": "state",
+                "time": "2022-01-02T03:04:05.000Z",
+                "datacontenttype": "application/json",
+                "data": {"features": {}},
+            })
+        );
+
+        let event: Value =
+            serde_json::from_slice(&wrap("app", "device", "raw", time, &[0xff]).unwrap()).unwrap();
+        assert_eq!(event["datacontenttype"], json!("application/octet-stream"));
+        assert_eq!(event["data_base64"], json!("/w=="));
+        assert_eq!(event.get("data"), None);
+    }
+
+    #[test]
+    fn test_unwrap() {
+        assert_eq!(
+            unwrap(&command(json!({
+                "specversion": "1.0",
+                "id": "1",
+                "source": "app/device",
+                "type": "io.drogue.command.v1",
+                "subject": "set-value",
+                "datacontenttype": "application/json",
+                "data": {"value": true},
+            }))),
+            Some(Command {
+                name: "set-value".into(),
+                payload: Some(br#"{"value":true}"#.to_vec()),
+            })
+        );
+        assert_eq!(
+            unwrap(&command(json!({
+                "specversion": "1.0",
+                "id": "1",
+                "source": "app",
+                "type": "io.drogue.command.reboot",
+            }))),
+            Some(Command {
+                name: "reboot".into(),
+                payload: None,
+            })
+        );
+        assert_eq!(
+            unwrap(&command(json!({
+                "specversion": "1.0",
+                "id": "1",
+                "source": "app",
+                "type": "say",
+                "datacontenttype": "text/plain",
+                "data": "hi",
+            }))),
+            Some(Command {
+                name: "say".into(),
+                payload: Some(b"hi".to_vec()),
+            })
+        );
+        assert_eq!(
+            unwrap(&command(json!({
+                "specversion": "1.0",
+                "id": "1",
+                "source": "app",
+                "type": "raw",
+                "data_base64": "/w==",
+            }))),
+            Some(Command {
+                name: "raw".into(),
+                payload: Some(vec![0xff]),
+            })
+        );
+    }
+
+    #[test]
+    fn test_not_a_cloud_event() {
+        assert_eq!(unwrap(&command(json!({"value": true}))), None);
+        assert_eq!(
+            unwrap(&command(json!({
+                "specversion": "0.3",
+                "id": "1",
+                "source": "app",
+                "type": "say",
+            }))),
+            None
+        );
+        assert_eq!(
+            unwrap(&Command {
+                name: "cmd".into(),
+                payload: None
+            }),
+            None
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use super::{Event, Session};
+use crate::connector::{
+    coap::{self, Message},
+    mqtt::QoS,
+    socket::{close_reason, str_err, Socket},
+};
+use crate::simulator::{Command, ConnectOptions, Connector, ConnectorOptions, SubscribeOptions};
+use crate::utils::handler::Handler;
+use std::{cell::RefCell, rc::Rc};
+use web_sys::{BinaryType, CloseEvent, MessageEvent, WebSocket};
+
+/// Send telemetry and receive commands using CoAP over WebSockets.
+pub struct CoapConnector {
+    url: String,
+    session: Rc<RefCell<Session>>,
+
+    on_command: Handler<Command>,
+    on_connection_lost: Handler<String>,
+
+    subscription: Rc<RefCell<Option<SubscribeOptions>>>,
+    socket: Option<Socket>,
+}
+
+fn send(socket: &WebSocket, message: &Message) {
+    match message.encode() {
+        Ok(data) => {
+            if let Err(err) = socket.send_with_u8_array(&data) {
+                log::info!("Failed to send: {err:?}");
+            }
+        }
+        Err(err) => log::info!("Failed to encode message: {err}"),
+    }
+}
+
+impl CoapConnector {
+    pub fn new(opts: ConnectorOptions) -> Self {
+        let authorization = opts.credentials.to_authorization(opts.settings);
+
+        Self {
+            url: opts.url.to_string(),
+            session: Rc::new(RefCell::new(Session::new(authorization))),
+            on_command: opts.on_command,
+            on_connection_lost: opts.on_connection_lost,
+            subscription: Default::default(),
+            socket: None,
+        }
+    }
+}
+
+impl Connector for CoapConnector {
+    fn connect(&mut self, opts: ConnectOptions) -> anyhow::Result<()> {
+        let socket = WebSocket::new_with_str(&self.url, coap::SUBPROTOCOL).map_err(str_err)?;
+        socket.set_binary_type(BinaryType::Arraybuffer);
+
+        let on_open = {
+            let socket = socket.clone();
+            let session = self.session.clone();
+            move || send(&socket, &session.borrow().csm())
+        };
+
+        let on_message = {
+            let socket = socket.clone();
+            let session = self.session.clone();
+            let subscription = self.subscription.clone();
+            let on_success = opts.on_success;
+            let on_command = self.on_command.clone();
+            let on_connection_lost = self.on_connection_lost.clone();
+            move |event: MessageEvent| {
+                let data = js_sys::Uint8Array::new(&event.data()).to_vec();
+                let message = match Message::decode(&data) {
+                    Ok(message) => message,
+                    Err(err) => {
+                        log::info!("Ignoring invalid message: {err}");
+                        return;
+                    }
+                };
+
+                let event = session.borrow_mut().handle(message);
+                match event {
+                    Some(Event::Established) => on_success.emit(()),
+                    Some(Event::Reply(reply)) => send(&socket, &reply),
+                    Some(Event::Subscribed) => {
+                        if let Some(opts) = subscription.borrow_mut().take() {
+                            opts.on_success.emit(());
+                        }
+                    }
+                    Some(Event::SubscribeFailed(err)) => {
+                        if let Some(opts) = subscription.borrow_mut().take() {
+                            opts.on_failure.emit(err);
+                        }
+                    }
+                    Some(Event::Command(command)) => on_command.emit(command),
+                    Some(Event::Closed(reason)) => on_connection_lost.emit(reason),
+                    None => {}
+                }
+            }
+        };
+
+        let on_close = {
+            let session = self.session.clone();
+            let on_failure = opts.on_failure;
+            let on_connection_lost = self.on_connection_lost.clone();
+            move |event: CloseEvent| {
+                let reason = close_reason(&event);
+                // a close before the session was established is a failure to connect
+                if session.borrow().is_established() {
+                    on_connection_lost.emit(reason);
+                } else {
+                    on_failure.emit(reason);
+                }
+            }
+        };
+
+        self.socket = Some(Socket::new(socket, on_open, on_message, on_close));
+
+        Ok(())
+    }
+
+    fn subscribe(&mut self, opts: SubscribeOptions) -> anyhow::Result<()> {
+        let socket = match &self.socket {
+            Some(socket) => socket.socket(),
+            None => anyhow::bail!("Not connected"),
+        };
+
+        self.subscription.replace(Some(opts));
+        send(socket, &self.session.borrow_mut().observe());
+        Ok(())
+    }
+
+    fn publish(&mut self, channel: &str, payload: Vec<u8>, _qos: QoS) {
+        let socket = match self.socket.as_ref().and_then(Socket::open) {
+            Some(socket) => socket,
+            None => return,
+        };
+
+        send(socket, &self.session.borrow_mut().publish(channel, payload));
+    }
+}
@@ -0,0 +1,1347 @@
+//! The simulation engine, independent of the frontend running it.
+
+use crate::{
+    connector::mqtt::QoS,
+    runtime::Timeout,
+    settings::{Settings, SimulationEntry, Target},
+    simulator::{
+        cloudevents, homeassistant,
+        lorawan::{self, Uplink},
+        mqtt::Topics,
+        publish::{ChannelState, PublishEvent},
+        reconcile::{self, Action},
+        simulations::{
+            self, Control, SimulationDescription, SimulationFactory, SimulationHandler,
+            SimulationState,
+        },
+        Command, ConnectOptions, Connector, ConnectorFactory, Event, InternalState, Schedule,
+        SimulatorId, SimulatorState, State, SubscribeOptions,
+    },
+    utils::handler::Handler,
+};
+use chrono::{DateTime, Utc};
+use num_traits::ToPrimitive;
+use std::{
+    cell::Cell,
+    collections::{btree_map::Entry, BTreeMap, HashMap, HashSet},
+    rc::Rc,
+};
+
+/// Messages driving the engine.
+///
+/// The engine sends these to itself, using the link provided when creating it. The owner of the
+/// engine must feed them back into [`Engine::update`].
+#[derive(Debug)]
+pub enum Msg {
+    Connected,
+    Subscribed,
+    Disconnected(String),
+    Command(Command),
+    PublishEvent(PublishEvent),
+    SimulationState(SimulatorId, SimulationState),
+    Schedule,
+    Control(SimulatorId, Control),
+}
+
+/// Changes reported by the engine.
+#[derive(Debug)]
+pub enum Output {
+    State(SimulatorState),
+    SimulationState(SimulatorId, SimulationState),
+    InternalState(InternalState),
+    Command(Rc<Command>),
+    Event(Rc<Event>),
+}
+
+#[derive(Clone, Debug)]
+struct PowerLoss {
+    /// The simulation which reported the power loss.
+    source: SimulatorId,
+    /// If the simulator was connected when the power was lost.
+    reconnect: bool,
+}
+
+pub struct Engine {
+    link: Handler<Msg>,
+    output: Handler<Output>,
+    connectors: Box<dyn ConnectorFactory>,
+
+    state: SimulatorState,
+    settings: Settings,
+
+    connector: Option<Box<dyn Connector>>,
+    commands: Vec<Command>,
+    events: Vec<Event>,
+
+    simulations: HashMap<SimulatorId, Box<dyn SimulationHandler>>,
+    data: InternalState,
+
+    /// Simulations which are currently started.
+    started: HashSet<SimulatorId>,
+    /// Schedules, together with their origin.
+    schedules: BTreeMap<SimulatorId, (Schedule, DateTime<Utc>)>,
+    schedule_timer: Option<Timeout>,
+
+    /// Number of events published by simulations.
+    published: Rc<Cell<u64>>,
+    /// An active power loss, emulating a dead device.
+    power_loss: Option<PowerLoss>,
+    /// Frame counter of LoRaWAN uplinks, restarting with every connection.
+    f_cnt: u32,
+
+    sim_states: BTreeMap<SimulatorId, SimulationState>,
+}
+
+impl Engine {
+    pub fn new<F>(link: Handler<Msg>, output: Handler<Output>, connectors: F) -> Self
+    where
+        F: ConnectorFactory + 'static,
+    {
+        Self {
+            link,
+            output,
+            connectors: Box::new(connectors),
+            state: Default::default(),
+            settings: Default::default(),
+            connector: None,
+            commands: vec![],
+            events: vec![],
+            simulations: Default::default(),
+            data: Default::default(),
+            started: Default::default(),
+            schedules: Default::default(),
+            schedule_timer: None,
+            published: Default::default(),
+            power_loss: None,
+            f_cnt: 0,
+            sim_states: Default::default(),
+        }
+    }
+
+    pub fn state(&self) -> &SimulatorState {
+        &self.state
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// All commands received so far.
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    /// All events published so far.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// The current state of the device, as published by the simulations.
+    pub fn data(&self) -> &InternalState {
+        &self.data
+    }
+
+    pub fn simulation_state(&self, id: &str) -> Option<&SimulationState> {
+        self.sim_states.get(id)
+    }
+
+    pub fn update(&mut self, msg: Msg) {
+        log::debug!("update: {msg:?}");
+        match msg {
+            Msg::Connected => {
+                self.state.state = State::Subscribing;
+                self.send_state();
+                if let Some(connector) = &mut self.connector {
+                    if let Err(err) = connector.subscribe(SubscribeOptions {
+                        on_success: self.link.reform(|_| Msg::Subscribed),
+                        on_failure: self.link.reform(Msg::Disconnected),
+                    }) {
+                        log::warn!("Failed to subscribe: {err}");
+                    };
+                }
+            }
+            Msg::Subscribed => {
+                self.state.state = State::Connected;
+                self.send_state();
+                self.publish_discovery();
+            }
+            Msg::Disconnected(err) => {
+                self.state.state = State::Failed(err);
+                self.send_state();
+            }
+            Msg::Command(command) => {
+                let command = if self.settings.payload.cloud_events {
+                    cloudevents::unwrap(&command).unwrap_or(command)
+                } else {
+                    command
+                };
+                let commands = match &self.settings.payload.lorawan {
+                    Some(lorawan) => lorawan.downlinks(&command).unwrap_or_else(|| vec![command]),
+                    None => vec![command],
+                };
+                for command in commands {
+                    self.command(command);
+                }
+            }
+            Msg::PublishEvent(event) => {
+                self.publish(event);
+            }
+            Msg::Schedule => {
+                self.evaluate_schedules();
+                self.send_state();
+            }
+            Msg::Control(id, control) => {
+                self.control(id, control);
+            }
+            Msg::SimulationState(id, state) => {
+                // update global description list
+
+                let changed = match self.state.simulations.entry(id.clone()) {
+                    Entry::Vacant(e) => {
+                        e.insert(state.description.clone());
+                        true
+                    }
+                    Entry::Occupied(mut e) => {
+                        if e.get() != &state.description {
+                            e.insert(state.description.clone());
+                            true
+                        } else {
+                            false
+                        }
+                    }
+                };
+
+                if changed {
+                    self.send_state();
+                }
+
+                // update subscriptions
+
+                self.sim_states.insert(id.clone(), state.clone());
+                self.output.emit(Output::SimulationState(id, state));
+            }
+        }
+    }
+
+    /// Start a single step of a paused simulation.
+    pub fn step(&mut self, id: &str) {
+        if let Some(sim) = self.simulations.get_mut(id) {
+            sim.step();
+        }
+    }
+
+    fn send_state(&self) {
+        log::debug!("Broadcast state: {:?}", self.state);
+        self.output.emit(Output::State(self.state.clone()));
+    }
+
+    fn send_internal_state(&self) {
+        self.output.emit(Output::InternalState(self.data.clone()));
+    }
+
+    fn add_generator(&mut self, id: String, generator: Box<dyn SimulationHandler>) -> SimulatorId {
+        // stop a previous instance
+
+        if self.started.remove(&id) {
+            if let Some(mut previous) = self.simulations.remove(&id) {
+                previous.stop();
+            }
+        }
+
+        // insert
+
+        self.state
+            .simulations
+            .insert(id.clone(), SimulationDescription { label: id.clone() });
+        self.state
+            .claims
+            .insert(id.clone(), generator.claims().to_vec());
+        self.state
+            .claims
+            .insert_inputs(id.clone(), generator.inputs().to_vec());
+        self.simulations.insert(id.clone(), generator);
+
+        // return handle
+
+        id
+    }
+
+    fn remove_generator(&mut self, id: &SimulatorId) {
+        self.state.simulations.remove(id);
+        self.state.claims.remove(id);
+        self.state.schedules.remove(id);
+        self.schedules.remove(id);
+
+        if let Some(mut generator) = self.simulations.remove(id) {
+            if self.started.remove(id) {
+                generator.stop()
+            }
+        }
+
+        if matches!(&self.power_loss, Some(power_loss) if &power_loss.source == id) {
+            self.control(id.clone(), Control::PowerRestored);
+        }
+    }
+
+    /// Handle control requests from simulations.
+    fn control(&mut self, id: SimulatorId, control: Control) {
+        match control {
+            Control::PowerLoss => {
+                if self.power_loss.is_some() {
+                    return;
+                }
+                log::info!("Power loss, reported by: {id}");
+                self.power_loss = Some(PowerLoss {
+                    source: id,
+                    reconnect: self.state.running,
+                });
+                self.evaluate_schedules();
+                if self.state.running {
+                    self.stop();
+                } else {
+                    self.send_state();
+                }
+            }
+            Control::PowerRestored => {
+                match &self.power_loss {
+                    Some(power_loss) if power_loss.source == id => {}
+                    _ => return,
+                }
+                log::info!("Power restored, reported by: {id}");
+                let reconnect = self
+                    .power_loss
+                    .take()
+                    .map(|power_loss| power_loss.reconnect)
+                    .unwrap_or_default();
+                self.evaluate_schedules();
+                if reconnect && !self.state.running {
+                    self.start();
+                } else {
+                    self.send_state();
+                }
+            }
+        }
+    }
+
+    /// Start or stop a generator, if it isn't in the requested state already.
+    fn set_started(&mut self, id: &SimulatorId, started: bool) {
+        let ctx = {
+            let state_id = id.clone();
+            let control_id = id.clone();
+            simulations::Context::new(
+                self.link.reform(Msg::PublishEvent),
+                self.link
+                    .reform(move |state| Msg::SimulationState(state_id.clone(), state)),
+                self.link
+                    .reform(move |control| Msg::Control(control_id.clone(), control)),
+                self.published.clone(),
+            )
+        };
+
+        if let Some(generator) = self.simulations.get_mut(id) {
+            match (started, self.started.contains(id)) {
+                (true, false) => {
+                    generator.start(ctx);
+                    generator.data(&self.data);
+                    self.started.insert(id.clone());
+                }
+                (false, true) => {
+                    generator.stop();
+                    self.started.remove(id);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Evaluate all schedules, start or stop generators, and set up a timer for the next transition.
+    fn evaluate_schedules(&mut self) {
+        self.schedule_timer.take();
+
+        let now = Utc::now();
+        let mut next: Option<DateTime<Utc>> = None;
+
+        let ids: Vec<_> = self.simulations.keys().cloned().collect();
+        for id in ids {
+            let state = match self.schedules.get(&id) {
+                Some((schedule, origin)) => match schedule.evaluate(*origin, now) {
+                    Ok(state) => Some(Ok(state)),
+                    Err(err) => {
+                        log::warn!("Failed to evaluate schedule of {id}: {err}");
+                        Some(Err(err.to_string()))
+                    }
+                },
+                None => None,
+            };
+
+            let active = match &state {
+                Some(Ok(state)) => {
+                    next = match (next, state.next) {
+                        (Some(a), Some(b)) => Some(a.min(b)),
+                        (a, b) => a.or(b),
+                    };
+                    state.active
+                }
+                Some(Err(_)) => false,
+                None => true,
+            };
+
+            // during a power loss, only the source of the power loss keeps running
+            let active = active
+                && self
+                    .power_loss
+                    .as_ref()
+                    .map(|power_loss| power_loss.source == id)
+                    .unwrap_or(true);
+
+            // a simulation targeting its own inputs would feed itself
+            let conflicts = self.state.claims.conflicting_inputs(&id);
+            if active && !conflicts.is_empty() {
+                log::warn!("Not starting {id}, it targets its own inputs: {conflicts:?}");
+            }
+            let active = active && conflicts.is_empty();
+
+            self.set_started(&id, active);
+
+            match state {
+                Some(state) => self.state.schedules.insert(id, state),
+                None => self.state.schedules.remove(&id),
+            };
+        }
+
+        if let Some(next) = next {
+            // internally this is a i32, so we cap it, and re-evaluate when necessary
+            let millis = (next - now)
+                .num_milliseconds()
+                .clamp(0, i32::MAX as i64)
+                .to_u32()
+                .unwrap_or(i32::MAX as u32);
+            let link = self.link.clone();
+            self.schedule_timer = Some(Timeout::new(millis, move || {
+                link.emit(Msg::Schedule);
+            }));
+        }
+    }
+
+    pub fn publish_raw(&mut self, channel: &str, payload: Vec<u8>) {
+        if let Some(connector) = &mut self.connector {
+            let payload = match &self.settings.payload.lorawan {
+                Some(lorawan) => {
+                    self.f_cnt = self.f_cnt.wrapping_add(1);
+                    let (rssi, snr) = lorawan.signal.sample();
+                    let uplink = Uplink {
+                        application: &self.settings.application,
+                        device: &self.settings.device,
+                        f_cnt: self.f_cnt,
+                        time: Utc::now(),
+                        rssi,
+                        snr,
+                    };
+                    match lorawan.uplink(uplink, &payload) {
+                        Ok(payload) => payload,
+                        Err(err) => {
+                            log::warn!("Failed to encode uplink: {err}");
+                            return;
+                        }
+                    }
+                }
+                None => payload,
+            };
+
+            connector.publish(channel, payload.clone(), QoS::QoS0);
+
+            let event = Event {
+                timestamp: Utc::now(),
+                channel: channel.to_string(),
+                payload,
+            };
+
+            self.events.push(event.clone());
+            self.output.emit(Output::Event(Rc::new(event)));
+        }
+    }
+
+    fn publish(&mut self, event: PublishEvent) {
+        self.published.set(self.published.get() + 1);
+
+        match event {
+            PublishEvent::Full { channel, state } => {
+                self.publish_channel_state(&channel, &state);
+                self.data.0.insert(channel, state);
+                self.broadcast_data();
+            }
+            PublishEvent::Single { channel, state } => {
+                let entry = self.data.0.entry(channel.clone());
+                let state = match entry {
+                    Entry::Vacant(e) => {
+                        let mut features = BTreeMap::new();
+                        features.insert(state.name, state.state);
+                        let state = ChannelState { features };
+                        e.insert(state.clone());
+                        state
+                    }
+                    Entry::Occupied(mut e) => {
+                        let e = e.get_mut();
+                        e.features.insert(state.name, state.state);
+                        e.clone()
+                    }
+                };
+
+                self.publish_channel_state(&channel, &state);
+                self.broadcast_data();
+            }
+            PublishEvent::Raw { channel, payload } => {
+                self.publish_raw(&channel, payload);
+            }
+        }
+        self.send_internal_state();
+    }
+
+    fn publish_channel_state(&mut self, channel: &str, state: &ChannelState) {
+        let payload = match &self.settings.payload.lorawan {
+            Some(lorawan) => Ok(lorawan::encode(&lorawan.encoder, state)),
+            None if self.settings.payload.cloud_events => state
+                .to_payload(self.settings.payload.format)
+                .and_then(|payload| {
+                    cloudevents::wrap(
+                        &self.settings.application,
+                        &self.settings.device,
+                        channel,
+                        Utc::now(),
+                        &payload,
+                    )
+                }),
+            None => state.to_payload(self.settings.payload.format),
+        };
+        if let Ok(payload) = payload {
+            self.publish_raw(channel, payload);
+        }
+    }
+
+    /// Publish the Home Assistant discovery configurations, if enabled.
+    ///
+    /// They are published as is, not being an event of the device.
+    fn publish_discovery(&mut self) {
+        let (topics, home_assistant) = match &self.settings.target {
+            Target::Mqtt {
+                topics,
+                home_assistant: Some(home_assistant),
+                ..
+            } => (topics, home_assistant),
+            _ => return,
+        };
+
+        let configs = Topics::new(topics, &self.settings)
+            .and_then(|topics| homeassistant::configs(home_assistant, &self.settings, &topics));
+        let configs = match configs {
+            Ok(configs) => configs,
+            Err(err) => {
+                log::warn!("Unable to create Home Assistant discovery configurations: {err}");
+                return;
+            }
+        };
+
+        if let Some(connector) = &mut self.connector {
+            for discovery in configs {
+                let result = serde_json::to_vec(&discovery.config)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|payload| {
+                        connector.publish_retained(&discovery.topic, payload, QoS::QoS1)
+                    });
+                if let Err(err) = result {
+                    log::warn!("Failed to publish discovery to {}: {err}", discovery.topic);
+                }
+            }
+        }
+    }
+
+    /// Handle a command, received from the cloud.
+    fn command(&mut self, command: Command) {
+        // record in history
+
+        self.commands.push(command.clone());
+        let command = Rc::new(command);
+
+        for sim in self.simulations.values_mut() {
+            sim.command(&command);
+        }
+
+        // broadcast
+
+        self.output.emit(Output::Command(command));
+    }
+
+    /// Start connecting to the target.
+    pub fn start(&mut self) {
+        if self.state.running {
+            return;
+        }
+
+        self.state.running = true;
+        self.f_cnt = 0;
+        self.send_state();
+
+        log::info!("Creating client");
+
+        let connector = self.connectors.create(
+            &self.settings,
+            self.link.reform(Msg::Command),
+            self.link.reform(Msg::Disconnected),
+        );
+
+        self.connector = match connector {
+            Ok(mut connector) => {
+                self.state.state = State::Connecting;
+                self.send_state();
+
+                if let Err(err) = connector.connect(ConnectOptions {
+                    on_success: self.link.reform(|_| Msg::Connected),
+                    on_failure: self.link.reform(Msg::Disconnected),
+                }) {
+                    log::warn!("Failed to start connecting: {err}");
+                }
+
+                Some(connector)
+            }
+            Err(err) => {
+                log::error!("Failed to create connector: {err}");
+                self.state.running = false;
+                self.state.state = State::Failed(err.to_string());
+                self.send_state();
+                return;
+            }
+        };
+
+        // Done
+
+        log::info!("Started");
+    }
+
+    /// Disconnect from the target.
+    pub fn stop(&mut self) {
+        if !self.state.running {
+            return;
+        }
+
+        self.connector.take();
+        self.state.running = false;
+        self.state.state = State::Disconnected;
+        self.send_state();
+    }
+
+    pub fn update_settings(&mut self, settings: Settings) {
+        let reconnect = self.settings.target != settings.target
+            || self.settings.application != settings.application
+            || self.settings.device != settings.device;
+
+        self.apply_settings(settings);
+        if self.state.running {
+            if reconnect {
+                // disconnect to trigger reconnect
+                self.stop();
+                self.start();
+            } else {
+                self.send_state();
+            }
+        } else if self.settings.auto_connect && self.power_loss.is_none() {
+            // auto-connect on, but not started yet
+            self.start();
+        } else {
+            // only need to send state here, as self.start() already does it
+            self.send_state();
+        }
+    }
+
+    /// Apply the new settings
+    ///
+    /// This reconciles the running simulations with the new configuration, keeping the state of
+    /// simulations where possible.
+    fn apply_settings(&mut self, settings: Settings) {
+        let actions = reconcile::plan(&self.settings.simulations, &settings.simulations);
+
+        for (id, action) in actions {
+            let entry = match settings.simulations.get(&id) {
+                Some(entry) => entry,
+                None => {
+                    self.remove_generator(&id);
+                    continue;
+                }
+            };
+
+            let created = match action {
+                Action::Add | Action::Replace => {
+                    self.create_generator(&id, entry);
+                    true
+                }
+                Action::Update => {
+                    let claims = self.simulations.get_mut(&id).and_then(|generator| {
+                        generator
+                            .update(&entry.simulation)
+                            .then(|| (generator.claims().to_vec(), generator.inputs().to_vec()))
+                    });
+                    match claims {
+                        Some((claims, inputs)) => {
+                            self.state.claims.insert(id.clone(), claims);
+                            self.state.claims.insert_inputs(id.clone(), inputs);
+                            false
+                        }
+                        None => {
+                            self.create_generator(&id, entry);
+                            true
+                        }
+                    }
+                }
+                Action::Keep if !self.simulations.contains_key(&id) => {
+                    // initial settings, which were never applied
+                    self.create_generator(&id, entry);
+                    true
+                }
+                Action::Keep | Action::Remove => false,
+            };
+
+            if !created {
+                let paused = self
+                    .settings
+                    .simulations
+                    .get(&id)
+                    .map(|current| current.paused)
+                    .unwrap_or_default();
+                if let Some(generator) = self.simulations.get_mut(&id) {
+                    match (paused, entry.paused) {
+                        (false, true) => generator.pause(),
+                        (true, false) => generator.resume(),
+                        _ => {}
+                    }
+                }
+            }
+
+            match &entry.schedule {
+                Some(schedule) => {
+                    // keep the origin, unless the schedule changed
+                    let origin = match self.schedules.get(&id) {
+                        Some((current, origin)) if current == schedule => *origin,
+                        _ => Utc::now(),
+                    };
+                    self.schedules.insert(id, (schedule.clone(), origin));
+                }
+                None => {
+                    self.schedules.remove(&id);
+                }
+            }
+        }
+
+        self.settings = settings;
+
+        self.evaluate_schedules();
+        self.broadcast_data();
+    }
+
+    /// Let all simulations know about the current state of the device.
+    fn broadcast_data(&mut self) {
+        for generator in self.simulations.values_mut() {
+            generator.data(&self.data);
+        }
+    }
+
+    /// Create a new generator from its configuration, replacing an existing one.
+    fn create_generator(&mut self, id: &SimulatorId, entry: &SimulationEntry) {
+        let mut generator = entry.simulation.create();
+        if entry.paused {
+            generator.pause();
+        }
+        self.add_generator(id.clone(), generator);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::simulator::{
+        mock::{settings, Harness},
+        simulations::View,
+        Claim,
+    };
+    use serde_json::json;
+    use std::time::Duration;
+    use tokio::task::LocalSet;
+
+    fn sine() -> Settings {
+        settings(json!({
+            "sine1": {
+                "sine": {"amplitude": 1.0, "length": "1m", "period": "10ms"}
+            }
+        }))
+    }
+
+    fn counter(period: &str, paused: bool) -> Settings {
+        settings(json!({
+            "counter1": {
+                "counter": {"period": period},
+                "paused": paused,
+            }
+        }))
+    }
+
+    /// All values published by the counter so far.
+    fn counter_values(harness: &Harness) -> Vec<f64> {
+        harness
+            .broker
+            .published_to("state")
+            .iter()
+            .filter_map(|p| p.json()["features"]["feature"]["value"].as_f64())
+            .collect()
+    }
+
+    /// Let the simulations run for a while, handling all their messages.
+    ///
+    /// The tests run with a paused clock, which skips ahead instead of waiting.
+    async fn settle(harness: &mut Harness) {
+        for _ in 0..5 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            harness.process();
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_natively() {
+        LocalSet::new()
+            .run_until(async {
+                let mut harness = Harness::new(sine());
+                assert!(harness.engine.state().simulations.contains_key("sine1"));
+
+                // not connected, but the simulation still runs
+                assert!(
+                    harness
+                        .run_until(|h| h.engine.data().0.contains_key("state"))
+                        .await
+                );
+
+                let state = &harness.engine.data().0["state"];
+                assert!(state.features["feature"].properties["value"].is_number());
+                assert!(harness.broker.published().is_empty());
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_connect() {
+        LocalSet::new()
+            .run_until(async {
+                let mut harness = Harness::new(sine());
+                harness.engine.start();
+
+                assert!(harness.run_until(|h| h.state().is_connected()).await);
+                assert_eq!(
+                    harness.states,
+                    vec![
+                        State::Disconnected,
+                        State::Connecting,
+                        State::Subscribing,
+                        State::Connected
+                    ]
+                );
+
+                // wait for the simulation to publish
+                assert!(
+                    harness
+                        .run_until(|h| h.broker.published_to("state").len() >= 2)
+                        .await
+                );
+                for published in harness.broker.published_to("state") {
+                    assert!(published.json()["features"]["feature"]["value"].is_number());
+                }
+                assert!(!harness.engine.events().is_empty());
+
+                harness.engine.stop();
+                harness.process();
+                assert!(!harness.broker.is_connected());
+                assert_eq!(harness.states.last(), Some(&State::Disconnected));
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_connect_failure() {
+        LocalSet::new()
+            .run_until(async {
+                let mut harness = Harness::new(sine());
+                harness.broker.fail_connect(Some("denied"));
+                harness.engine.start();
+
+                assert!(
+                    harness
+                        .run_until(|h| matches!(h.state(), State::Failed(_)))
+                        .await
+                );
+                assert_eq!(harness.state(), &State::Failed("denied".into()));
+                assert!(!harness.states.contains(&State::Subscribing));
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_create_failure() {
+        LocalSet::new()
+            .run_until(async {
+                let mut harness = Harness::new(sine());
+                harness.broker.fail_create(Some("invalid topic"));
+                harness.engine.start();
+                harness.process();
+
+                assert_eq!(harness.state(), &State::Failed("invalid topic".into()));
+                assert!(!harness.engine.state().running);
+                assert_eq!(harness.broker.connectors(), 0);
+
+                // starting again is possible, once the problem is fixed
+                harness.broker.fail_create(None);
+                harness.engine.start();
+                assert!(harness.run_until(|h| h.state().is_connected()).await);
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_subscribe_failure() {
+        LocalSet::new()
+            .run_until(async {
+                let mut harness = Harness::new(sine());
+                harness.broker.fail_subscribe(Some("not authorized"));
+                harness.engine.start();
+
+                assert!(
+                    harness
+                        .run_until(|h| matches!(h.state(), State::Failed(_)))
+                        .await
+                );
+                assert_eq!(
+                    harness.states[1..],
+                    [
+                        State::Connecting,
+                        State::Subscribing,
+                        State::Failed("not authorized".into())
+                    ]
+                );
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_connection_lost() {
+        LocalSet::new()
+            .run_until(async {
+                let mut harness = Harness::new(sine());
+                harness.engine.start();
+                assert!(harness.run_until(|h| h.state().is_connected()).await);
+
+                assert!(harness.broker.connection_lost("gone"));
+                assert!(
+                    harness
+                        .run_until(|h| matches!(h.state(), State::Failed(_)))
+                        .await
+                );
+                assert_eq!(harness.state(), &State::Failed("gone".into()));
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_command() {
+        LocalSet::new()
+            .run_until(async {
+                let mut harness = Harness::new(settings(json!({
+                    "switch1": {
+                        "switch": {"target": {"channel": "switch"}}
+                    }
+                })));
+                harness.engine.start();
+                assert!(harness.run_until(|h| h.state().is_connected()).await);
+
+                // the initial state
+                assert!(
+                    harness
+                        .run_until(|h| !h.broker.published_to("switch").is_empty())
+                        .await
+                );
+                assert_eq!(
+                    harness.broker.published_to("switch")[0].json()["features"]["feature"]["value"],
+                    json!(false)
+                );
+
+                // ignored, as it is for a different channel
+                assert!(harness
+                    .broker
+                    .command("other", Some(br#"{"feature":{"value":true}}"#)));
+                assert!(harness
+                    .broker
+                    .command("switch", Some(br#"{"feature":{"value":true}}"#)));
+
+                assert!(
+                    harness
+                        .run_until(|h| h
+                            .broker
+                            .published_to("switch")
+                            .iter()
+                            .any(|p| p.json()["features"]["feature"]["value"] == json!(true)))
+                        .await
+                );
+                assert_eq!(
+                    harness
+                        .engine
+                        .commands()
+                        .iter()
+                        .map(|c| c.name.as_str())
+                        .collect::<Vec<_>>(),
+                    vec!["other", "switch"]
+                );
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ramp_aborted_by_command() {
+        LocalSet::new()
+            .run_until(async {
+                let mut harness = Harness::new(settings(json!({
+                    "dimmer1": {
+                        "dimmer": {"ramp": "10s", "target": {"channel": "dimmer"}}
+                    }
+                })));
+                harness.engine.start();
+                assert!(harness.run_until(|h| h.state().is_connected()).await);
+
+                let values = |h: &Harness| -> Vec<f64> {
+                    h.broker
+                        .published_to("dimmer")
+                        .iter()
+                        .filter_map(|p| p.json()["features"]["feature"]["value"].as_f64())
+                        .collect()
+                };
+
+                // start ramping up, and turn it down again while ramping
+                assert!(harness
+                    .broker
+                    .command("dimmer", Some(br#"{"feature":{"value":100}}"#)));
+                assert!(
+                    harness
+                        .run_until(|h| values(h).iter().any(|v| *v > 0.0))
+                        .await
+                );
+                assert!(harness
+                    .broker
+                    .command("dimmer", Some(br#"{"feature":{"value":0}}"#)));
+                assert!(harness.run_until(|h| values(h).last() == Some(&0.0)).await);
+
+                // the first ramp would have ended by now
+                tokio::time::sleep(Duration::from_secs(20)).await;
+                harness.process();
+                assert_eq!(values(&harness).last(), Some(&0.0));
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_lorawan() {
+        LocalSet::new()
+            .run_until(async {
+                let field = json!([{"feature": "feature", "type": "bool"}]);
+                let mut settings = settings(json!({
+                    "switch1": {
+                        "switch": {"target": {"channel": "switch"}}
+                    }
+                }));
+                settings.payload.lorawan = Some(
+                    serde_json::from_value(json!({
+                        "network": "ttn",
+                        "devEui": "0011223344AABBCC",
+                        "encoder": field,
+                        "downlinks": [{"fPort": 10, "command": "switch", "fields": field}],
+                    }))
+                    .unwrap(),
+                );
+
+                let mut harness = Harness::new(settings);
+                harness.engine.start();
+                assert!(
+                    harness
+                        .run_until(|h| !h.broker.published_to("switch").is_empty())
+                        .await
+                );
+
+                let uplink = harness.broker.published_to("switch")[0].json();
+                assert_eq!(uplink["end_device_ids"]["device_id"], json!("device"));
+                assert_eq!(uplink["uplink_message"]["f_cnt"], json!(1));
+                assert_eq!(uplink["uplink_message"]["frm_payload"], json!("AA=="));
+
+                assert!(harness.broker.command(
+                    "down",
+                    Some(br#"{"downlinks":[{"f_port":10,"frm_payload":"AQ=="}]}"#)
+                ));
+
+                assert!(
+                    harness
+                        .run_until(|h| h
+                            .broker
+                            .published_to("switch")
+                            .iter()
+                            .any(|p| p.json()["uplink_message"]["frm_payload"] == json!("AQ==")))
+                        .await
+                );
+                assert_eq!(
+                    harness
+                        .engine
+                        .commands()
+                        .iter()
+                        .map(|c| c.name.as_str())
+                        .collect::<Vec<_>>(),
+                    vec!["switch"]
+                );
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_home_assistant() {
+        LocalSet::new()
+            .run_until(async {
+                let mut settings = settings(json!({
+                    "switch1": {
+                        "switch": {"target": {"channel": "switch"}}
+                    }
+                }));
+                if let Target::Mqtt { home_assistant, .. } = &mut settings.target {
+                    *home_assistant = Some(Default::default());
+                }
+                let topics = Topics::new(&Default::default(), &settings).unwrap();
+
+                let mut harness = Harness::new(settings);
+                harness.engine.start();
+                assert!(harness.run_until(|h| h.state().is_connected()).await);
+
+                let discovery = harness
+                    .broker
+                    .published_to("homeassistant/switch/app_device/switch1/config");
+                assert_eq!(discovery.len(), 1);
+                assert!(discovery[0].retain);
+                let config = discovery[0].json();
+                assert_eq!(config["state_topic"], json!("switch"));
+
+                // send a command, the way Home Assistant would
+                let command = topics
+                    .parse_command(
+                        config["command_topic"].as_str().unwrap(),
+                        config["payload_on"].as_str().unwrap().as_bytes().to_vec(),
+                    )
+                    .unwrap();
+                assert!(harness
+                    .broker
+                    .command(&command.name, command.payload.as_deref()));
+
+                assert!(
+                    harness
+                        .run_until(|h| h
+                            .broker
+                            .published_to("switch")
+                            .iter()
+                            .any(|p| p.json()["features"]["feature"]["value"] == json!(true)))
+                        .await
+                );
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_update_keeps_state() {
+        LocalSet::new()
+            .run_until(async {
+                let mut harness = Harness::new(counter("10ms", false));
+                harness.engine.start();
+                assert!(harness.run_until(|h| counter_values(h).len() >= 3).await);
+                let last = *counter_values(&harness).last().unwrap();
+                let published = counter_values(&harness).len();
+
+                // same type, different properties: updated in place
+                harness.engine.update_settings(counter("20ms", false));
+                assert!(
+                    harness
+                        .run_until(|h| counter_values(h).len() >= published + 2)
+                        .await
+                );
+
+                // a new generator would start over with the initial value
+                let values = counter_values(&harness);
+                assert!(
+                    values[published..].iter().all(|v| *v > last),
+                    "counter restarted: {values:?}"
+                );
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_update_keeps_paused_state() {
+        LocalSet::new()
+            .run_until(async {
+                let mut harness = Harness::new(counter("10ms", false));
+                harness.engine.start();
+                assert!(harness.run_until(|h| counter_values(h).len() >= 2).await);
+
+                // pause, while also changing a property
+                harness.engine.update_settings(counter("5ms", true));
+                settle(&mut harness).await;
+                let paused = counter_values(&harness);
+                settle(&mut harness).await;
+                assert_eq!(counter_values(&harness), paused);
+
+                // changing a property again must not resume it
+                harness.engine.update_settings(counter("10ms", true));
+                settle(&mut harness).await;
+                assert_eq!(counter_values(&harness), paused);
+
+                // resume, continuing from where it was paused
+                harness.engine.update_settings(counter("10ms", false));
+                assert!(
+                    harness
+                        .run_until(|h| counter_values(h).len() >= paused.len() + 2)
+                        .await
+                );
+                let last = *paused.last().unwrap();
+                let values = counter_values(&harness);
+                assert!(
+                    values[paused.len()..].iter().all(|v| *v > last),
+                    "counter restarted: {values:?}"
+                );
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_remove_stops_generator() {
+        LocalSet::new()
+            .run_until(async {
+                let mut harness = Harness::new(counter("10ms", false));
+                harness.engine.start();
+                assert!(harness.run_until(|h| counter_values(h).len() >= 2).await);
+
+                harness.engine.update_settings(settings(json!({})));
+                assert!(!harness.engine.state().simulations.contains_key("counter1"));
+                assert!(!harness.engine.state().claims.contains_key("counter1"));
+
+                settle(&mut harness).await;
+                let removed = counter_values(&harness);
+                settle(&mut harness).await;
+                assert_eq!(counter_values(&harness), removed);
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_control_panel_changes() {
+        LocalSet::new()
+            .run_until(async {
+                let mut harness = Harness::new(settings(json!({
+                    "panel1": {
+                        "controlPanel": {
+                            "delay": "50ms",
+                            "controls": [{"property": "setpoint", "type": "number", "initial": 5}],
+                        }
+                    }
+                })));
+                assert!(
+                    harness
+                        .run_until(|h| h.engine.simulation_state("panel1").is_some())
+                        .await
+                );
+
+                let increment = match &harness.engine.simulation_state("panel1").unwrap().view {
+                    View::Form(controls) => match &controls[0].1 {
+                        View::Toolbar(items) => match &items[2] {
+                            View::Button { onclick, .. } => onclick.clone(),
+                            view => panic!("Unexpected view: {view:?}"),
+                        },
+                        view => panic!("Unexpected view: {view:?}"),
+                    },
+                    view => panic!("Unexpected view: {view:?}"),
+                };
+
+                // the second change arrives before the delay expires, and must not get lost
+                increment.emit(());
+                increment.emit(());
+
+                let setpoint = |h: &Harness| {
+                    h.engine
+                        .data()
+                        .0
+                        .get("state")?
+                        .features
+                        .get("feature")?
+                        .properties
+                        .get("setpoint")?
+                        .as_f64()
+                };
+                assert!(harness.run_until(|h| setpoint(h) == Some(7.0)).await);
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_formula_targeting_input() {
+        LocalSet::new()
+            .run_until(async {
+                let formula = |property: &str| {
+                    settings(json!({
+                        "counter1": {
+                            "counter": {"period": "10ms"}
+                        },
+                        "formula1": {
+                            "formula": {
+                                "expression": "value * 2",
+                                "inputs": {"value": {}},
+                                "target": {"property": property},
+                            }
+                        }
+                    }))
+                };
+
+                let mut harness = Harness::new(formula("value"));
+                assert_eq!(
+                    harness.engine.state().claims.conflicting_inputs("formula1"),
+                    vec![Claim::Property {
+                        channel: "state".into(),
+                        feature: "feature".into(),
+                        property: "value".into(),
+                    }]
+                );
+                assert!(harness
+                    .engine
+                    .state()
+                    .claims
+                    .conflicting_inputs("counter1")
+                    .is_empty());
+
+                // the formula is not started, so it doesn't overwrite the counter
+                assert!(
+                    harness
+                        .run_until(|h| h.engine.data().0.contains_key("state"))
+                        .await
+                );
+                assert_eq!(harness.engine.simulation_state("formula1"), None);
+
+                // fixing the target lets it run
+                harness.engine.update_settings(formula("double"));
+                assert!(harness
+                    .engine
+                    .state()
+                    .claims
+                    .conflicting_inputs("formula1")
+                    .is_empty());
+                assert!(
+                    harness
+                        .run_until(|h| h.engine.data().0["state"].features["feature"]
+                            .properties
+                            .contains_key("double"))
+                        .await
+                );
+            })
+            .await;
+    }
+}
@@ -0,0 +1,298 @@
+//! Home Assistant MQTT discovery.
+//!
+//! Home Assistant creates entities from retained configurations, published to
+//! `<prefix>/<component>/<node_id>/<object_id>/config`. The state of an entity is extracted from the payloads the
+//! simulator publishes, and commands are sent to the command topic, in the format the simulations expect.
+
+use crate::settings::{PayloadFormat, Settings, Simulation};
+use crate::simulator::{mqtt::Topics, simulations::SingleTarget};
+use anyhow::{anyhow, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Placeholder for the value of a command template.
+const VALUE_PLACEHOLDER: &str = "__value__";
+
+/// Publish Home Assistant discovery configurations, once subscribed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HomeAssistant {
+    /// The prefix of the discovery topics.
+    #[serde(default = "default_discovery_prefix")]
+    pub discovery_prefix: String,
+}
+
+impl Default for HomeAssistant {
+    fn default() -> Self {
+        Self {
+            discovery_prefix: default_discovery_prefix(),
+        }
+    }
+}
+
+fn default_discovery_prefix() -> String {
+    "homeassistant".into()
+}
+
+/// The discovery configuration of an entity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Discovery {
+    pub topic: String,
+    pub config: Value,
+}
+
+/// Create the discovery configurations for all simulations which map to an entity.
+///
+/// Sine, sawtooth and wave generators become sensors, switches become switches, dimmers numbers, mode selectors
+/// selects, and LED matrices lights.
+pub fn configs(
+    home_assistant: &HomeAssistant,
+    settings: &Settings,
+    topics: &Topics,
+) -> anyhow::Result<Vec<Discovery>> {
+    if settings.payload.lorawan.is_some() {
+        bail!("Home Assistant can't decode LoRaWAN uplinks");
+    }
+
+    let node_id = object_id(&format!("{}_{}", settings.application, settings.device));
+    let device = json!({
+        "identifiers": [node_id],
+        "name": settings.device,
+        "manufacturer": "Drogue IoT",
+        "model": "Device simulator",
+    });
+
+    let mut result = vec![];
+
+    for (id, entry) in &settings.simulations {
+        let (component, target, mut config) = match &entry.simulation {
+            Simulation::Sine(properties) => ("sensor", &properties.target, json!({})),
+            Simulation::Sawtooth(properties) => ("sensor", &properties.target, json!({})),
+            Simulation::Wave(properties) => ("sensor", &properties.target, json!({})),
+            Simulation::Switch(properties) => {
+                let target = &properties.target;
+                let state = value(settings, target);
+                let config = json!({
+                    "value_template": format!("{{{{ 'ON' if {state} else 'OFF' }}}}"),
+                    "payload_on": command_payload(target, json!(true)),
+                    "payload_off": command_payload(target, json!(false)),
+                });
+                ("switch", target, config)
+            }
+            Simulation::Dimmer(properties) => {
+                let target = &properties.target;
+                // a number, so the placeholder must not be quoted
+                let template = command_payload(target, json!(VALUE_PLACEHOLDER))
+                    .replace(&format!("\"{VALUE_PLACEHOLDER}\""), "{{ value }}");
+                let config = json!({
+                    "min": properties.min.0,
+                    "max": properties.max.0,
+                    "command_template": template,
+                });
+                ("number", target, config)
+            }
+            Simulation::ModeSelector(properties) => {
+                let target = &properties.target;
+                let template = command_payload(target, json!(VALUE_PLACEHOLDER))
+                    .replace(VALUE_PLACEHOLDER, "{{ value }}");
+                let config = json!({
+                    "options": properties.modes,
+                    "command_template": template,
+                });
+                ("select", target, config)
+            }
+            Simulation::LedMatrix(properties) => {
+                let target = &properties.target;
+                let state = value(settings, target);
+                let config = json!({
+                    "schema": "template",
+                    "state_template": format!("{{{{ 'on' if {state}['on'] else 'off' }}}}"),
+                    "command_on_template": command_payload(target, json!({"fill": true})),
+                    "command_off_template": command_payload(target, json!({"fill": false})),
+                });
+                ("light", target, config)
+            }
+            _ => continue,
+        };
+
+        let object_id = object_id(id);
+        config["name"] = json!(id);
+        config["unique_id"] = json!(format!("{node_id}_{object_id}"));
+        config["device"] = device.clone();
+        config["state_topic"] = json!(topics.publish(&target.channel));
+        if config.get("value_template").is_none() && config.get("state_template").is_none() {
+            config["value_template"] = json!(format!("{{{{ {} }}}}", value(settings, target)));
+        }
+        if component != "sensor" {
+            let command_topic = topics.command(&target.channel).ok_or_else(|| {
+                anyhow!(
+                    "Command topic must not contain wildcards, for Home Assistant to send commands"
+                )
+            })?;
+            config["command_topic"] = json!(command_topic);
+        }
+
+        result.push(Discovery {
+            topic: format!(
+                "{}/{component}/{node_id}/{object_id}/config",
+                home_assistant.discovery_prefix
+            ),
+            config,
+        });
+    }
+
+    Ok(result)
+}
+
+/// The template expression extracting the value of a target, from the published payload.
+fn value(settings: &Settings, target: &SingleTarget) -> String {
+    let mut path = vec![];
+    if settings.payload.cloud_events {
+        path.push("data");
+    }
+    path.push(match settings.payload.format {
+        PayloadFormat::JsonCompact => "features",
+        PayloadFormat::Doppelgaenger => "state",
+    });
+    path.push(&target.feature);
+    path.push(&target.property);
+
+    path.into_iter()
+        .fold("value_json".to_string(), |result, key| {
+            // a JSON string is a valid string literal for templates too
+            format!("{result}[{}]", Value::from(key))
+        })
+}
+
+/// The payload of a command, setting the value of the target.
+fn command_payload(target: &SingleTarget, value: Value) -> String {
+    json!({ &target.feature: { &target.property: value } }).to_string()
+}
+
+/// Turn a name into an ID, which is valid as level of a discovery topic.
+fn object_id(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{settings::MqttTopics, simulator::mock::settings};
+
+    fn configs(settings: &Settings) -> anyhow::Result<Vec<Discovery>> {
+        let topics = Topics::new(&Default::default(), settings)?;
+        super::configs(&Default::default(), settings, &topics)
+    }
+
+    #[test]
+    fn test_sensor() {
+        let mut settings = settings(json!({
+            "sine1": {
+                "sine": {
+                    "amplitude": 1.0,
+                    "length": "1m",
+                    "target": {"channel": "state", "feature": "temperature"}
+                }
+            },
+            "accelerometer": {"accelerometer": {}},
+        }));
+        // names which need to be sanitized for the discovery topics
+        settings.application = "my-app".into();
+        settings.device = "device 1".into();
+
+        assert_eq!(
+            configs(&settings).unwrap(),
+            vec![Discovery {
+                topic: "homeassistant/sensor/my-app_device_1/sine1/config".into(),
+                config: json!({
+                    "name": "sine1",
+                    "unique_id": "my-app_device_1_sine1",
+                    "device": {
+                        "identifiers": ["my-app_device_1"],
+                        "name": "device 1",
+                        "manufacturer": "Drogue IoT",
+                        "model": "Device simulator",
+                    },
+                    "state_topic": "state",
+                    "value_template": r#"{{ value_json["features"]["temperature"]["value"] }}"#,
+                })
+            }]
+        );
+    }
+
+    #[test]
+    fn test_actuators() {
+        let mut settings = settings(json!({
+            "switch1": {"switch": {"target": {"channel": "switch"}}},
+            "dimmer1": {"dimmer": {"target": {"channel": "dimmer"}}},
+            "mode1": {"modeSelector": {"modes": ["eco", "boost"], "target": {"channel": "mode"}}},
+            "matrix": {"ledMatrix": {"target": {"channel": "display"}}},
+        }));
+        settings.payload.cloud_events = true;
+
+        let configs = configs(&settings).unwrap();
+        let config = |id: &str| {
+            configs
+                .iter()
+                .find(|discovery| discovery.config["name"] == json!(id))
+                .map(|discovery| discovery.config.clone())
+                .unwrap()
+        };
+
+        let switch = config("switch1");
+        assert_eq!(switch["command_topic"], json!("command/inbox//switch"));
+        assert_eq!(
+            switch["value_template"],
+            json!(r#"{{ 'ON' if value_json["data"]["features"]["feature"]["value"] else 'OFF' }}"#)
+        );
+        assert_eq!(switch["payload_on"], json!(r#"{"feature":{"value":true}}"#));
+        assert_eq!(
+            switch["payload_off"],
+            json!(r#"{"feature":{"value":false}}"#)
+        );
+
+        let dimmer = config("dimmer1");
+        assert_eq!(dimmer["max"], json!(100.0));
+        assert_eq!(
+            dimmer["command_template"],
+            json!(r#"{"feature":{"value":{{ value }}}}"#)
+        );
+
+        let mode = config("mode1");
+        assert_eq!(mode["options"], json!(["eco", "boost"]));
+        assert_eq!(
+            mode["command_template"],
+            json!(r#"{"feature":{"value":"{{ value }}"}}"#)
+        );
+
+        let light = config("matrix");
+        assert_eq!(light["command_topic"], json!("command/inbox//display"));
+        assert_eq!(
+            light["command_off_template"],
+            json!(r#"{"feature":{"value":{"fill":false}}}"#)
+        );
+        assert_eq!(light.get("value_template"), None);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let settings = settings(json!({
+            "switch1": {"switch": {}},
+        }));
+        let topics = Topics::new(
+            &MqttTopics {
+                publish: "{channel}".into(),
+                command: "+/{command}".into(),
+            },
+            &settings,
+        )
+        .unwrap();
+        assert!(super::configs(&Default::default(), &settings, &topics).is_err());
+    }
+}
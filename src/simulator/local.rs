@@ -0,0 +1,86 @@
+use super::Connector;
+use crate::connector::{local, mqtt::QoS};
+use crate::simulator::{Command, ConnectOptions, SubscribeOptions};
+use crate::utils::handler::Handler;
+
+/// Connects the simulator to the in-browser loopback bus.
+pub struct LocalConnector {
+    on_command: Handler<Command>,
+    connection: Option<local::DeviceConnection>,
+}
+
+impl LocalConnector {
+    pub fn new(on_command: Handler<Command>) -> Self {
+        Self {
+            on_command,
+            connection: None,
+        }
+    }
+}
+
+impl Connector for LocalConnector {
+    fn connect(&mut self, opts: ConnectOptions) -> anyhow::Result<()> {
+        self.connection = Some(local::connect_device(self.on_command.reform(
+            |(name, payload)| Command {
+                name,
+                payload: Some(payload),
+            },
+        )));
+        opts.on_success.emit(());
+        Ok(())
+    }
+
+    fn subscribe(&mut self, opts: SubscribeOptions) -> anyhow::Result<()> {
+        // commands are always delivered to the connected device
+        opts.on_success.emit(());
+        Ok(())
+    }
+
+    fn publish(&mut self, channel: &str, payload: Vec<u8>, _qos: QoS) {
+        if let Some(connection) = &self.connection {
+            connection.publish(channel, payload);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn test_connector() {
+        let commands = Rc::new(RefCell::new(vec![]));
+        let mut connector = LocalConnector::new({
+            let commands = commands.clone();
+            Handler::from(move |command| commands.borrow_mut().push(command))
+        });
+
+        // not connected yet
+        connector.publish("state", b"{}".to_vec(), QoS::QoS0);
+        assert!(local::channels().is_empty());
+        assert!(!local::send_command("set", vec![]));
+
+        connector
+            .connect(ConnectOptions {
+                on_success: Handler::noop(),
+                on_failure: Handler::noop(),
+            })
+            .unwrap();
+
+        connector.publish("state", b"{}".to_vec(), QoS::QoS0);
+        assert_eq!(local::channels()["state"].last.payload, b"{}");
+
+        assert!(local::send_command("set", b"on".to_vec()));
+        assert_eq!(
+            *commands.borrow(),
+            vec![Command {
+                name: "set".into(),
+                payload: Some(b"on".to_vec())
+            }]
+        );
+
+        drop(connector);
+        assert!(!local::is_device_connected());
+    }
+}
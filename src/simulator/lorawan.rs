@@ -0,0 +1,522 @@
+//! Emulate devices which are connected through a LoRaWAN network server.
+//!
+//! Uplinks are wrapped in the JSON messages the network server forwards to applications, and
+//! downlinks scheduled by applications are decoded into commands.
+
+use crate::{
+    simulator::{publish::ChannelState, simulations::default_value_property, Command},
+    utils::{
+        float::{ApproxF64, Zero},
+        random::random,
+    },
+};
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LoRaWan {
+    /// The network server, defining the format of uplinks and downlinks.
+    pub network: Network,
+    /// The DevEUI, as 16 hex digits.
+    pub dev_eui: String,
+    /// The port of uplinks.
+    #[serde(default = "default_f_port")]
+    pub f_port: u8,
+    /// Encodes a channel state into the frame payload.
+    #[serde(default)]
+    pub encoder: Vec<Field>,
+    /// Decodes downlinks, by port.
+    #[serde(default)]
+    pub downlinks: Vec<Downlink>,
+    #[serde(default)]
+    pub signal: Signal,
+}
+
+const fn default_f_port() -> u8 {
+    1
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Network {
+    /// The Things Stack (v3).
+    Ttn,
+    /// ChirpStack (v4).
+    ChirpStack,
+}
+
+/// A field of the frame payload, encoded big endian.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Field {
+    pub feature: String,
+    #[serde(default = "default_value_property")]
+    pub property: String,
+    pub r#type: FieldType,
+    /// The value is multiplied with the scale before encoding, and divided by it after decoding.
+    #[serde(default = "default_scale")]
+    pub scale: ApproxF64<Zero, 2>,
+}
+
+fn default_scale() -> ApproxF64<Zero, 2> {
+    1f64.into()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum FieldType {
+    Bool,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+}
+
+/// Turns downlinks on a port into a command.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Downlink {
+    pub f_port: u8,
+    /// The name of the command.
+    pub command: String,
+    /// Decodes the frame payload into the JSON payload of the command.
+    #[serde(default)]
+    pub fields: Vec<Field>,
+}
+
+/// The simulated signal quality of uplinks.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Signal {
+    /// RSSI in dBm.
+    #[serde(default = "default_rssi")]
+    pub rssi: ApproxF64<Zero, 2>,
+    /// SNR in dB.
+    #[serde(default = "default_snr")]
+    pub snr: ApproxF64<Zero, 2>,
+    /// Maximum random deviation of RSSI and SNR.
+    #[serde(default = "default_variation")]
+    pub variation: ApproxF64<Zero, 2>,
+}
+
+impl Default for Signal {
+    fn default() -> Self {
+        Self {
+            rssi: default_rssi(),
+            snr: default_snr(),
+            variation: default_variation(),
+        }
+    }
+}
+
+fn default_rssi() -> ApproxF64<Zero, 2> {
+    (-80f64).into()
+}
+
+fn default_snr() -> ApproxF64<Zero, 2> {
+    7.5f64.into()
+}
+
+fn default_variation() -> ApproxF64<Zero, 2> {
+    3f64.into()
+}
+
+impl Signal {
+    /// Simulate the RSSI and SNR of an uplink.
+    pub fn sample(&self) -> (f64, f64) {
+        let deviation = || (random() * 2.0 - 1.0) * self.variation.0;
+        (
+            (self.rssi.0 + deviation()).round(),
+            ((self.snr.0 + deviation()) * 10.0).round() / 10.0,
+        )
+    }
+}
+
+/// Metadata of an uplink.
+pub struct Uplink<'a> {
+    pub application: &'a str,
+    pub device: &'a str,
+    pub f_cnt: u32,
+    pub time: DateTime<Utc>,
+    pub rssi: f64,
+    pub snr: f64,
+}
+
+impl LoRaWan {
+    /// Validate the settings, beyond what the deserializer checks.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.dev_eui.len() != 16 || !self.dev_eui.chars().all(|c| c.is_ascii_hexdigit()) {
+            bail!("The DevEUI must consist of 16 hex digits");
+        }
+        if self.encoder.is_empty() {
+            bail!("The encoder of the LoRaWAN payload must have at least one field");
+        }
+        Ok(())
+    }
+
+    /// Wrap a frame payload into the uplink message of the network server.
+    pub fn uplink(&self, uplink: Uplink, frm_payload: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let time = uplink.time.to_rfc3339();
+        let data = base64::encode(frm_payload);
+        let dev_eui = self.dev_eui.to_uppercase();
+
+        let message = match self.network {
+            Network::Ttn => json!({
+                "end_device_ids": {
+                    "device_id": uplink.device,
+                    "application_ids": { "application_id": uplink.application },
+                    "dev_eui": dev_eui,
+                },
+                "received_at": time,
+                "uplink_message": {
+                    "f_port": self.f_port,
+                    "f_cnt": uplink.f_cnt,
+                    "frm_payload": data,
+                    "rx_metadata": [{
+                        "gateway_ids": { "gateway_id": "simulator" },
+                        "rssi": uplink.rssi,
+                        "channel_rssi": uplink.rssi,
+                        "snr": uplink.snr,
+                    }],
+                    "received_at": time,
+                },
+            }),
+            Network::ChirpStack => json!({
+                "deduplicationId": uuid::Uuid::new_v4().to_string(),
+                "time": time,
+                "deviceInfo": {
+                    "applicationName": uplink.application,
+                    "deviceName": uplink.device,
+                    "devEui": dev_eui.to_lowercase(),
+                },
+                "fCnt": uplink.f_cnt,
+                "fPort": self.f_port,
+                "data": data,
+                "rxInfo": [{
+                    "gatewayId": "simulator",
+                    "rssi": uplink.rssi,
+                    "snr": uplink.snr,
+                }],
+            }),
+        };
+
+        Ok(serde_json::to_vec(&message)?)
+    }
+
+    /// Decode the downlinks of a command, returns `None` if it doesn't contain downlinks.
+    pub fn downlinks(&self, command: &Command) -> Option<Vec<Command>> {
+        let json: Value = serde_json::from_slice(command.payload.as_deref()?).ok()?;
+
+        let frames: Vec<(u8, Vec<u8>)> = match self.network {
+            // {"downlinks": [{"f_port": 1, "frm_payload": "..."}]}
+            Network::Ttn => json["downlinks"]
+                .as_array()?
+                .iter()
+                .filter_map(|downlink| frame(&downlink["f_port"], &downlink["frm_payload"]))
+                .collect(),
+            // {"devEui": "...", "fPort": 1, "data": "..."}
+            Network::ChirpStack => vec![frame(&json["fPort"], &json["data"])?],
+        };
+
+        Some(
+            frames
+                .into_iter()
+                .filter_map(|(f_port, data)| self.decode_downlink(f_port, &data))
+                .collect(),
+        )
+    }
+
+    fn decode_downlink(&self, f_port: u8, data: &[u8]) -> Option<Command> {
+        match self.downlinks.iter().find(|d| d.f_port == f_port) {
+            Some(downlink) => match decode(&downlink.fields, data) {
+                Ok(json) => Some(Command {
+                    name: downlink.command.clone(),
+                    payload: serde_json::to_vec(&json).ok(),
+                }),
+                Err(err) => {
+                    log::info!("Failed to decode downlink on port {f_port}: {err}");
+                    None
+                }
+            },
+            // pass on unknown downlinks, named by their port
+            None => Some(Command {
+                name: f_port.to_string(),
+                payload: Some(data.to_vec()),
+            }),
+        }
+    }
+}
+
+fn frame(f_port: &Value, data: &Value) -> Option<(u8, Vec<u8>)> {
+    let f_port = u8::try_from(f_port.as_u64()?).ok()?;
+    let data = base64::decode(data.as_str().unwrap_or_default()).ok()?;
+    Some((f_port, data))
+}
+
+/// Encode the fields of a channel state. Missing or invalid values are encoded as zero.
+pub fn encode(fields: &[Field], state: &ChannelState) -> Vec<u8> {
+    let mut data = vec![];
+
+    for field in fields {
+        let value = state
+            .features
+            .get(&field.feature)
+            .and_then(|feature| feature.properties.get(&field.property));
+        let value = match value {
+            Some(Value::Bool(value)) => *value as u8 as f64,
+            Some(Value::Number(value)) => value.as_f64().unwrap_or_default(),
+            _ => 0f64,
+        } * field.scale.0;
+
+        match field.r#type {
+            FieldType::Bool => data.push((value != 0f64) as u8),
+            FieldType::U8 => data.push(value.round() as u8),
+            FieldType::I8 => data.extend((value.round() as i8).to_be_bytes()),
+            FieldType::U16 => data.extend((value.round() as u16).to_be_bytes()),
+            FieldType::I16 => data.extend((value.round() as i16).to_be_bytes()),
+            FieldType::U32 => data.extend((value.round() as u32).to_be_bytes()),
+            FieldType::I32 => data.extend((value.round() as i32).to_be_bytes()),
+            FieldType::F32 => data.extend((value as f32).to_be_bytes()),
+        }
+    }
+
+    data
+}
+
+/// Decode a frame payload into a JSON object, with the fields as properties of their features.
+pub fn decode(fields: &[Field], data: &[u8]) -> anyhow::Result<Value> {
+    let mut result = Map::new();
+    let mut data = data;
+
+    for field in fields {
+        let size = field.r#type.size();
+        if data.len() < size {
+            bail!("Missing data for '{}/{}'", field.feature, field.property);
+        }
+        let (bytes, rest) = data.split_at(size);
+        data = rest;
+
+        let number = |value: f64| -> anyhow::Result<Value> {
+            serde_json::Number::from_f64(value / field.scale.0)
+                .map(Value::Number)
+                .ok_or_else(|| anyhow!("Invalid value for '{}'", field.feature))
+        };
+
+        let value = match field.r#type {
+            FieldType::Bool => Value::Bool(bytes[0] != 0),
+            FieldType::U8 => number(bytes[0] as f64)?,
+            FieldType::I8 => number(bytes[0] as i8 as f64)?,
+            FieldType::U16 => number(u16::from_be_bytes([bytes[0], bytes[1]]) as f64)?,
+            FieldType::I16 => number(i16::from_be_bytes([bytes[0], bytes[1]]) as f64)?,
+            FieldType::U32 => number(u32::from_be_bytes(bytes.try_into()?) as f64)?,
+            FieldType::I32 => number(i32::from_be_bytes(bytes.try_into()?) as f64)?,
+            FieldType::F32 => number(f32::from_be_bytes(bytes.try_into()?) as f64)?,
+        };
+
+        result
+            .entry(field.feature.clone())
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .map(|feature| feature.insert(field.property.clone(), value));
+    }
+
+    Ok(Value::Object(result))
+}
+
+impl FieldType {
+    /// The encoded size, in bytes.
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Bool | Self::U8 | Self::I8 => 1,
+            Self::U16 | Self::I16 => 2,
+            Self::U32 | Self::I32 | Self::F32 => 4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::simulator::publish::Feature;
+    use std::collections::BTreeMap;
+
+    fn field(feature: &str, r#type: FieldType, scale: f64) -> Field {
+        Field {
+            feature: feature.into(),
+            property: default_value_property(),
+            r#type,
+            scale: scale.into(),
+        }
+    }
+
+    fn lorawan(network: Network) -> LoRaWan {
+        LoRaWan {
+            network,
+            dev_eui: "0011223344aabbcc".into(),
+            f_port: 2,
+            encoder: vec![
+                field("temperature", FieldType::I16, 100.0),
+                field("switch", FieldType::Bool, 1.0),
+            ],
+            downlinks: vec![Downlink {
+                f_port: 10,
+                command: "state".into(),
+                fields: vec![field("switch", FieldType::Bool, 1.0)],
+            }],
+            signal: Default::default(),
+        }
+    }
+
+    fn uplink() -> Uplink<'static> {
+        Uplink {
+            application: "app",
+            device: "device",
+            f_cnt: 42,
+            time: DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z")
+                .unwrap()
+                .into(),
+            rssi: -81.0,
+            snr: 7.2,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(lorawan(Network::Ttn).validate().is_ok());
+
+        for dev_eui in [
+            "",
+            "0011223344aabbc",
+            "0011223344aabbccd",
+            "0011223344aabbcg",
+        ] {
+            let mut lorawan = lorawan(Network::Ttn);
+            lorawan.dev_eui = dev_eui.into();
+            assert!(lorawan.validate().is_err(), "{dev_eui}");
+        }
+
+        let mut lorawan = lorawan(Network::Ttn);
+        lorawan.encoder.clear();
+        assert!(lorawan.validate().is_err());
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut features = BTreeMap::new();
+        let mut properties = BTreeMap::new();
+        properties.insert("value".to_string(), json!(-12.345));
+        features.insert("temperature".to_string(), Feature { properties });
+        let state = ChannelState { features };
+
+        let fields = lorawan(Network::Ttn).encoder;
+        // the switch is missing, and encoded as zero
+        assert_eq!(encode(&fields, &state), vec![0xFB, 0x2D, 0x00]);
+
+        assert_eq!(
+            decode(&fields, &[0xFB, 0x2D, 0x01]).unwrap(),
+            json!({"temperature": {"value": -12.35}, "switch": {"value": true}})
+        );
+        assert!(decode(&fields, &[0xFB]).is_err());
+    }
+
+    #[test]
+    fn test_uplink_ttn() {
+        let uplink = lorawan(Network::Ttn).uplink(uplink(), &[1, 2]).unwrap();
+        let json: Value = serde_json::from_slice(&uplink).unwrap();
+
+        assert_eq!(json["end_device_ids"]["device_id"], "device");
+        assert_eq!(
+            json["end_device_ids"]["application_ids"]["application_id"],
+            "app"
+        );
+        assert_eq!(json["end_device_ids"]["dev_eui"], "0011223344AABBCC");
+        assert_eq!(json["uplink_message"]["f_port"], 2);
+        assert_eq!(json["uplink_message"]["f_cnt"], 42);
+        assert_eq!(json["uplink_message"]["frm_payload"], "AQI=");
+        assert_eq!(json["uplink_message"]["rx_metadata"][0]["rssi"], -81.0);
+        assert_eq!(json["uplink_message"]["rx_metadata"][0]["snr"], 7.2);
+    }
+
+    #[test]
+    fn test_uplink_chirpstack() {
+        let uplink = lorawan(Network::ChirpStack)
+            .uplink(uplink(), &[1, 2])
+            .unwrap();
+        let json: Value = serde_json::from_slice(&uplink).unwrap();
+
+        assert_eq!(json["deviceInfo"]["devEui"], "0011223344aabbcc");
+        assert_eq!(json["fPort"], 2);
+        assert_eq!(json["fCnt"], 42);
+        assert_eq!(json["data"], "AQI=");
+        assert_eq!(json["rxInfo"][0]["rssi"], -81.0);
+        assert_eq!(json["time"], "2022-01-01T00:00:00+00:00");
+    }
+
+    fn command(payload: Value) -> Command {
+        Command {
+            name: "down".into(),
+            payload: Some(serde_json::to_vec(&payload).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_downlinks_ttn() {
+        let commands = lorawan(Network::Ttn)
+            .downlinks(&command(json!({"downlinks": [
+                {"f_port": 10, "frm_payload": "AQ==", "priority": "NORMAL"},
+                {"f_port": 3, "frm_payload": "/w=="},
+            ]})))
+            .unwrap();
+
+        assert_eq!(
+            commands,
+            vec![
+                Command {
+                    name: "state".into(),
+                    payload: Some(br#"{"switch":{"value":true}}"#.to_vec())
+                },
+                Command {
+                    name: "3".into(),
+                    payload: Some(vec![0xff])
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_downlinks_chirpstack() {
+        let commands = lorawan(Network::ChirpStack)
+            .downlinks(&command(
+                json!({"devEui": "0011223344aabbcc", "fPort": 10, "data": "AA=="}),
+            ))
+            .unwrap();
+
+        assert_eq!(
+            commands,
+            vec![Command {
+                name: "state".into(),
+                payload: Some(br#"{"switch":{"value":false}}"#.to_vec())
+            }]
+        );
+    }
+
+    #[test]
+    fn test_not_a_downlink() {
+        let lorawan = lorawan(Network::Ttn);
+        assert_eq!(lorawan.downlinks(&command(json!({"value": 1}))), None);
+        assert_eq!(
+            lorawan.downlinks(&Command {
+                name: "text".into(),
+                payload: Some(b"hello".to_vec())
+            }),
+            None
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! An in-memory connector, and a harness for running the engine against it in tests.
+
+use crate::{
+    connector::mqtt::QoS,
+    runtime,
+    settings::Settings,
+    simulator::{
+        engine::{Engine, Msg, Output},
+        Command, ConnectOptions, Connector, ConnectorFactory, State, SubscribeOptions,
+    },
+    utils::handler::Handler,
+};
+use anyhow::bail;
+use futures::{
+    channel::mpsc::{self, UnboundedReceiver},
+    select, FutureExt, StreamExt,
+};
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+/// A message published through the mock connector.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Published {
+    pub channel: String,
+    pub payload: Vec<u8>,
+    pub qos: QoS,
+    pub retain: bool,
+}
+
+impl Published {
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.payload).unwrap_or_default()
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    published: Vec<Published>,
+    /// Number of connectors created so far.
+    connectors: usize,
+    /// Handlers of the current connection.
+    connection: Option<Connection>,
+    fail_create: Option<String>,
+    fail_connect: Option<String>,
+    fail_subscribe: Option<String>,
+}
+
+struct Connection {
+    id: usize,
+    on_command: Handler<Command>,
+    on_connection_lost: Handler<String>,
+}
+
+/// An in-memory broker, creating connectors which record all publishes.
+///
+/// Clones share the same state, so that a test can keep a handle to the broker, while the engine
+/// uses it as its [`ConnectorFactory`].
+#[derive(Clone, Default)]
+pub struct MockBroker {
+    inner: Rc<RefCell<Inner>>,
+}
+
+impl MockBroker {
+    /// Let the creation of the next connectors fail, or succeed again when `None`.
+    pub fn fail_create(&self, reason: Option<&str>) {
+        self.inner.borrow_mut().fail_create = reason.map(Into::into);
+    }
+
+    /// Let the next connection attempts fail, or succeed again when `None`.
+    pub fn fail_connect(&self, reason: Option<&str>) {
+        self.inner.borrow_mut().fail_connect = reason.map(Into::into);
+    }
+
+    /// Let the next subscriptions fail, or succeed again when `None`.
+    pub fn fail_subscribe(&self, reason: Option<&str>) {
+        self.inner.borrow_mut().fail_subscribe = reason.map(Into::into);
+    }
+
+    /// All messages published so far.
+    pub fn published(&self) -> Vec<Published> {
+        self.inner.borrow().published.clone()
+    }
+
+    /// All messages published to a channel so far.
+    pub fn published_to(&self, channel: &str) -> Vec<Published> {
+        self.inner
+            .borrow()
+            .published
+            .iter()
+            .filter(|published| published.channel == channel)
+            .cloned()
+            .collect()
+    }
+
+    /// Number of connectors created so far.
+    pub fn connectors(&self) -> usize {
+        self.inner.borrow().connectors
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.inner.borrow().connection.is_some()
+    }
+
+    /// Send a command to the device, returns `false` if it isn't connected.
+    pub fn command(&self, name: &str, payload: Option<&[u8]>) -> bool {
+        let on_command = match &self.inner.borrow().connection {
+            Some(connection) => connection.on_command.clone(),
+            None => return false,
+        };
+        on_command.emit(Command {
+            name: name.into(),
+            payload: payload.map(Into::into),
+        });
+        true
+    }
+
+    /// Drop the connection, returns `false` if it wasn't connected.
+    pub fn connection_lost(&self, reason: &str) -> bool {
+        let connection = self.inner.borrow_mut().connection.take();
+        match connection {
+            Some(connection) => {
+                connection.on_connection_lost.emit(reason.into());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl ConnectorFactory for MockBroker {
+    fn create(
+        &self,
+        _: &Settings,
+        on_command: Handler<Command>,
+        on_connection_lost: Handler<String>,
+    ) -> anyhow::Result<Box<dyn Connector>> {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(reason) = &inner.fail_create {
+            bail!("{reason}");
+        }
+        inner.connectors += 1;
+        Ok(Box::new(MockConnector {
+            id: inner.connectors,
+            broker: self.clone(),
+            on_command,
+            on_connection_lost,
+        }))
+    }
+}
+
+pub struct MockConnector {
+    id: usize,
+    broker: MockBroker,
+    on_command: Handler<Command>,
+    on_connection_lost: Handler<String>,
+}
+
+impl Connector for MockConnector {
+    fn connect(&mut self, opts: ConnectOptions) -> anyhow::Result<()> {
+        let fail_connect = self.broker.inner.borrow().fail_connect.clone();
+        match fail_connect {
+            Some(reason) => opts.on_failure.emit(reason),
+            None => {
+                self.broker.inner.borrow_mut().connection = Some(Connection {
+                    id: self.id,
+                    on_command: self.on_command.clone(),
+                    on_connection_lost: self.on_connection_lost.clone(),
+                });
+                opts.on_success.emit(());
+            }
+        }
+        Ok(())
+    }
+
+    fn subscribe(&mut self, opts: SubscribeOptions) -> anyhow::Result<()> {
+        let fail_subscribe = self.broker.inner.borrow().fail_subscribe.clone();
+        match fail_subscribe {
+            Some(reason) => opts.on_failure.emit(reason),
+            None => opts.on_success.emit(()),
+        }
+        Ok(())
+    }
+
+    fn publish(&mut self, channel: &str, payload: Vec<u8>, qos: QoS) {
+        self.broker.inner.borrow_mut().published.push(Published {
+            channel: channel.into(),
+            payload,
+            qos,
+            retain: false,
+        });
+    }
+
+    fn publish_retained(&mut self, topic: &str, payload: Vec<u8>, qos: QoS) -> anyhow::Result<()> {
+        self.broker.inner.borrow_mut().published.push(Published {
+            channel: topic.into(),
+            payload,
+            qos,
+            retain: true,
+        });
+        Ok(())
+    }
+}
+
+impl Drop for MockConnector {
+    fn drop(&mut self) {
+        let mut inner = self.broker.inner.borrow_mut();
+        if matches!(&inner.connection, Some(connection) if connection.id == self.id) {
+            inner.connection = None;
+        }
+    }
+}
+
+/// Settings of a device, running the given simulations, for testing.
+///
+/// The target is a local MQTT broker, which is never connected automatically.
+pub fn settings(simulations: serde_json::Value) -> Settings {
+    serde_json::from_value(serde_json::json!({
+        "autoConnect": false,
+        "target": {"mqtt": {"url": "mqtt://localhost", "credentials": "none"}},
+        "application": "app",
+        "device": "device",
+        "simulations": simulations,
+    }))
+    .unwrap()
+}
+
+/// Runs the engine against a [`MockBroker`].
+///
+/// Simulations spawn tasks, so the harness must run inside a tokio `LocalSet` natively. Running
+/// with a paused clock (`start_paused`) skips ahead to the next timer, instead of waiting for it.
+pub struct Harness {
+    pub engine: Engine,
+    pub broker: MockBroker,
+    /// All connection states reported by the engine, without repetitions.
+    pub states: Vec<State>,
+    rx: UnboundedReceiver<Msg>,
+    outputs: UnboundedReceiver<Output>,
+}
+
+impl Harness {
+    /// Maximum time to wait for a condition.
+    pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+    pub fn new(settings: Settings) -> Self {
+        let (tx, rx) = mpsc::unbounded();
+        let (output_tx, outputs) = mpsc::unbounded();
+        let broker = MockBroker::default();
+
+        let mut engine = Engine::new(
+            Handler::from(move |msg| {
+                tx.unbounded_send(msg).ok();
+            }),
+            Handler::from(move |output| {
+                output_tx.unbounded_send(output).ok();
+            }),
+            broker.clone(),
+        );
+        engine.update_settings(settings);
+
+        let mut harness = Self {
+            engine,
+            broker,
+            states: vec![],
+            rx,
+            outputs,
+        };
+        harness.process();
+        harness
+    }
+
+    /// Handle all pending messages, without waiting for new ones.
+    pub fn process(&mut self) {
+        loop {
+            self.collect_outputs();
+            match self.rx.try_next() {
+                Ok(Some(msg)) => self.engine.update(msg),
+                _ => break,
+            }
+        }
+        self.collect_outputs();
+    }
+
+    /// Handle messages, until the condition is met.
+    ///
+    /// Returns `false` if the condition wasn't met before the timeout.
+    pub async fn run_until<F>(&mut self, f: F) -> bool
+    where
+        F: Fn(&Self) -> bool,
+    {
+        let mut timeout = runtime::sleep(Self::TIMEOUT.as_millis() as u32).fuse();
+        loop {
+            self.process();
+            if f(self) {
+                return true;
+            }
+            select! {
+                msg = self.rx.next() => match msg {
+                    Some(msg) => self.engine.update(msg),
+                    None => return false,
+                },
+                () = timeout => return false,
+            }
+        }
+    }
+
+    /// The current connection state.
+    pub fn state(&self) -> &State {
+        &self.engine.state().state
+    }
+
+    fn collect_outputs(&mut self) {
+        while let Ok(Some(output)) = self.outputs.try_next() {
+            if let Output::State(state) = output {
+                if self.states.last() != Some(&state.state) {
+                    self.states.push(state.state);
+                }
+            }
+        }
+    }
+}
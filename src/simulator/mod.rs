@@ -1,55 +1,90 @@
+#[cfg(target_arch = "wasm32")]
+mod agent;
 mod claims;
-mod mqtt;
-mod publish;
+pub mod cloudevents;
+pub mod coap;
+pub mod engine;
+pub mod homeassistant;
+pub mod local;
+pub mod lorawan;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+pub mod mqtt;
+pub mod publish;
+mod reconcile;
+mod schedule;
 pub mod simulations;
+#[cfg(target_arch = "wasm32")]
+pub mod sse;
+pub mod websocket;
 
+#[cfg(target_arch = "wasm32")]
+pub use agent::*;
 pub use claims::*;
+pub use schedule::*;
 
 use crate::{
     connector::mqtt::QoS,
-    data::{self, SharedDataBridge},
-    settings::{Credentials, PayloadFormat, Settings, Target},
+    settings::{Credentials, Settings},
     simulator::{
-        mqtt::MqttConnector,
         publish::{ChannelState, PublishEvent, Publisher, SimulatorStateUpdate},
-        simulations::{
-            SimulationDescription, SimulationFactory, SimulationHandler, SimulationState,
-        },
+        simulations::{Control, SimulationDescription, SimulationState, SimulatorControl},
     },
+    utils::handler::Handler,
 };
 use chrono::{DateTime, Utc};
 use std::{
-    collections::{btree_map::Entry, BTreeMap, HashMap, HashSet},
+    collections::BTreeMap,
     fmt::{Debug, Display, Formatter},
-    ops::{Deref, DerefMut},
-    rc::Rc,
 };
-use yew::{html::Scope, Callback, Component};
-use yew_agent::*;
 
 pub struct ConnectorOptions<'a> {
     pub url: &'a str,
     pub credentials: &'a Credentials,
     pub settings: &'a Settings,
 
-    pub on_command: Callback<Command>,
-    pub on_connection_lost: Callback<String>,
+    pub on_command: Handler<Command>,
+    pub on_connection_lost: Handler<String>,
 }
 
 pub struct ConnectOptions {
-    pub on_success: Callback<()>,
-    pub on_failure: Callback<String>,
+    pub on_success: Handler<()>,
+    pub on_failure: Handler<String>,
 }
 
 pub struct SubscribeOptions {
-    pub on_success: Callback<()>,
-    pub on_failure: Callback<String>,
+    pub on_success: Handler<()>,
+    pub on_failure: Handler<String>,
 }
 
 pub trait Connector {
     fn connect(&mut self, opts: ConnectOptions) -> anyhow::Result<()>;
     fn subscribe(&mut self, opts: SubscribeOptions) -> anyhow::Result<()>;
     fn publish(&mut self, channel: &str, payload: Vec<u8>, qos: QoS);
+
+    /// Publish a retained message to a topic, as is, bypassing the mapping of channels to topics.
+    ///
+    /// Only supported by MQTT connectors.
+    fn publish_retained(
+        &mut self,
+        topic: &str,
+        _payload: Vec<u8>,
+        _qos: QoS,
+    ) -> anyhow::Result<()> {
+        anyhow::bail!("Retained messages are not supported, unable to publish to: {topic}")
+    }
+}
+
+/// Creates connectors for the target of the settings.
+pub trait ConnectorFactory {
+    /// Create a new connector, failing if the target isn't supported or its configuration is
+    /// invalid.
+    fn create(
+        &self,
+        settings: &Settings,
+        on_command: Handler<Command>,
+        on_connection_lost: Handler<String>,
+    ) -> anyhow::Result<Box<dyn Connector>>;
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -67,67 +102,14 @@ pub struct Event {
 
 pub type SimulatorId = String;
 
-pub struct Simulator {
-    link: AgentLink<Self>,
-    subscribers: HashSet<HandlerId>,
-
-    state: SimulatorState,
-
-    _settings_agent: SharedDataBridge<Settings>,
-    settings: Settings,
-
-    connector: Option<Box<dyn Connector>>,
-    commands: Vec<Command>,
-    events: Vec<Event>,
-
-    simulations: HashMap<SimulatorId, Box<dyn SimulationHandler>>,
-    data: InternalState,
-
-    sim_subs: BTreeMap<SimulatorId, Vec<HandlerId>>,
-    sim_states: BTreeMap<SimulatorId, SimulationState>,
-
-    internal_subs: Vec<HandlerId>,
-}
-
-#[derive(Debug)]
-pub enum Msg {
-    Settings(Settings),
-    Connected,
-    Subscribed,
-    Disconnected(String),
-    Command(Command),
-    PublishEvent(PublishEvent),
-    SimulationState(SimulatorId, SimulationState),
-}
-
-pub enum Request {
-    Start,
-    Stop,
-    Publish { channel: String, payload: Vec<u8> },
-    FetchCommandHistory,
-    FetchEventHistory,
-    SubscribeSimulation(String),
-    UnsubscribeSimulation(String),
-    SubscribeInternalState,
-    UnsubscribeInternalState,
-}
-
-pub enum Response {
-    State(SimulatorState),
-    SimulationState(SimulationState),
-    Command(Rc<Command>),
-    CommandHistory(Vec<Command>),
-    Event(Rc<Event>),
-    EventHistory(Vec<Event>),
-    InternalState(InternalState),
-}
-
 #[derive(Clone, Debug)]
 pub struct SimulatorState {
     pub running: bool,
     pub state: State,
     pub simulations: BTreeMap<String, SimulationDescription>,
     pub claims: Claims,
+    /// State of scheduled simulations, or the error evaluating the schedule.
+    pub schedules: BTreeMap<String, Result<ScheduleState, String>>,
 }
 
 impl Default for SimulatorState {
@@ -137,11 +119,12 @@ impl Default for SimulatorState {
             state: State::Disconnected,
             simulations: Default::default(),
             claims: Default::default(),
+            schedules: Default::default(),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum State {
     Connecting,
     Subscribing,
@@ -172,458 +155,20 @@ impl State {
 #[derive(Clone, Debug, Default)]
 pub struct InternalState(pub BTreeMap<String, ChannelState>);
 
-impl Agent for Simulator {
-    type Reach = Context<Self>;
-    type Message = Msg;
-    type Input = Request;
-    type Output = Response;
-
-    fn create(link: AgentLink<Self>) -> Self {
-        log::info!("Created new simulator");
-
-        let mut settings_agent = SharedDataBridge::new(link.callback(|response| match response {
-            data::Response::State(settings) => Msg::Settings(settings),
-        }));
-        settings_agent.request_state();
-
-        let result = Self {
-            link,
-            subscribers: HashSet::new(),
-            state: Default::default(),
-            _settings_agent: settings_agent,
-            settings: Default::default(),
-            connector: None,
-            commands: vec![],
-            events: vec![],
-            simulations: Default::default(),
-            data: Default::default(),
-            sim_subs: Default::default(),
-            sim_states: Default::default(),
-            internal_subs: Default::default(),
-        };
-
-        // done
-
-        result
-    }
-
-    fn update(&mut self, msg: Self::Message) {
-        log::debug!("update: {msg:?}");
-        match msg {
-            Msg::Settings(settings) => {
-                self.update_settings(settings);
-            }
-            Msg::Connected => {
-                self.state.state = State::Subscribing;
-                self.send_state();
-                if let Some(connector) = &mut self.connector {
-                    if let Err(err) = connector.subscribe(SubscribeOptions {
-                        on_success: self.link.callback(|_| Msg::Subscribed),
-                        on_failure: self.link.callback(|err| Msg::Disconnected(err)),
-                    }) {
-                        log::warn!("Failed to subscribe: {err}");
-                    };
-                }
-            }
-            Msg::Subscribed => {
-                self.state.state = State::Connected;
-                self.send_state();
-            }
-            Msg::Disconnected(err) => {
-                self.state.state = State::Failed(err);
-                self.send_state();
-            }
-            Msg::Command(command) => {
-                // record in history
-
-                self.commands.push(command.clone());
-                let command = Rc::new(command);
-
-                for sim in self.simulations.values_mut() {
-                    sim.command(&command);
-                }
-
-                // broadcast
-
-                for id in &self.subscribers {
-                    self.link
-                        .respond(id.clone(), Response::Command(command.clone()));
-                }
-            }
-            Msg::PublishEvent(event) => {
-                self.publish(event);
-            }
-            Msg::SimulationState(id, state) => {
-                // update global description list
-
-                let changed = match self.state.simulations.entry(id.clone()) {
-                    Entry::Vacant(e) => {
-                        e.insert(state.description.clone());
-                        true
-                    }
-                    Entry::Occupied(mut e) => {
-                        if e.get() != &state.description {
-                            e.insert(state.description.clone());
-                            true
-                        } else {
-                            false
-                        }
-                    }
-                };
-
-                if changed {
-                    self.send_state();
-                }
-
-                // update subscriptions
-
-                self.sim_states.insert(id.clone(), state.clone());
-                if let Some(subs) = self.sim_subs.get(&id) {
-                    for sub in subs {
-                        self.link
-                            .respond(sub.clone(), Response::SimulationState(state.clone()));
-                    }
-                }
-            }
-        }
-    }
-
-    fn connected(&mut self, id: HandlerId) {
-        if id.is_respondable() {
-            self.subscribers.insert(id);
-            self.link.respond(id, Response::State(self.state.clone()));
-        }
-    }
-
-    fn handle_input(&mut self, msg: Self::Input, id: HandlerId) {
-        match msg {
-            Request::Start => {
-                if !self.state.running {
-                    self.start();
-                }
-            }
-            Request::Stop => {
-                if self.state.running {
-                    self.stop();
-                }
-            }
-            Request::Publish { channel, payload } => {
-                self.publish_raw(&channel, payload);
-            }
-            Request::FetchCommandHistory => {
-                if id.is_respondable() {
-                    self.link
-                        .respond(id, Response::CommandHistory(self.commands.clone()));
-                }
-            }
-            Request::FetchEventHistory => {
-                if id.is_respondable() {
-                    self.link
-                        .respond(id, Response::EventHistory(self.events.clone()));
-                }
-            }
-            Request::SubscribeSimulation(sim_id) if id.is_respondable() => {
-                if let Some(state) = self.sim_states.get(&sim_id) {
-                    self.link
-                        .respond(id, Response::SimulationState(state.clone()));
-                }
-
-                match self.sim_subs.entry(sim_id) {
-                    Entry::Occupied(mut e) => {
-                        e.get_mut().push(id);
-                    }
-                    Entry::Vacant(e) => {
-                        e.insert(vec![id]);
-                    }
-                }
-            }
-            Request::SubscribeSimulation(_) => {}
-            Request::UnsubscribeSimulation(sim_id) => match self.sim_subs.entry(sim_id) {
-                Entry::Occupied(mut e) => {
-                    e.get_mut().retain(|i| i != &id);
-                    if e.get().is_empty() {
-                        e.remove();
-                    }
-                }
-                Entry::Vacant(_) => {}
-            },
-            Request::SubscribeInternalState if id.is_respondable() => {
-                self.internal_subs.push(id.clone());
-                self.link
-                    .respond(id, Response::InternalState(self.data.clone()));
-            }
-            Request::SubscribeInternalState => {}
-            Request::UnsubscribeInternalState => {
-                self.internal_subs.retain(|i| i != &id);
-            }
-        }
-    }
-
-    fn disconnected(&mut self, id: HandlerId) {
-        if id.is_respondable() {
-            self.subscribers.remove(&id);
-        }
-    }
-}
-
-impl Simulator {
-    fn send_state(&self) {
-        log::debug!("Broadcast state: {:?}", self.state);
-        for id in &self.subscribers {
-            self.link
-                .respond(id.clone(), Response::State(self.state.clone()));
-        }
-    }
-
-    fn send_internal_state(&self) {
-        for id in &self.internal_subs {
-            self.link
-                .respond(id.clone(), Response::InternalState(self.data.clone()));
-        }
-    }
-
-    fn add_generator(
-        &mut self,
-        id: String,
-        mut generator: Box<dyn SimulationHandler>,
-    ) -> SimulatorId {
-        // start
-
-        let sim_id = id.clone();
-        let ctx = simulations::Context::new(
-            self.link.callback(Msg::PublishEvent),
-            self.link
-                .callback(move |state| Msg::SimulationState(sim_id.clone(), state)),
-        );
-        generator.start(ctx);
-
-        // insert
-
-        self.state
-            .simulations
-            .insert(id.clone(), SimulationDescription { label: id.clone() });
-        self.state
-            .claims
-            .insert(id.clone(), generator.claims().to_vec());
-        self.simulations.insert(id.clone(), generator);
-        self.send_state();
-
-        // return handle
-
-        id
-    }
-
-    fn remove_generator(&mut self, id: &SimulatorId) {
-        self.state.simulations.remove(id);
-        self.state.claims.remove(id);
-
-        if let Some(mut generator) = self.simulations.remove(id) {
-            generator.stop()
-        }
-    }
-
-    fn publish_raw(&mut self, channel: &str, payload: Vec<u8>) {
-        if let Some(connector) = &mut self.connector {
-            connector.publish(channel, payload.clone(), QoS::QoS0);
-
-            let event = Event {
-                timestamp: Utc::now(),
-                channel: channel.to_string(),
-                payload,
-            };
-
-            self.events.push(event.clone());
-
-            let event = Rc::new(event);
-            for id in &self.subscribers {
-                self.link
-                    .respond(id.clone(), Response::Event(event.clone()));
-            }
-        }
-    }
-
-    fn publish(&mut self, event: PublishEvent) {
-        match event {
-            PublishEvent::Full { channel, state } => {
-                self.publish_channel_state(&channel, &state);
-                self.data.0.insert(channel, state);
-            }
-            PublishEvent::Single { channel, state } => {
-                let entry = self.data.0.entry(channel.clone());
-                let state = match entry {
-                    Entry::Vacant(e) => {
-                        let mut features = BTreeMap::new();
-                        features.insert(state.name, state.state);
-                        let state = ChannelState { features };
-                        e.insert(state.clone());
-                        state
-                    }
-                    Entry::Occupied(mut e) => {
-                        let e = e.get_mut();
-                        e.features.insert(state.name, state.state);
-                        e.clone()
-                    }
-                };
-
-                self.publish_channel_state(&channel, &state);
-            }
-        }
-        self.send_internal_state();
-    }
-
-    fn publish_channel_state(&mut self, channel: &str, state: &ChannelState) {
-        if let Ok(payload) = state.to_payload(self.settings.payload.format) {
-            self.publish_raw(&channel, payload);
-        }
-    }
-
-    fn start(&mut self) {
-        self.state.running = true;
-        self.send_state();
-
-        log::info!("Creating client");
-
-        let connector = match &self.settings.target {
-            Target::Mqtt { url, credentials } => {
-                let mut connector = MqttConnector::new(ConnectorOptions {
-                    credentials,
-                    url,
-                    settings: &self.settings,
-                    on_connection_lost: self.link.callback(|err| Msg::Disconnected(err)),
-                    on_command: self.link.callback(|msg| Msg::Command(msg)),
-                });
-
-                self.state.state = State::Connecting;
-                self.send_state();
-
-                if let Err(err) = connector.connect(ConnectOptions {
-                    on_success: self.link.callback(|_| Msg::Connected),
-                    on_failure: self.link.callback(|err| Msg::Disconnected(err)),
-                }) {
-                    log::warn!("Failed to start connecting: {err}");
-                }
-
-                Some(Box::new(connector) as Box<dyn Connector>)
-            }
-            // FIXME: implement HTTP too
-            _ => None,
-        };
-
-        self.connector = connector;
-
-        // Done
-
-        log::info!("Started");
-    }
-
-    fn stop(&mut self) {
-        self.connector.take();
-        self.state.running = false;
-        self.state.state = State::Disconnected;
-        self.send_state();
-    }
-
-    fn update_settings(&mut self, settings: Settings) {
-        self.apply_settings(settings);
-        if self.state.running {
-            // disconnect to trigger reconnect
-            self.stop();
-            self.start();
-        } else if self.settings.auto_connect {
-            // auto-connect on, but not started yet
-            self.start();
-        } else {
-            // only need to send state here, as self.start() already does it
-            self.send_state();
-        }
-    }
-
-    /// Apply the new settings
-    fn apply_settings(&mut self, settings: Settings) {
-        let mut current_sims: HashSet<_> = self.simulations.keys().cloned().collect();
-
-        for (id, sim) in &settings.simulations {
-            self.add_generator(id.clone(), sim.create());
-            current_sims.remove(id);
-        }
-
-        for removed_id in current_sims {
-            self.remove_generator(&removed_id);
-        }
-
-        self.settings = settings;
-    }
-}
-
-impl Publisher for Callback<PublishEvent> {
+impl Publisher for Handler<PublishEvent> {
     fn publish(&self, event: PublishEvent) {
         self.emit(event);
     }
 }
 
-impl SimulatorStateUpdate for Callback<SimulationState> {
+impl SimulatorStateUpdate for Handler<SimulationState> {
     fn state(&self, state: SimulationState) {
         self.emit(state)
     }
 }
 
-pub struct SimulatorBridge(Box<dyn Bridge<Simulator>>);
-
-impl SimulatorBridge {
-    pub fn new(callback: Callback<Response>) -> SimulatorBridge {
-        Self(Simulator::bridge(callback))
-    }
-
-    pub fn from<C, F>(link: &Scope<C>, f: F) -> Self
-    where
-        C: Component,
-        F: Fn(SimulatorState) -> C::Message + 'static,
-    {
-        let callback = link.batch_callback(move |msg| match msg {
-            Response::State(data) => vec![f(data)],
-            _ => vec![],
-        });
-        Self::new(callback)
-    }
-
-    pub fn subscribe_simulation(&mut self, id: String) {
-        self.send(Request::SubscribeSimulation(id));
-    }
-
-    pub fn unsubscribe_simulation(&mut self, id: String) {
-        self.send(Request::UnsubscribeSimulation(id));
-    }
-
-    pub fn start(&mut self) {
-        self.send(Request::Start);
-    }
-
-    pub fn stop(&mut self) {
-        self.send(Request::Stop);
-    }
-
-    pub fn publish<C, P>(&mut self, channel: C, payload: P)
-    where
-        C: Into<String>,
-        P: Into<Vec<u8>>,
-    {
-        self.send(Request::Publish {
-            channel: channel.into(),
-            payload: payload.into(),
-        })
-    }
-}
-
-impl Deref for SimulatorBridge {
-    type Target = Box<dyn Bridge<Simulator>>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
-impl DerefMut for SimulatorBridge {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+impl SimulatorControl for Handler<Control> {
+    fn control(&self, control: Control) {
+        self.emit(control)
     }
 }
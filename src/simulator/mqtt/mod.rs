@@ -0,0 +1,261 @@
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+
+#[cfg(target_arch = "wasm32")]
+pub use wasm::*;
+
+use crate::settings::{MqttTopics, Settings};
+use crate::simulator::Command;
+use anyhow::bail;
+
+/// The topics of a device, created from the templates of the target.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Topics {
+    publish: String,
+    command: Vec<Level>,
+}
+
+/// A level of the command topic template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Level {
+    Literal(String),
+    /// A single level wildcard (`+`).
+    Any,
+    /// A multi level wildcard (`#`), matching the remaining levels.
+    Rest,
+    /// A level containing the command name, between a prefix and a suffix.
+    Command {
+        prefix: String,
+        suffix: String,
+    },
+    /// The remaining levels, being the command name.
+    CommandRest,
+}
+
+impl Topics {
+    /// Create the topics, replacing the application and device in the templates.
+    pub fn new(templates: &MqttTopics, settings: &Settings) -> anyhow::Result<Self> {
+        let replace = |template: &str| {
+            template
+                .replace("{application}", &settings.application)
+                .replace("{device}", &settings.device)
+        };
+
+        let publish = replace(&templates.publish);
+        if publish.contains(['+', '#']) {
+            bail!(
+                "Publish topic must not contain wildcards: {}",
+                templates.publish
+            );
+        }
+
+        if templates.command.matches("{command}").count() != 1 {
+            bail!(
+                "Command topic must contain '{{command}}' exactly once: {}",
+                templates.command
+            );
+        }
+
+        let levels: Vec<_> = templates.command.split('/').collect();
+        let mut command = Vec::with_capacity(levels.len());
+        for (i, level) in levels.iter().enumerate() {
+            let last = i == levels.len() - 1;
+            command.push(match *level {
+                "+" => Level::Any,
+                "#" if last => Level::Rest,
+                "{command}" if last => Level::CommandRest,
+                level if level.contains('#') => {
+                    bail!("Multi level wildcard must be last: {}", templates.command)
+                }
+                level if level.contains('+') => {
+                    bail!("Invalid single level wildcard: {}", templates.command)
+                }
+                level => match level.split_once("{command}") {
+                    Some((prefix, suffix)) => Level::Command {
+                        prefix: replace(prefix),
+                        suffix: replace(suffix),
+                    },
+                    None => Level::Literal(replace(level)),
+                },
+            });
+        }
+
+        Ok(Self { publish, command })
+    }
+
+    /// The topic to publish the events of a channel to.
+    pub fn publish(&self, channel: &str) -> String {
+        self.publish.replace("{channel}", channel)
+    }
+
+    /// The topic to send a command to, or `None` if the template contains single level wildcards.
+    pub fn command(&self, name: &str) -> Option<String> {
+        let mut levels = Vec::with_capacity(self.command.len());
+        for level in &self.command {
+            match level {
+                Level::Literal(literal) => levels.push(literal.clone()),
+                Level::Command { prefix, suffix } => levels.push(format!("{prefix}{name}{suffix}")),
+                Level::CommandRest => levels.push(name.to_string()),
+                // also matches the parent level
+                Level::Rest => {}
+                Level::Any => return None,
+            }
+        }
+        Some(levels.join("/"))
+    }
+
+    /// The topic filter for receiving commands.
+    pub fn command_filter(&self) -> String {
+        self.command
+            .iter()
+            .map(|level| match level {
+                Level::Literal(literal) => literal.as_str(),
+                Level::Any | Level::Command { .. } => "+",
+                Level::Rest | Level::CommandRest => "#",
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Parse a command, from the topic and payload of a received message.
+    pub fn parse_command(&self, topic: &str, payload: Vec<u8>) -> Option<Command> {
+        let mut levels = topic.split('/');
+        let mut name = None;
+
+        for level in &self.command {
+            match level {
+                Level::Rest => {
+                    // consume the remaining levels
+                    levels.by_ref().for_each(drop);
+                    break;
+                }
+                Level::CommandRest => {
+                    name = Some(levels.by_ref().collect::<Vec<_>>().join("/"));
+                    break;
+                }
+                Level::Any => {
+                    levels.next()?;
+                }
+                Level::Literal(literal) => {
+                    if levels.next()? != literal {
+                        return None;
+                    }
+                }
+                Level::Command { prefix, suffix } => {
+                    let level = levels.next()?;
+                    name = Some(
+                        level
+                            .strip_prefix(prefix.as_str())?
+                            .strip_suffix(suffix.as_str())?
+                            .to_string(),
+                    );
+                }
+            }
+        }
+
+        if levels.next().is_some() {
+            // more levels than the template has
+            return None;
+        }
+
+        name.filter(|name| !name.is_empty()).map(|name| Command {
+            name,
+            payload: Some(payload),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn topics(publish: &str, command: &str) -> anyhow::Result<Topics> {
+        Topics::new(
+            &MqttTopics {
+                publish: publish.into(),
+                command: command.into(),
+            },
+            &Settings {
+                application: "app".into(),
+                device: "dev".into(),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn name(topics: &Topics, topic: &str) -> Option<String> {
+        topics
+            .parse_command(topic, vec![])
+            .map(|command| command.name)
+    }
+
+    #[test]
+    fn test_default() {
+        let topics = Topics::new(&Default::default(), &Default::default()).unwrap();
+        assert_eq!(topics.publish("state"), "state");
+        assert_eq!(topics.command_filter(), "command/inbox//#");
+        assert_eq!(
+            topics.parse_command("command/inbox//set-value", b"{}".to_vec()),
+            Some(Command {
+                name: "set-value".into(),
+                payload: Some(b"{}".to_vec())
+            })
+        );
+        assert_eq!(name(&topics, "command/inbox//a/b").as_deref(), Some("a/b"));
+        assert_eq!(
+            topics.command("set-value").as_deref(),
+            Some("command/inbox//set-value")
+        );
+        assert_eq!(name(&topics, "command/inbox/other/set-value"), None);
+        assert_eq!(name(&topics, "command/inbox//"), None);
+        assert_eq!(name(&topics, "state"), None);
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let topics = topics(
+            "{application}/{device}/{channel}",
+            "{device}/cmd/{command}/req",
+        )
+        .unwrap();
+        assert_eq!(topics.publish("state"), "app/dev/state");
+        assert_eq!(topics.command_filter(), "dev/cmd/+/req");
+        assert_eq!(
+            name(&topics, "dev/cmd/reboot/req").as_deref(),
+            Some("reboot")
+        );
+        assert_eq!(name(&topics, "other/cmd/reboot/req"), None);
+        assert_eq!(name(&topics, "dev/cmd/reboot"), None);
+        assert_eq!(name(&topics, "dev/cmd/reboot/req/more"), None);
+        assert_eq!(
+            topics.command("reboot").as_deref(),
+            Some("dev/cmd/reboot/req")
+        );
+    }
+
+    #[test]
+    fn test_wildcards() {
+        let topics = topics("things/{device}/{channel}", "things/+/set-{command}/#").unwrap();
+        assert_eq!(topics.command_filter(), "things/+/+/#");
+        assert_eq!(
+            name(&topics, "things/dev/set-power").as_deref(),
+            Some("power")
+        );
+        assert_eq!(
+            name(&topics, "things/any/set-power/1/2").as_deref(),
+            Some("power")
+        );
+        assert_eq!(name(&topics, "things/dev/get-power"), None);
+        assert_eq!(name(&topics, "things/dev/set-"), None);
+        assert_eq!(topics.command("power"), None);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(topics("{channel}/#", "{command}").is_err());
+        assert!(topics("{channel}", "commands").is_err());
+        assert!(topics("{channel}", "{command}/{command}").is_err());
+        assert!(topics("{channel}", "#/{command}").is_err());
+        assert!(topics("{channel}", "a+/{command}").is_err());
+    }
+}
@@ -1,24 +1,25 @@
-use super::Connector;
+use super::Topics;
 use crate::connector::mqtt::{MqttClient, MqttConnectOptions, MqttMessage, QoS};
-use crate::settings::Credentials;
-use crate::simulator::{Command, ConnectOptions, ConnectorOptions, SubscribeOptions};
-use std::time::Duration;
-use yew::Callback;
+use crate::simulator::{ConnectOptions, Connector, ConnectorOptions, SubscribeOptions};
+use crate::utils::handler::Handler;
+use std::{rc::Rc, time::Duration};
 
+/// MQTT over WebSockets, using the Paho client of the browser.
 pub struct MqttConnector {
     client: MqttClient,
+    topics: Rc<Topics>,
     username: Option<String>,
     password: Option<String>,
 }
 
-trait CallbackExt<IN> {
-    fn filter_reform<T, F>(&self, f: F) -> Callback<T>
+trait HandlerExt<IN> {
+    fn filter_reform<T, F>(&self, f: F) -> Handler<T>
     where
         F: Fn(T) -> Option<IN> + 'static;
 }
 
-impl<IN: 'static> CallbackExt<IN> for Callback<IN> {
-    fn filter_reform<T, F>(&self, func: F) -> Callback<T>
+impl<IN: 'static> HandlerExt<IN> for Handler<IN> {
+    fn filter_reform<T, F>(&self, func: F) -> Handler<T>
     where
         F: Fn(T) -> Option<IN> + 'static,
     {
@@ -28,41 +29,27 @@ impl<IN: 'static> CallbackExt<IN> for Callback<IN> {
                 this.emit(output);
             }
         };
-        Callback::from(func)
+        Handler::from(func)
     }
 }
 
 impl MqttConnector {
-    pub fn new(opts: ConnectorOptions) -> Self {
+    pub fn new(opts: ConnectorOptions, topics: Topics) -> Self {
+        let topics = Rc::new(topics);
         let mut client = MqttClient::new(&opts.url, None);
         client.set_on_connection_lost(opts.on_connection_lost);
-        client.set_on_message_arrived(opts.on_command.filter_reform(|msg: MqttMessage| {
-            if let Some(command) = msg.topic.strip_prefix("command/inbox//") {
-                Some(Command {
-                    name: command.to_string(),
-                    payload: Some(msg.payload),
-                })
-            } else {
-                None
-            }
-        }));
+        client.set_on_message_arrived({
+            let topics = topics.clone();
+            opts.on_command.filter_reform(move |msg: MqttMessage| {
+                topics.parse_command(&msg.topic, msg.payload)
+            })
+        });
 
-        let (username, password) = match opts.credentials {
-            Credentials::None => (None, None),
-            Credentials::Password(password) => (
-                Some(format!(
-                    "{}@{}",
-                    opts.settings.device, opts.settings.application
-                )),
-                Some(password.clone()),
-            ),
-            Credentials::UsernamePassword { username, password } => {
-                (Some(username.clone()), Some(password.clone()))
-            }
-        };
+        let (username, password) = opts.credentials.to_username_password(opts.settings);
 
         Self {
             client,
+            topics,
             username,
             password,
         }
@@ -87,7 +74,7 @@ impl Connector for MqttConnector {
 
     fn subscribe(&mut self, opts: SubscribeOptions) -> anyhow::Result<()> {
         self.client.subscribe(
-            "command/inbox/#",
+            self.topics.command_filter(),
             QoS::QoS0,
             Duration::from_secs(5),
             opts.on_success,
@@ -96,8 +83,15 @@ impl Connector for MqttConnector {
     }
 
     fn publish(&mut self, channel: &str, payload: Vec<u8>, qos: QoS) {
-        if let Err(err) = self.client.publish(channel, payload, qos, false) {
+        if let Err(err) = self
+            .client
+            .publish(self.topics.publish(channel), payload, qos, false)
+        {
             log::info!("Failed to publish: {err}");
         }
     }
+
+    fn publish_retained(&mut self, topic: &str, payload: Vec<u8>, qos: QoS) -> anyhow::Result<()> {
+        self.client.publish(topic, payload, qos, true)
+    }
 }
@@ -143,4 +143,6 @@ pub enum PublishEvent {
         channel: String,
         state: ChannelState,
     },
+    /// A one-shot message, not part of the channel state.
+    Raw { channel: String, payload: Vec<u8> },
 }
@@ -0,0 +1,146 @@
+use crate::settings::{SimulationDiscriminants, SimulationEntry};
+use std::collections::BTreeMap;
+
+/// An action required to bring a simulation in line with the new settings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// A new simulation, which must be created.
+    Add,
+    /// The configuration changed, but the type is the same. It can be updated in place.
+    Update,
+    /// The type of the simulation changed, it must be re-created.
+    Replace,
+    /// The simulation was removed.
+    Remove,
+    /// The configuration is unchanged.
+    Keep,
+}
+
+/// Plan the actions required to get from the current to the new simulations.
+pub fn plan(
+    current: &BTreeMap<String, SimulationEntry>,
+    new: &BTreeMap<String, SimulationEntry>,
+) -> BTreeMap<String, Action> {
+    let mut result = BTreeMap::new();
+
+    for (id, entry) in new {
+        let action = match current.get(id) {
+            None => Action::Add,
+            Some(current) if current.simulation == entry.simulation => Action::Keep,
+            Some(current)
+                if SimulationDiscriminants::from(&current.simulation)
+                    == SimulationDiscriminants::from(&entry.simulation) =>
+            {
+                Action::Update
+            }
+            Some(_) => Action::Replace,
+        };
+        result.insert(id.clone(), action);
+    }
+
+    for id in current.keys() {
+        if !new.contains_key(id) {
+            result.insert(id.clone(), Action::Remove);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        settings::Simulation,
+        simulator::simulations::{led_matrix, sawtooth},
+    };
+    use std::time::Duration;
+
+    fn sawtooth(max: f64) -> SimulationEntry {
+        Simulation::Sawtooth(Box::new(sawtooth::Properties {
+            max: max.into(),
+            length: Duration::from_secs(60),
+            period: Duration::from_secs(1),
+            target: Default::default(),
+        }))
+        .into()
+    }
+
+    fn sims<const N: usize>(
+        entries: [(&str, SimulationEntry); N],
+    ) -> BTreeMap<String, SimulationEntry> {
+        entries
+            .into_iter()
+            .map(|(id, entry)| (id.to_string(), entry))
+            .collect()
+    }
+
+    #[test]
+    fn test_add_remove() {
+        let result = plan(&sims([("a", sawtooth(1.0))]), &sims([("b", sawtooth(1.0))]));
+
+        assert_eq!(
+            result,
+            [
+                ("a".to_string(), Action::Remove),
+                ("b".to_string(), Action::Add),
+            ]
+            .into_iter()
+            .collect()
+        );
+    }
+
+    #[test]
+    fn test_keep_update() {
+        let result = plan(
+            &sims([("a", sawtooth(1.0)), ("b", sawtooth(1.0))]),
+            &sims([("a", sawtooth(1.0)), ("b", sawtooth(2.0))]),
+        );
+
+        assert_eq!(
+            result,
+            [
+                ("a".to_string(), Action::Keep),
+                ("b".to_string(), Action::Update),
+            ]
+            .into_iter()
+            .collect()
+        );
+    }
+
+    #[test]
+    fn test_paused_only() {
+        let mut paused = sawtooth(1.0);
+        paused.paused = true;
+
+        let result = plan(&sims([("a", sawtooth(1.0))]), &sims([("a", paused)]));
+
+        assert_eq!(
+            result,
+            [("a".to_string(), Action::Keep)].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn test_replace() {
+        let result = plan(
+            &sims([("a", sawtooth(1.0))]),
+            &sims([(
+                "a",
+                Simulation::LedMatrix(Box::new(led_matrix::Properties {
+                    target: Default::default(),
+                    color: None,
+                    color_off: None,
+                    rows: 5,
+                    columns: 5,
+                }))
+                .into(),
+            )]),
+        );
+
+        assert_eq!(
+            result,
+            [("a".to_string(), Action::Replace)].into_iter().collect()
+        );
+    }
+}
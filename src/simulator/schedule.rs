@@ -0,0 +1,352 @@
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{str::FromStr, time::Duration};
+
+/// Lifecycle schedule of a simulation.
+///
+/// The schedule defines windows in which the simulation is running. A window starts after the
+/// initial `delay`, or with every occurrence of the `cron` expression. A window lasts for
+/// `duration`, or indefinitely if no duration is set. Inside a window, the `dutyCycle` alternates
+/// between running and stopped.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Schedule {
+    /// Delay the start of the simulation.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "humantime_serde"
+    )]
+    #[schemars(with = "Option<String>")]
+    pub delay: Option<Duration>,
+
+    /// Limit the time the simulation runs, per window.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "humantime_serde"
+    )]
+    #[schemars(with = "Option<String>")]
+    pub duration: Option<Duration>,
+
+    /// Start a new window on a cron-like schedule (in UTC, including seconds).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cron: Option<String>,
+
+    /// Alternate between running and stopped, inside a window.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duty_cycle: Option<DutyCycle>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DutyCycle {
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
+    pub on: Duration,
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
+    pub off: Duration,
+}
+
+/// The evaluated state of a schedule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScheduleState {
+    /// If the simulation should be running.
+    pub active: bool,
+    /// The point in time of the next transition, if there is one.
+    pub next: Option<DateTime<Utc>>,
+}
+
+impl Schedule {
+    /// Validate the schedule, beyond what the schema can express.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(cron) = &self.cron {
+            cron::Schedule::from_str(cron)?;
+        }
+        if let Some(DutyCycle { on, off }) = &self.duty_cycle {
+            if on.is_zero() {
+                bail!("The 'on' time of the duty cycle must not be zero");
+            }
+            to_chrono(*on)?
+                .checked_add(&to_chrono(*off)?)
+                .ok_or_else(|| anyhow!("The duty cycle is too long"))?;
+        }
+        Ok(())
+    }
+
+    /// Evaluate the schedule.
+    ///
+    /// The `origin` is the point in time the simulation was added.
+    pub fn evaluate(
+        &self,
+        origin: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<ScheduleState> {
+        // an invalid duty cycle would cause transitions without any delay
+        self.validate()?;
+
+        let start = add(origin, to_chrono(self.delay.unwrap_or_default())?)?;
+        if now < start {
+            return Ok(ScheduleState {
+                active: false,
+                next: Some(self.first_window(start)?),
+            });
+        }
+
+        let duration = self.duration.map(to_chrono).transpose()?;
+
+        let window = match &self.cron {
+            Some(cron) => {
+                let cron = cron::Schedule::from_str(cron)?;
+                // only windows which started inside the duration can still be active
+                let from = match duration.and_then(|duration| now.checked_sub_signed(duration)) {
+                    Some(from) => std::cmp::max(start, from),
+                    None => start,
+                };
+                match cron.after(&(from - ChronoDuration::milliseconds(1))).next() {
+                    Some(fire) if fire <= now => fire,
+                    _ => {
+                        return Ok(ScheduleState {
+                            active: false,
+                            next: cron.after(&now).next(),
+                        })
+                    }
+                }
+            }
+            None => start,
+        };
+
+        let end = duration.map(|duration| add(window, duration)).transpose()?;
+        if let Some(end) = end {
+            if end <= now {
+                // window is over, wait for the next one
+                let next = match &self.cron {
+                    Some(cron) => cron::Schedule::from_str(cron)?.after(&now).next(),
+                    None => None,
+                };
+                return Ok(ScheduleState {
+                    active: false,
+                    next,
+                });
+            }
+        }
+
+        let (active, next) = match &self.duty_cycle {
+            Some(DutyCycle { on, off }) => {
+                // validated, neither zero nor overflowing
+                let on = to_chrono(*on)?;
+                let cycle = on + to_chrono(*off)?;
+                let cycle_ms = cycle.num_milliseconds().max(1);
+                let pos =
+                    ChronoDuration::milliseconds((now - window).num_milliseconds() % cycle_ms);
+                if pos < on {
+                    (true, add(now, on - pos)?)
+                } else {
+                    (false, add(now, cycle - pos)?)
+                }
+            }
+            None => (true, end.unwrap_or(now)),
+        };
+
+        // transitions are bound by the end of the window
+        let next = match end {
+            Some(end) if next > end => Some(end),
+            None if self.duty_cycle.is_none() => None,
+            _ => Some(next),
+        };
+
+        Ok(ScheduleState { active, next })
+    }
+
+    /// Find the start of the first window, at or after the start.
+    fn first_window(&self, start: DateTime<Utc>) -> anyhow::Result<DateTime<Utc>> {
+        Ok(match &self.cron {
+            Some(cron) => cron::Schedule::from_str(cron)?
+                .after(&(start - ChronoDuration::milliseconds(1)))
+                .next()
+                .unwrap_or(start),
+            None => start,
+        })
+    }
+}
+
+fn to_chrono(duration: Duration) -> anyhow::Result<ChronoDuration> {
+    ChronoDuration::from_std(duration).map_err(|_| anyhow!("Duration out of range: {duration:?}"))
+}
+
+fn add(time: DateTime<Utc>, duration: ChronoDuration) -> anyhow::Result<DateTime<Utc>> {
+    time.checked_add_signed(duration)
+        .ok_or_else(|| anyhow!("Time out of range: {time} + {duration}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn time(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn state(active: bool, next: Option<&str>) -> ScheduleState {
+        ScheduleState {
+            active,
+            next: next.map(time),
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let schedule = Schedule::default();
+        let origin = time("2022-01-01T00:00:00Z");
+
+        assert_eq!(
+            schedule.evaluate(origin, origin).unwrap(),
+            state(true, None)
+        );
+    }
+
+    #[test]
+    fn test_delay_and_duration() {
+        let schedule = Schedule {
+            delay: Some(Duration::from_secs(60)),
+            duration: Some(Duration::from_secs(120)),
+            ..Default::default()
+        };
+        let origin = time("2022-01-01T00:00:00Z");
+
+        assert_eq!(
+            schedule.evaluate(origin, origin).unwrap(),
+            state(false, Some("2022-01-01T00:01:00Z"))
+        );
+        assert_eq!(
+            schedule
+                .evaluate(origin, time("2022-01-01T00:02:00Z"))
+                .unwrap(),
+            state(true, Some("2022-01-01T00:03:00Z"))
+        );
+        assert_eq!(
+            schedule
+                .evaluate(origin, time("2022-01-01T00:03:00Z"))
+                .unwrap(),
+            state(false, None)
+        );
+    }
+
+    #[test]
+    fn test_duty_cycle() {
+        let schedule = Schedule {
+            duty_cycle: Some(DutyCycle {
+                on: Duration::from_secs(10 * 60),
+                off: Duration::from_secs(50 * 60),
+            }),
+            ..Default::default()
+        };
+        let origin = time("2022-01-01T00:00:00Z");
+
+        assert_eq!(
+            schedule
+                .evaluate(origin, time("2022-01-01T00:05:00Z"))
+                .unwrap(),
+            state(true, Some("2022-01-01T00:10:00Z"))
+        );
+        assert_eq!(
+            schedule
+                .evaluate(origin, time("2022-01-01T00:30:00Z"))
+                .unwrap(),
+            state(false, Some("2022-01-01T01:00:00Z"))
+        );
+        assert_eq!(
+            schedule
+                .evaluate(origin, time("2022-01-01T01:00:00Z"))
+                .unwrap(),
+            state(true, Some("2022-01-01T01:10:00Z"))
+        );
+    }
+
+    #[test]
+    fn test_cron() {
+        let schedule = Schedule {
+            // every hour, on the hour
+            cron: Some("0 0 * * * *".into()),
+            duration: Some(Duration::from_secs(5 * 60)),
+            ..Default::default()
+        };
+        let origin = time("2022-01-01T00:30:00Z");
+
+        assert_eq!(
+            schedule.evaluate(origin, origin).unwrap(),
+            state(false, Some("2022-01-01T01:00:00Z"))
+        );
+        assert_eq!(
+            schedule
+                .evaluate(origin, time("2022-01-01T01:02:00Z"))
+                .unwrap(),
+            state(true, Some("2022-01-01T01:05:00Z"))
+        );
+        assert_eq!(
+            schedule
+                .evaluate(origin, time("2022-01-01T01:05:00Z"))
+                .unwrap(),
+            state(false, Some("2022-01-01T02:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn test_zero_duty_cycle() {
+        let origin = time("2022-01-01T00:00:00Z");
+        for (on, off) in [(0, 0), (0, 10)] {
+            let schedule = Schedule {
+                duty_cycle: Some(DutyCycle {
+                    on: Duration::from_secs(on),
+                    off: Duration::from_secs(off),
+                }),
+                ..Default::default()
+            };
+
+            assert!(schedule.validate().is_err());
+            assert!(schedule.evaluate(origin, origin).is_err());
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        let origin = time("2022-01-01T00:00:00Z");
+        let huge = Duration::from_secs(u64::MAX / 2);
+        let far = Duration::from_secs(1_000_000 * 365 * 24 * 60 * 60);
+
+        for schedule in [
+            Schedule {
+                delay: Some(huge),
+                ..Default::default()
+            },
+            Schedule {
+                delay: Some(far),
+                ..Default::default()
+            },
+            Schedule {
+                duration: Some(far),
+                ..Default::default()
+            },
+            Schedule {
+                duty_cycle: Some(DutyCycle { on: far, off: far }),
+                ..Default::default()
+            },
+        ] {
+            assert!(schedule.evaluate(origin, origin).is_err(), "{schedule:?}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_cron() {
+        let schedule = Schedule {
+            cron: Some("foo".into()),
+            ..Default::default()
+        };
+        let origin = time("2022-01-01T00:00:00Z");
+
+        assert!(schedule.evaluate(origin, origin).is_err());
+    }
+}
@@ -1,104 +1,449 @@
+use crate::runtime::{self, Interval};
 use crate::simulator::{
     publish::PublisherExt,
     simulations::{
         default_period, Context, FeatureTarget, Generator, Sender, SenderConfiguration,
-        SenderHandle, SimulationState,
+        SenderHandle, SimulationState, View,
     },
     Claim,
 };
-use crate::utils::ui::details;
+use crate::utils::{
+    float::{ApproxF64, Zero},
+    random::{exponential, random, standard_normal},
+};
+#[cfg(target_arch = "wasm32")]
 use gloo_utils::{format::JsValueSerdeExt, window};
-use patternfly_yew::*;
+use num_traits::ToPrimitive;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::time::Duration;
+use serde_json::{json, Value};
+#[cfg(target_arch = "wasm32")]
+use std::{cell::RefCell, rc::Rc};
+use std::{
+    f64::consts::{PI, TAU},
+    time::Duration,
+};
+#[cfg(target_arch = "wasm32")]
 use wasm_bindgen::{prelude::*, JsCast};
-use wasm_bindgen_futures::spawn_local;
-use web_sys::DeviceOrientationEvent;
-use yew::prelude::*;
+#[cfg(target_arch = "wasm32")]
+use web_sys::{DeviceAcceleration, DeviceMotionEvent, DeviceOrientationEvent, DeviceRotationRate};
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+/// Standard gravity, in m/s².
+pub const G: f64 = 9.80665;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 pub struct Properties {
     #[serde(default = "default_period")]
     #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
     pub delay: Duration,
+    /// The source of the data, defaults to the sensors of the browser.
+    #[serde(default)]
+    pub source: Source,
     #[serde(default)]
     pub target: FeatureTarget,
 }
 
+/// The source of the accelerometer data.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Source {
+    /// Use the sensors of the browser (`devicemotion` and `deviceorientation` events).
+    #[default]
+    Browser,
+    /// A device resting on a table.
+    Idle,
+    /// A device carried by a walking person.
+    Walking {
+        /// Steps per minute.
+        #[serde(default = "default_cadence")]
+        cadence: ApproxF64<Zero, 2>,
+    },
+    /// A device mounted in a vehicle, with road vibrations, acceleration, braking and turns.
+    Vehicle,
+    /// An idle device, which experiences random shocks.
+    Shock {
+        /// Mean number of shocks per minute.
+        rate: ApproxF64<Zero, 2>,
+        /// Peak of a shock, in g.
+        magnitude: ApproxF64<Zero, 2>,
+    },
+}
+
+fn default_cadence() -> ApproxF64<Zero, 2> {
+    110f64.into()
+}
+
 impl SenderConfiguration for Properties {
     fn delay(&self) -> Duration {
         self.delay
     }
 }
 
-pub struct AccelerometerSimulation {
-    claims: Vec<Claim>,
-    properties: Properties,
+/// A vector, in m/s².
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Acceleration {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
 
-    sensor: Option<Sensor>,
-    tx: Option<SenderHandle<State, Properties>>,
+impl Acceleration {
+    #[cfg(target_arch = "wasm32")]
+    fn from_browser(value: Option<DeviceAcceleration>) -> Option<Self> {
+        let value = value?;
+        Some(Self {
+            x: value.x()?,
+            y: value.y()?,
+            z: value.z()?,
+        })
+    }
+
+    fn to_json(self) -> Value {
+        json!({"x": self.x, "y": self.y, "z": self.z})
+    }
 }
 
-struct Sensor {
-    listener: Closure<dyn FnMut(DeviceOrientationEvent)>,
+/// Angles, or angular rates, in degrees (per second).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rotation {
+    pub alpha: f64,
+    pub beta: f64,
+    pub gamma: f64,
+}
+
+impl Rotation {
+    #[cfg(target_arch = "wasm32")]
+    fn from_browser(value: Option<DeviceRotationRate>) -> Option<Self> {
+        let value = value?;
+        Some(Self {
+            alpha: value.alpha()?,
+            beta: value.beta()?,
+            gamma: value.gamma()?,
+        })
+    }
+
+    fn to_json(self) -> Value {
+        json!({"alpha": self.alpha, "beta": self.beta, "gamma": self.gamma})
+    }
 }
 
-#[derive(Clone, Debug)]
-pub enum State {
-    Orientation { x: f64, y: f64, z: f64 },
-    None,
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct State {
+    /// Acceleration, without the effect of gravity, in m/s².
+    pub acceleration: Option<Acceleration>,
+    /// Acceleration, including the effect of gravity, in m/s².
+    pub acceleration_including_gravity: Option<Acceleration>,
+    /// Rotation rate, in degrees per second.
+    pub rotation_rate: Option<Rotation>,
+    /// Orientation, in degrees.
+    pub orientation: Option<Rotation>,
 }
 
 impl State {
-    pub fn to_properties(&self) -> Option<Vec<(&str, f64)>> {
-        match self {
-            State::None => None,
-            State::Orientation { x, y, z } => Some(vec![("x", *x), ("y", *y), ("z", *z)]),
+    pub fn to_properties(&self) -> Option<Vec<(&'static str, Value)>> {
+        let properties: Vec<_> = [
+            ("acceleration", self.acceleration.map(Acceleration::to_json)),
+            (
+                "accelerationIncludingGravity",
+                self.acceleration_including_gravity
+                    .map(Acceleration::to_json),
+            ),
+            ("rotationRate", self.rotation_rate.map(Rotation::to_json)),
+            ("orientation", self.orientation.map(Rotation::to_json)),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| (name, value)))
+        .collect();
+
+        if properties.is_empty() {
+            None
+        } else {
+            Some(properties)
+        }
+    }
+
+    fn to_details(&self) -> Option<Vec<(&'static str, String)>> {
+        let acceleration = |a: Acceleration| format!("x: {:.2}, y: {:.2}, z: {:.2}", a.x, a.y, a.z);
+        let rotation =
+            |r: Rotation| format!("α: {:.1}, β: {:.1}, γ: {:.1}", r.alpha, r.beta, r.gamma);
+
+        let details: Vec<_> = [
+            ("Acceleration (m/s²)", self.acceleration.map(acceleration)),
+            (
+                "Acceleration incl. gravity (m/s²)",
+                self.acceleration_including_gravity.map(acceleration),
+            ),
+            ("Rotation rate (°/s)", self.rotation_rate.map(rotation)),
+            ("Orientation (°)", self.orientation.map(rotation)),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| (name, value)))
+        .collect();
+
+        if details.is_empty() {
+            None
+        } else {
+            Some(details)
         }
     }
 }
 
-impl From<DeviceOrientationEvent> for State {
-    fn from(e: DeviceOrientationEvent) -> Self {
-        match (e.alpha(), e.beta(), e.gamma()) {
-            (Some(alpha), Some(beta), Some(gamma)) => State::Orientation {
-                x: alpha,
-                y: beta,
-                z: gamma,
-            },
-            _ => Self::None,
+/// Generates synthetic motion data for a profile.
+pub struct Synthetic {
+    source: Source,
+    start: f64,
+    last_shock: Option<f64>,
+    next_shock: Option<f64>,
+}
+
+impl Synthetic {
+    pub fn new(source: Source, now: f64) -> Self {
+        Self {
+            source,
+            start: now,
+            last_shock: None,
+            next_shock: None,
+        }
+    }
+
+    /// Create a sample, at a timestamp in milliseconds.
+    pub fn sample<R>(&mut self, now: f64, random: &mut R) -> State
+    where
+        R: FnMut() -> f64,
+    {
+        let t = (now - self.start).max(0.0) / 1000.0;
+
+        if let Source::Shock { rate, .. } = &self.source {
+            let mean = 60_000.0 / rate.0.max(f64::EPSILON);
+            let next = *self
+                .next_shock
+                .get_or_insert_with(|| now + exponential(mean, random));
+            if now >= next {
+                self.last_shock = Some(now);
+                self.next_shock = Some(now + exponential(mean, random));
+            }
+        }
+
+        let mut noise = |sigma: f64| sigma * standard_normal(random);
+
+        let (acceleration, rotation_rate, orientation) = match &self.source {
+            Source::Browser | Source::Idle => (
+                Acceleration {
+                    x: noise(0.02),
+                    y: noise(0.02),
+                    z: noise(0.02),
+                },
+                Rotation {
+                    alpha: noise(0.1),
+                    beta: noise(0.1),
+                    gamma: noise(0.1),
+                },
+                Rotation::default(),
+            ),
+            Source::Walking { cadence } => {
+                // one step per cycle, swaying left and right every two steps
+                let f = cadence.0 / 60.0;
+                let phase = TAU * f * t;
+                (
+                    Acceleration {
+                        x: 1.0 * (phase + PI / 2.0).sin() + noise(0.1),
+                        y: 0.5 * (phase / 2.0).sin() + noise(0.1),
+                        z: 2.5 * phase.sin() + 0.8 * (2.0 * phase).sin() + noise(0.2),
+                    },
+                    Rotation {
+                        alpha: 5.0 * (phase / 2.0).cos() + noise(1.0),
+                        beta: 10.0 * phase.cos() + noise(1.0),
+                        gamma: 8.0 * (phase / 2.0).cos() + noise(1.0),
+                    },
+                    Rotation {
+                        alpha: 0.0,
+                        beta: 5.0 * phase.sin(),
+                        gamma: 8.0 * (phase / 2.0).sin(),
+                    },
+                )
+            }
+            Source::Vehicle => {
+                // accelerating and braking, turning, and road vibrations
+                let longitudinal = TAU * t / 30.0;
+                let turn = TAU * t / 45.0;
+                let yaw_rate = 5.0 * turn.sin();
+                (
+                    Acceleration {
+                        x: 1.5 * longitudinal.sin() + noise(0.3),
+                        y: 1.0 * turn.sin() + noise(0.3),
+                        z: noise(0.5),
+                    },
+                    Rotation {
+                        alpha: yaw_rate + noise(0.5),
+                        beta: noise(0.5),
+                        gamma: noise(0.5),
+                    },
+                    Rotation {
+                        // integral of the yaw rate
+                        alpha: (-5.0 * 45.0 / TAU * turn.cos()).rem_euclid(360.0),
+                        beta: 0.0,
+                        gamma: 0.0,
+                    },
+                )
+            }
+            Source::Shock { magnitude, .. } => {
+                // a damped oscillation
+                let shock = self
+                    .last_shock
+                    .map(|last| {
+                        let dt = (now - last) / 1000.0;
+                        magnitude.0 * G * (-dt / 0.05).exp() * (TAU * 40.0 * dt).cos()
+                    })
+                    .unwrap_or_default();
+
+                (
+                    Acceleration {
+                        x: shock + noise(0.02),
+                        y: 0.5 * shock + noise(0.02),
+                        z: noise(0.02),
+                    },
+                    Rotation {
+                        alpha: noise(0.1),
+                        beta: noise(0.1),
+                        gamma: noise(0.1),
+                    },
+                    Rotation::default(),
+                )
+            }
+        };
+
+        // lying flat, gravity is measured on the z axis
+        let including_gravity = Acceleration {
+            z: acceleration.z + G,
+            ..acceleration
+        };
+
+        State {
+            acceleration: Some(acceleration),
+            acceleration_including_gravity: Some(including_gravity),
+            rotation_rate: Some(rotation_rate),
+            orientation: Some(orientation),
         }
     }
 }
 
+pub struct AccelerometerSimulation {
+    claims: Vec<Claim>,
+    properties: Properties,
+    paused: bool,
+
+    input: Option<Input>,
+    tx: Option<SenderHandle<State, Properties>>,
+}
+
+/// The active input, which is only held to keep it running until dropped.
+#[allow(dead_code)]
+enum Input {
+    #[cfg(target_arch = "wasm32")]
+    Browser(Sensor),
+    Synthetic(Interval),
+}
+
+impl Input {
+    /// Create the input for the source, which is `None` if the source is not available.
+    fn new(properties: &Properties, tx: SenderHandle<State, Properties>) -> Option<Self> {
+        match &properties.source {
+            #[cfg(target_arch = "wasm32")]
+            Source::Browser => Some(Self::Browser(Sensor::new(tx))),
+            #[cfg(not(target_arch = "wasm32"))]
+            Source::Browser => {
+                log::warn!("The browser accelerometer is only available in the browser");
+                None
+            }
+            source => {
+                let mut synthetic = Synthetic::new(source.clone(), runtime::now());
+                let millis = properties
+                    .delay
+                    .as_millis()
+                    .to_u32()
+                    .unwrap_or(u32::MAX)
+                    .max(10);
+                let tx = tx.to_sync();
+                Some(Self::Synthetic(Interval::new(millis, move || {
+                    tx.update(synthetic.sample(runtime::now(), &mut random));
+                })))
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+struct Sensor {
+    motion: Closure<dyn FnMut(DeviceMotionEvent)>,
+    orientation: Closure<dyn FnMut(DeviceOrientationEvent)>,
+}
+
+#[cfg(target_arch = "wasm32")]
 impl Sensor {
     pub fn new(tx: SenderHandle<State, Properties>) -> Self {
-        let listener = Closure::wrap(Box::new(move |e: DeviceOrientationEvent| {
-            let state = State::from(e);
-            let mut tx = tx.clone();
-            spawn_local(async move {
-                tx.update(state).await.ok();
-            });
+        // merge the data of both events
+        let state = Rc::new(RefCell::new(State::default()));
+        let tx = tx.to_sync();
+
+        let motion = {
+            let state = state.clone();
+            let tx = tx.clone();
+            Closure::wrap(Box::new(move |e: DeviceMotionEvent| {
+                let state = {
+                    let mut state = state.borrow_mut();
+                    state.acceleration = Acceleration::from_browser(e.acceleration());
+                    state.acceleration_including_gravity =
+                        Acceleration::from_browser(e.acceleration_including_gravity());
+                    state.rotation_rate = Rotation::from_browser(e.rotation_rate());
+                    state.clone()
+                };
+                tx.update(state);
+            }) as Box<dyn FnMut(DeviceMotionEvent)>)
+        };
+
+        let orientation = Closure::wrap(Box::new(move |e: DeviceOrientationEvent| {
+            let state = {
+                let mut state = state.borrow_mut();
+                state.orientation = match (e.alpha(), e.beta(), e.gamma()) {
+                    (Some(alpha), Some(beta), Some(gamma)) => Some(Rotation { alpha, beta, gamma }),
+                    _ => None,
+                };
+                state.clone()
+            };
+            tx.update(state);
         }) as Box<dyn FnMut(DeviceOrientationEvent)>);
 
-        if let Some(cb) = listener.as_ref().dyn_ref() {
+        if let Some(cb) = motion.as_ref().dyn_ref() {
+            window()
+                .add_event_listener_with_callback("devicemotion", cb)
+                .ok();
+        }
+        if let Some(cb) = orientation.as_ref().dyn_ref() {
             window()
                 .add_event_listener_with_callback("deviceorientation", cb)
                 .ok();
         }
 
-        Self { listener }
+        Self {
+            motion,
+            orientation,
+        }
     }
 }
 
+#[cfg(target_arch = "wasm32")]
 impl Drop for Sensor {
     fn drop(&mut self) {
         log::info!("Dropping sensor");
-        if let Some(cb) = self.listener.as_ref().dyn_ref() {
-            if let Err(err) = window().remove_event_listener_with_callback("deviceorientation", cb)
-            {
-                log::warn!("Failed to remove listener: {:?}", err.into_serde::<Value>());
+        for (event, cb) in [
+            ("devicemotion", self.motion.as_ref().dyn_ref()),
+            ("deviceorientation", self.orientation.as_ref().dyn_ref()),
+        ] {
+            if let Some(cb) = cb {
+                if let Err(err) = window().remove_event_listener_with_callback(event, cb) {
+                    log::warn!("Failed to remove listener: {:?}", err.into_serde::<Value>());
+                }
             }
         }
     }
@@ -112,7 +457,8 @@ impl Generator for AccelerometerSimulation {
         Self {
             claims,
             properties,
-            sensor: None,
+            paused: false,
+            input: None,
             tx: None,
         }
     }
@@ -124,10 +470,18 @@ impl Generator for AccelerometerSimulation {
     fn update(&mut self, properties: Self::Properties) {
         self.claims = properties.target.claims();
         if self.properties != properties {
+            let restart = self.properties.source != properties.source
+                || self.properties.delay != properties.delay;
             self.properties = properties.clone();
             if let Some(tx) = &mut self.tx {
+                if restart {
+                    // drop the current input first
+                    self.input = None;
+                    self.input = Input::new(&properties, tx.clone());
+                }
+
                 let mut tx = tx.clone();
-                spawn_local(async move {
+                runtime::spawn(async move {
                     if let Err(err) = tx.configure(properties).await {
                         log::warn!("Failed to update configuration: {err}");
                     }
@@ -140,43 +494,146 @@ impl Generator for AccelerometerSimulation {
         let (tx, sender) = Sender::new(
             ctx,
             self.properties.clone(),
-            State::None,
+            State::default(),
             |_, ctx, config, state| {
-                let properties = state.to_properties();
-                if let Some(properties) = &properties {
+                if let Some(properties) = state.to_properties() {
                     ctx.publisher().publish_feature(
                         &config.target.channel,
                         &config.target.feature,
-                        properties.clone(),
+                        properties,
                     );
                 }
                 ctx.update(SimulationState {
                     description: config.target.describe("Accelerometer"),
-                    html: properties
-                        .map(|s| details(s))
-                        .unwrap_or_else(|| default_details()),
+                    view: state
+                        .to_details()
+                        .map(View::details)
+                        .unwrap_or_else(default_details),
                 });
             },
         );
 
-        sender.start();
-
-        let sensor = Sensor::new(tx.clone().into());
+        sender.paused(self.paused).start();
 
+        self.input = Input::new(&self.properties, tx.clone());
         self.tx = Some(tx);
-        self.sensor = Some(sensor);
     }
 
     fn stop(&mut self) {
-        self.sensor = None;
+        self.input = None;
         self.tx = None;
     }
+
+    fn pause(&mut self) {
+        self.paused = true;
+        if let Some(tx) = &self.tx {
+            tx.to_sync().pause();
+        }
+    }
+
+    fn resume(&mut self) {
+        self.paused = false;
+        if let Some(tx) = &self.tx {
+            tx.to_sync().resume();
+        }
+    }
+
+    fn step(&mut self) {
+        if let Some(tx) = &self.tx {
+            tx.to_sync().step();
+        }
+    }
+}
+
+fn default_details() -> View {
+    View::Message("No accelerometer data received from browser. This might be due to fact that your browser does not have access to an accelerometer. Most desktop browsers don't have one. Use one of the synthetic motion profiles instead.".into())
 }
 
-fn default_details() -> Html {
-    html!(
-        <Content>
-            { "No accelerometer data received from browser. This might be due to fact that your browser does not have access to an accelerometer. Most desktop browsers don't have one." }
-        </Content>
-    )
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn no_noise() -> impl FnMut() -> f64 {
+        // results in a standard normal value of zero
+        || 0.25
+    }
+
+    #[test]
+    fn test_idle() {
+        let mut synthetic = Synthetic::new(Source::Idle, 0.0);
+        let state = synthetic.sample(1000.0, &mut no_noise());
+
+        let a = state.acceleration.unwrap();
+        assert!(a.x.abs() < 1e-9 && a.y.abs() < 1e-9 && a.z.abs() < 1e-9);
+        assert!((state.acceleration_including_gravity.unwrap().z - G).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_walking() {
+        let mut synthetic = Synthetic::new(
+            Source::Walking {
+                cadence: 120f64.into(),
+            },
+            0.0,
+        );
+
+        // two steps per second, sample a second at 10ms
+        let max = (0..100)
+            .map(|i| {
+                synthetic
+                    .sample(i as f64 * 10.0, &mut no_noise())
+                    .acceleration
+                    .unwrap()
+                    .z
+            })
+            .fold(f64::MIN, f64::max);
+
+        assert!(max > 2.0 && max < 4.0, "Max: {max}");
+    }
+
+    #[test]
+    fn test_shock() {
+        let mut synthetic = Synthetic::new(
+            Source::Shock {
+                rate: 60f64.into(),
+                magnitude: 2f64.into(),
+            },
+            0.0,
+        );
+
+        // the first sample schedules the shock
+        let mut random = || 0.5;
+        let state = synthetic.sample(0.0, &mut random);
+        assert!(state.acceleration.unwrap().x.abs() < 0.1);
+
+        // ln(2) * 1000ms later, the shock happens
+        let state = synthetic.sample(700.0, &mut random);
+        assert!((state.acceleration.unwrap().x - 2.0 * G).abs() < 0.1);
+
+        // and decays
+        let state = synthetic.sample(1200.0, &mut random);
+        assert!(state.acceleration.unwrap().x.abs() < 0.1);
+    }
+
+    #[test]
+    fn test_properties() {
+        assert_eq!(None, State::default().to_properties());
+
+        let state = State {
+            rotation_rate: Some(Rotation {
+                alpha: 1.0,
+                beta: 2.0,
+                gamma: 3.0,
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Some(vec![(
+                "rotationRate",
+                json!({"alpha": 1.0, "beta": 2.0, "gamma": 3.0})
+            )]),
+            state.to_properties()
+        );
+    }
 }
@@ -0,0 +1,658 @@
+use crate::{
+    runtime::{random, sleep, spawn},
+    simulator::{
+        publish::PublisherExt,
+        simulations::{
+            slider::Step, Context, Generator, Sender, SenderConfiguration, SenderHandle,
+            SimulationState, SingleTarget, View,
+        },
+        Claim, Command,
+    },
+    utils::{
+        float::{ApproxF64, Zero},
+        handler::Handler,
+    },
+};
+use anyhow::bail;
+use futures::future::{abortable, AbortHandle};
+use num_traits::ToPrimitive;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+/// Number of steps when ramping a dimmer.
+const RAMP_STEPS: u32 = 10;
+
+/// Behavior common to all actuators.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Behavior {
+    /// Time it takes for the actuator to react.
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "String")]
+    pub latency: Duration,
+    /// Probability (0 to 1) of failing to apply the desired state.
+    #[serde(default = "default_failure_probability")]
+    pub failure_probability: ApproxF64<Zero, 2>,
+}
+
+impl Default for Behavior {
+    fn default() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            failure_probability: default_failure_probability(),
+        }
+    }
+}
+
+fn default_failure_probability() -> ApproxF64<Zero, 2> {
+    0f64.into()
+}
+
+/// An actuator, reflecting a desired state into its reported state.
+pub trait Actuator: 'static {
+    type Properties: SenderConfiguration + PartialEq;
+    type Value: Clone + PartialEq + 'static;
+
+    fn label() -> &'static str;
+
+    fn behavior(properties: &Self::Properties) -> &Behavior;
+    fn target(properties: &Self::Properties) -> &SingleTarget;
+
+    fn initial(properties: &Self::Properties) -> Self::Value;
+    /// Parse a desired state, returns [`None`] if it is invalid.
+    fn parse(properties: &Self::Properties, value: &Value) -> Option<Self::Value>;
+    fn to_json(value: &Self::Value) -> Value;
+
+    /// Render the control, allowing to change the state locally.
+    fn render(
+        properties: &Self::Properties,
+        value: &Self::Value,
+        request: Handler<Self::Value>,
+    ) -> View;
+
+    /// The steps from the current to the desired state.
+    fn transition(
+        _properties: &Self::Properties,
+        _current: &Self::Value,
+        desired: &Self::Value,
+    ) -> Vec<(Duration, Self::Value)> {
+        vec![(Duration::ZERO, desired.clone())]
+    }
+}
+
+pub struct ActuatorSimulation<A: Actuator> {
+    claims: Vec<Claim>,
+    properties: A::Properties,
+    paused: bool,
+
+    current: Rc<RefCell<A::Value>>,
+    sender: Option<SenderHandle<A::Value, A::Properties>>,
+    running: Running,
+}
+
+/// The request currently being applied, if any.
+type Running = Rc<RefCell<Option<AbortHandle>>>;
+
+/// Request a new state, applying latency, failures and the transition.
+///
+/// A request still being applied is aborted, the most recent desired state wins.
+fn request<A: Actuator>(
+    running: &Running,
+    sender: SenderHandle<A::Value, A::Properties>,
+    properties: A::Properties,
+    current: A::Value,
+    desired: A::Value,
+) {
+    let mut sender = sender;
+    let (task, handle) = abortable(async move {
+        let behavior = A::behavior(&properties);
+
+        if !behavior.latency.is_zero() {
+            sleep(behavior.latency.as_millis().to_u32().unwrap_or(u32::MAX)).await;
+        }
+
+        if random() < behavior.failure_probability.0 {
+            log::info!(
+                "{}: simulating failure to apply the desired state",
+                A::label()
+            );
+            return;
+        }
+
+        for (delay, value) in A::transition(&properties, &current, &desired) {
+            if !delay.is_zero() {
+                sleep(delay.as_millis().to_u32().unwrap_or(u32::MAX)).await;
+            }
+            if sender.update(value).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    if let Some(previous) = running.replace(Some(handle)) {
+        previous.abort();
+    }
+    spawn(async move {
+        let _ = task.await;
+    });
+}
+
+impl<A: Actuator> Generator for ActuatorSimulation<A> {
+    type Properties = A::Properties;
+
+    fn new(properties: Self::Properties) -> Self {
+        let claims = A::target(&properties).claims();
+        let current = Rc::new(RefCell::new(A::initial(&properties)));
+        Self {
+            claims,
+            properties,
+            paused: false,
+            current,
+            sender: None,
+            running: Default::default(),
+        }
+    }
+
+    fn claims(&self) -> &[Claim] {
+        &self.claims
+    }
+
+    fn update(&mut self, properties: Self::Properties) {
+        self.claims = A::target(&properties).claims();
+        if self.properties != properties {
+            self.properties = properties.clone();
+            if let Some(sender) = &self.sender {
+                sender.to_sync().configure(properties);
+            }
+        }
+    }
+
+    fn start(&mut self, ctx: Context) {
+        let current = self.current.clone();
+        let running = self.running.clone();
+        let (handle, sender) = Sender::new(
+            ctx,
+            self.properties.clone(),
+            self.current.borrow().clone(),
+            move |handle, ctx, config, state| {
+                current.replace(state.clone());
+
+                let target = A::target(config);
+                ctx.publisher().publish_single(
+                    &target.channel,
+                    &target.feature,
+                    &target.property,
+                    A::to_json(state),
+                );
+
+                let request = {
+                    let handle = handle.clone();
+                    let config = config.clone();
+                    let state = state.clone();
+                    let running = running.clone();
+                    Handler::from(move |desired| {
+                        request::<A>(
+                            &running,
+                            handle.clone(),
+                            config.clone(),
+                            state.clone(),
+                            desired,
+                        )
+                    })
+                };
+
+                ctx.update(SimulationState {
+                    description: target.describe(A::label()),
+                    view: View::Stack(vec![
+                        View::details([("Value", A::to_json(state))]),
+                        A::render(config, state, request),
+                    ]),
+                });
+            },
+        );
+
+        sender.paused(self.paused).start();
+
+        self.sender = Some(handle);
+    }
+
+    fn stop(&mut self) {
+        if let Some(running) = self.running.take() {
+            running.abort();
+        }
+        self.sender = None;
+    }
+
+    fn pause(&mut self) {
+        self.paused = true;
+        if let Some(sender) = &self.sender {
+            sender.to_sync().pause();
+        }
+    }
+
+    fn resume(&mut self) {
+        self.paused = false;
+        if let Some(sender) = &self.sender {
+            sender.to_sync().resume();
+        }
+    }
+
+    fn step(&mut self) {
+        if let Some(sender) = &self.sender {
+            sender.to_sync().step();
+        }
+    }
+
+    fn command(&mut self, command: &Command) {
+        let target = A::target(&self.properties);
+        if command.name != target.channel {
+            return;
+        }
+
+        let sender = match &self.sender {
+            Some(sender) => sender.clone(),
+            None => return,
+        };
+
+        let desired = command
+            .payload
+            .as_ref()
+            .and_then(|payload| serde_json::from_slice::<Value>(payload).ok())
+            .and_then(|json| A::parse(&self.properties, &json[&target.feature][&target.property]));
+
+        if let Some(desired) = desired {
+            let current = self.current.borrow().clone();
+            request::<A>(
+                &self.running,
+                sender,
+                self.properties.clone(),
+                current,
+                desired,
+            );
+        }
+    }
+}
+
+// switch
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchProperties {
+    #[serde(default)]
+    pub initial: bool,
+
+    #[serde(flatten)]
+    pub behavior: Behavior,
+    #[serde(default)]
+    pub target: SingleTarget,
+}
+
+impl SenderConfiguration for SwitchProperties {
+    fn delay(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+pub struct SwitchActuator;
+
+pub type SwitchSimulation = ActuatorSimulation<SwitchActuator>;
+
+impl Actuator for SwitchActuator {
+    type Properties = SwitchProperties;
+    type Value = bool;
+
+    fn label() -> &'static str {
+        "Switch"
+    }
+
+    fn behavior(properties: &Self::Properties) -> &Behavior {
+        &properties.behavior
+    }
+
+    fn target(properties: &Self::Properties) -> &SingleTarget {
+        &properties.target
+    }
+
+    fn initial(properties: &Self::Properties) -> Self::Value {
+        properties.initial
+    }
+
+    fn parse(_: &Self::Properties, value: &Value) -> Option<Self::Value> {
+        match value {
+            Value::Bool(value) => Some(*value),
+            Value::String(value) => match value.as_str() {
+                "on" | "true" => Some(true),
+                "off" | "false" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn to_json(value: &Self::Value) -> Value {
+        json!(value)
+    }
+
+    fn render(_: &Self::Properties, value: &Self::Value, request: Handler<Self::Value>) -> View {
+        View::Switch {
+            checked: *value,
+            onchange: request,
+        }
+    }
+}
+
+// dimmer
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DimmerProperties {
+    #[serde(default = "default_dimmer_min")]
+    pub min: ApproxF64<Zero, 2>,
+    #[serde(default = "default_dimmer_max")]
+    pub max: ApproxF64<Zero, 2>,
+    #[serde(default = "default_dimmer_min")]
+    pub initial: ApproxF64<Zero, 2>,
+    /// Time to ramp from the minimum to the maximum.
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "String")]
+    pub ramp: Duration,
+
+    #[serde(flatten)]
+    pub behavior: Behavior,
+    #[serde(default)]
+    pub target: SingleTarget,
+}
+
+fn default_dimmer_min() -> ApproxF64<Zero, 2> {
+    0f64.into()
+}
+
+fn default_dimmer_max() -> ApproxF64<Zero, 2> {
+    100f64.into()
+}
+
+impl DimmerProperties {
+    /// Validate the range of the dimmer.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.min.0 > self.max.0 {
+            bail!("The minimum of the dimmer must not exceed its maximum");
+        }
+        Ok(())
+    }
+
+    /// Limit a value to the range of the dimmer.
+    fn clamp(&self, value: f64) -> f64 {
+        // unlike f64::clamp, this doesn't panic for an invalid range
+        value.max(self.min.0).min(self.max.0)
+    }
+}
+
+impl SenderConfiguration for DimmerProperties {
+    fn delay(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+pub struct DimmerActuator;
+
+pub type DimmerSimulation = ActuatorSimulation<DimmerActuator>;
+
+impl Actuator for DimmerActuator {
+    type Properties = DimmerProperties;
+    type Value = f64;
+
+    fn label() -> &'static str {
+        "Dimmer"
+    }
+
+    fn behavior(properties: &Self::Properties) -> &Behavior {
+        &properties.behavior
+    }
+
+    fn target(properties: &Self::Properties) -> &SingleTarget {
+        &properties.target
+    }
+
+    fn initial(properties: &Self::Properties) -> Self::Value {
+        properties.clamp(properties.initial.0)
+    }
+
+    fn parse(properties: &Self::Properties, value: &Value) -> Option<Self::Value> {
+        value.as_f64().map(|value| properties.clamp(value))
+    }
+
+    fn to_json(value: &Self::Value) -> Value {
+        json!(value)
+    }
+
+    fn render(
+        properties: &Self::Properties,
+        value: &Self::Value,
+        request: Handler<Self::Value>,
+    ) -> View {
+        View::Slider {
+            min: Step::Value(properties.min),
+            max: Step::Value(properties.max),
+            value: *value,
+            onchange: request,
+        }
+    }
+
+    fn transition(
+        properties: &Self::Properties,
+        current: &Self::Value,
+        desired: &Self::Value,
+    ) -> Vec<(Duration, Self::Value)> {
+        ramp(
+            properties.ramp,
+            properties.max.0 - properties.min.0,
+            *current,
+            *desired,
+        )
+    }
+}
+
+/// Create the steps for ramping from the current to the desired value.
+///
+/// The ramp time is for the full range, a smaller change will take proportionally less time.
+fn ramp(ramp: Duration, range: f64, current: f64, desired: f64) -> Vec<(Duration, f64)> {
+    let delta = desired - current;
+    if ramp.is_zero() || range <= 0.0 || delta == 0.0 {
+        return vec![(Duration::ZERO, desired)];
+    }
+
+    let duration = ramp.mul_f64((delta.abs() / range).min(1.0));
+    let step = duration / RAMP_STEPS;
+
+    (1..=RAMP_STEPS)
+        .map(|i| {
+            let value = if i == RAMP_STEPS {
+                desired
+            } else {
+                current + delta * i as f64 / RAMP_STEPS as f64
+            };
+            (step, value)
+        })
+        .collect()
+}
+
+// mode selector
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ModeSelectorProperties {
+    pub modes: Vec<String>,
+    /// The initial mode, defaults to the first mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial: Option<String>,
+
+    #[serde(flatten)]
+    pub behavior: Behavior,
+    #[serde(default)]
+    pub target: SingleTarget,
+}
+
+impl SenderConfiguration for ModeSelectorProperties {
+    fn delay(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+pub struct ModeSelectorActuator;
+
+pub type ModeSelectorSimulation = ActuatorSimulation<ModeSelectorActuator>;
+
+impl Actuator for ModeSelectorActuator {
+    type Properties = ModeSelectorProperties;
+    type Value = String;
+
+    fn label() -> &'static str {
+        "Mode selector"
+    }
+
+    fn behavior(properties: &Self::Properties) -> &Behavior {
+        &properties.behavior
+    }
+
+    fn target(properties: &Self::Properties) -> &SingleTarget {
+        &properties.target
+    }
+
+    fn initial(properties: &Self::Properties) -> Self::Value {
+        properties
+            .initial
+            .clone()
+            .or_else(|| properties.modes.first().cloned())
+            .unwrap_or_default()
+    }
+
+    fn parse(properties: &Self::Properties, value: &Value) -> Option<Self::Value> {
+        value
+            .as_str()
+            .filter(|value| properties.modes.iter().any(|mode| mode == value))
+            .map(ToString::to_string)
+    }
+
+    fn to_json(value: &Self::Value) -> Value {
+        json!(value)
+    }
+
+    fn render(
+        properties: &Self::Properties,
+        value: &Self::Value,
+        request: Handler<Self::Value>,
+    ) -> View {
+        View::Toolbar(
+            properties
+                .modes
+                .iter()
+                .map(|mode| {
+                    let onclick = {
+                        let mode = mode.clone();
+                        request.reform(move |_| mode.clone())
+                    };
+                    View::Button {
+                        label: mode.clone(),
+                        primary: mode == value,
+                        onclick,
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ramp_immediate() {
+        assert_eq!(
+            vec![(Duration::ZERO, 50.0)],
+            ramp(Duration::ZERO, 100.0, 0.0, 50.0)
+        );
+        assert_eq!(
+            vec![(Duration::ZERO, 50.0)],
+            ramp(Duration::from_secs(1), 100.0, 50.0, 50.0)
+        );
+    }
+
+    #[test]
+    fn test_ramp_proportional() {
+        let steps = ramp(Duration::from_secs(10), 100.0, 0.0, 50.0);
+
+        assert_eq!(RAMP_STEPS as usize, steps.len());
+        assert_eq!(Duration::from_millis(500), steps[0].0);
+        assert_eq!(5.0, steps[0].1);
+        assert_eq!(50.0, steps.last().unwrap().1);
+
+        let total: Duration = steps.iter().map(|(delay, _)| *delay).sum();
+        assert_eq!(Duration::from_secs(5), total);
+    }
+
+    #[test]
+    fn test_ramp_down() {
+        let steps = ramp(Duration::from_secs(1), 100.0, 100.0, 0.0);
+
+        assert_eq!(90.0, steps[0].1);
+        assert_eq!(0.0, steps.last().unwrap().1);
+    }
+
+    #[test]
+    fn test_parse_switch() {
+        let properties = SwitchProperties {
+            initial: false,
+            behavior: Default::default(),
+            target: Default::default(),
+        };
+
+        assert_eq!(Some(true), SwitchActuator::parse(&properties, &json!(true)));
+        assert_eq!(
+            Some(false),
+            SwitchActuator::parse(&properties, &json!("off"))
+        );
+        assert_eq!(None, SwitchActuator::parse(&properties, &json!(1)));
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        let properties = ModeSelectorProperties {
+            modes: vec!["eco".into(), "comfort".into()],
+            initial: None,
+            behavior: Default::default(),
+            target: Default::default(),
+        };
+
+        assert_eq!("eco", ModeSelectorActuator::initial(&properties));
+        assert_eq!(
+            Some("comfort".to_string()),
+            ModeSelectorActuator::parse(&properties, &json!("comfort"))
+        );
+        assert_eq!(
+            None,
+            ModeSelectorActuator::parse(&properties, &json!("turbo"))
+        );
+    }
+
+    #[test]
+    fn test_dimmer_range() {
+        let mut properties = DimmerProperties {
+            min: 10f64.into(),
+            max: 50f64.into(),
+            initial: 0f64.into(),
+            ramp: Duration::ZERO,
+            behavior: Default::default(),
+            target: Default::default(),
+        };
+
+        assert!(properties.validate().is_ok());
+        assert_eq!(10.0, DimmerActuator::initial(&properties));
+        assert_eq!(Some(50.0), DimmerActuator::parse(&properties, &json!(80)));
+
+        properties.min = 60f64.into();
+        assert!(properties.validate().is_err());
+    }
+}
@@ -0,0 +1,412 @@
+use super::default_period;
+use crate::{
+    simulator::{
+        publish::PublisherExt,
+        simulations::{
+            tick::{TickState, TickedGenerator},
+            Context, Control, FeatureTarget, SimulationState, View,
+        },
+        Claim, Schedule,
+    },
+    utils::float::{ApproxF64, Zero},
+};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+const MILLIS_PER_HOUR: f64 = 3_600_000.0;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Properties {
+    #[serde(default = "default_period", with = "humantime_serde")]
+    #[schemars(with = "String")]
+    pub period: Duration,
+
+    /// Capacity of the battery, in mAh.
+    pub capacity: ApproxF64<Zero, 2>,
+    /// Initial level, in percent.
+    #[serde(default = "default_initial_level")]
+    pub initial_level: ApproxF64<Zero, 2>,
+
+    /// Current drawn by the idle device, in mA.
+    pub idle_current: ApproxF64<Zero, 2>,
+    /// Charge consumed by publishing a single message, in mAh.
+    #[serde(default = "default_publish_charge")]
+    pub publish_charge: ApproxF64<Zero, 2>,
+
+    /// Current when charging, in mA.
+    #[serde(default = "default_charge_current")]
+    pub charge_current: ApproxF64<Zero, 2>,
+    /// Windows in which the battery is being charged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub charging: Option<Schedule>,
+
+    /// Open circuit voltage, by level. Must be ordered by level.
+    #[serde(default = "default_voltage_curve")]
+    pub voltage_curve: Vec<VoltagePoint>,
+
+    /// Speed up time, to drain (or charge) the battery faster.
+    #[serde(default = "default_acceleration")]
+    pub acceleration: ApproxF64<Zero, 2>,
+
+    /// Emulate a dead device, stopping all other simulations and disconnecting, when the battery
+    /// is empty.
+    #[serde(default)]
+    pub shutdown: bool,
+
+    #[serde(default)]
+    pub target: FeatureTarget,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct VoltagePoint {
+    /// The level, in percent.
+    pub level: ApproxF64<Zero, 2>,
+    /// The voltage, in volts.
+    pub voltage: ApproxF64<Zero, 2>,
+}
+
+impl VoltagePoint {
+    pub fn new(level: f64, voltage: f64) -> Self {
+        Self {
+            level: level.into(),
+            voltage: voltage.into(),
+        }
+    }
+}
+
+fn default_initial_level() -> ApproxF64<Zero, 2> {
+    100f64.into()
+}
+
+fn default_publish_charge() -> ApproxF64<Zero, 2> {
+    0.01f64.into()
+}
+
+fn default_charge_current() -> ApproxF64<Zero, 2> {
+    0f64.into()
+}
+
+fn default_acceleration() -> ApproxF64<Zero, 2> {
+    1f64.into()
+}
+
+/// A simplified discharge curve of a single Li-ion cell.
+pub fn default_voltage_curve() -> Vec<VoltagePoint> {
+    vec![
+        VoltagePoint::new(0.0, 3.0),
+        VoltagePoint::new(10.0, 3.5),
+        VoltagePoint::new(20.0, 3.65),
+        VoltagePoint::new(50.0, 3.8),
+        VoltagePoint::new(80.0, 3.95),
+        VoltagePoint::new(100.0, 4.2),
+    ]
+}
+
+/// Evaluate the voltage for a level, interpolating linearly between the points of the curve.
+pub fn voltage(curve: &[VoltagePoint], level: f64) -> f64 {
+    let (first, last) = match (curve.first(), curve.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return 0.0,
+    };
+
+    if level <= first.level.0 {
+        return first.voltage.0;
+    }
+
+    for window in curve.windows(2) {
+        let (a, b) = (&window[0], &window[1]);
+        if level <= b.level.0 {
+            let range = b.level.0 - a.level.0;
+            if range <= 0.0 {
+                return b.voltage.0;
+            }
+            return a.voltage.0 + (b.voltage.0 - a.voltage.0) * (level - a.level.0) / range;
+        }
+    }
+
+    last.voltage.0
+}
+
+/// The electrical model of the battery.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Model {
+    /// Capacity, in mAh.
+    pub capacity: f64,
+    /// Remaining charge, in mAh.
+    pub charge: f64,
+    /// Idle current, in mA.
+    pub idle_current: f64,
+    /// Charge per message, in mAh.
+    pub publish_charge: f64,
+    /// Charging current, in mA.
+    pub charge_current: f64,
+}
+
+/// A transition of the battery state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transition {
+    None,
+    Empty,
+    Recovered,
+}
+
+impl Model {
+    /// The level, in percent.
+    pub fn level(&self) -> f64 {
+        if self.capacity <= 0.0 {
+            0.0
+        } else {
+            self.charge / self.capacity * 100.0
+        }
+    }
+
+    /// Advance the model by a number of hours, having published a number of messages.
+    pub fn step(&mut self, hours: f64, messages: u64, charging: bool) -> Transition {
+        let was_empty = self.charge <= 0.0;
+
+        let mut delta = -(self.idle_current * hours + self.publish_charge * messages as f64);
+        if charging {
+            delta += self.charge_current * hours;
+        }
+        self.charge = (self.charge + delta).clamp(0.0, self.capacity.max(0.0));
+
+        match (was_empty, self.charge <= 0.0) {
+            (false, true) => Transition::Empty,
+            (true, false) => Transition::Recovered,
+            _ => Transition::None,
+        }
+    }
+}
+
+pub struct State {
+    properties: Properties,
+
+    model: Model,
+    /// Origin of the charging schedule
+    origin: DateTime<Utc>,
+    last: Option<(f64, u64)>,
+    empty: bool,
+}
+
+impl TickState for State {
+    fn period(&self) -> Duration {
+        self.properties.period
+    }
+}
+
+pub struct BatteryGenerator;
+
+impl TickedGenerator for BatteryGenerator {
+    type Properties = Properties;
+    type State = State;
+
+    fn make_state(
+        properties: &Self::Properties,
+        current_state: Option<Self::State>,
+    ) -> Self::State {
+        let capacity = properties.capacity.0;
+        let (charge, origin, last, empty) = match current_state {
+            // keep the charge, relative to the (new) capacity
+            Some(state) => (
+                state.model.level() / 100.0 * capacity,
+                state.origin,
+                state.last,
+                state.empty,
+            ),
+            None => (
+                properties.initial_level.0.clamp(0.0, 100.0) / 100.0 * capacity,
+                Utc::now(),
+                None,
+                false,
+            ),
+        };
+
+        Self::State {
+            properties: properties.clone(),
+            model: Model {
+                capacity,
+                charge,
+                idle_current: properties.idle_current.0,
+                publish_charge: properties.publish_charge.0,
+                charge_current: properties.charge_current.0,
+            },
+            origin,
+            last,
+            empty,
+        }
+    }
+
+    fn make_claims(properties: &Self::Properties) -> Vec<Claim> {
+        properties.target.claims()
+    }
+
+    fn tick(now: f64, state: &mut Self::State, ctx: &mut Context) {
+        let published = ctx.published();
+
+        let charging = match &state.properties.charging {
+            Some(schedule) => match schedule.evaluate(state.origin, Utc::now()) {
+                Ok(schedule) => schedule.active,
+                Err(err) => {
+                    log::info!("Failed to evaluate charging schedule: {err}");
+                    false
+                }
+            },
+            None => false,
+        };
+
+        if state.last.is_none() && state.model.charge <= 0.0 {
+            // started empty
+            state.empty = true;
+            if state.properties.shutdown {
+                ctx.control(Control::PowerLoss);
+            }
+        } else if let Some((last_time, last_published)) = state.last {
+            let hours =
+                (now - last_time).max(0.0) / MILLIS_PER_HOUR * state.properties.acceleration.0;
+            let messages = published.saturating_sub(last_published);
+
+            match state.model.step(hours, messages, charging) {
+                Transition::Empty => {
+                    state.empty = true;
+                    if state.properties.shutdown {
+                        ctx.control(Control::PowerLoss);
+                    }
+                }
+                Transition::Recovered => {
+                    state.empty = false;
+                    if state.properties.shutdown {
+                        ctx.control(Control::PowerRestored);
+                    }
+                }
+                Transition::None => {}
+            }
+        }
+        // the publish below is counted as well, but only other messages drain the battery
+        state.last = Some((now, published + 1));
+
+        let level = state.model.level();
+        let voltage = voltage(&state.properties.voltage_curve, level);
+
+        ctx.update(SimulationState {
+            description: state.properties.target.describe("Battery"),
+            view: View::details([
+                ("Level", format!("{level:.1} %")),
+                ("Voltage", format!("{voltage:.2} V")),
+                ("Charge", format!("{:.1} mAh", state.model.charge)),
+                ("Charging", charging.to_string()),
+                ("Empty", state.empty.to_string()),
+            ]),
+        });
+
+        ctx.publisher().publish_feature(
+            &state.properties.target.channel,
+            &state.properties.target.feature,
+            [
+                ("level", json!(level)),
+                ("voltage", json!(voltage)),
+                ("charging", json!(charging)),
+            ],
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{simulator::publish::PublishEvent, utils::handler::Handler};
+    use std::{cell::Cell, rc::Rc};
+
+    fn model() -> Model {
+        Model {
+            capacity: 1000.0,
+            charge: 1000.0,
+            idle_current: 100.0,
+            publish_charge: 1.0,
+            charge_current: 500.0,
+        }
+    }
+
+    #[test]
+    fn test_voltage() {
+        let curve = default_voltage_curve();
+
+        assert_eq!(3.0, voltage(&curve, -1.0));
+        assert_eq!(3.0, voltage(&curve, 0.0));
+        assert!((voltage(&curve, 5.0) - 3.25).abs() < 0.0001);
+        assert!((voltage(&curve, 35.0) - 3.725).abs() < 0.0001);
+        assert_eq!(4.2, voltage(&curve, 100.0));
+        assert_eq!(4.2, voltage(&curve, 150.0));
+        assert_eq!(0.0, voltage(&[], 50.0));
+    }
+
+    #[test]
+    fn test_discharge() {
+        let mut model = model();
+
+        // one hour idle, and 100 messages
+        assert_eq!(Transition::None, model.step(1.0, 100, false));
+        assert!((model.charge - 800.0).abs() < 0.0001);
+        assert!((model.level() - 80.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_empty_and_recover() {
+        let mut model = model();
+
+        assert_eq!(Transition::Empty, model.step(20.0, 0, false));
+        assert_eq!(0.0, model.charge);
+
+        // stays empty
+        assert_eq!(Transition::None, model.step(1.0, 0, false));
+
+        // charging: +500, -100
+        assert_eq!(Transition::Recovered, model.step(1.0, 0, true));
+        assert!((model.charge - 400.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_charge_is_capped() {
+        let mut model = model();
+
+        assert_eq!(Transition::None, model.step(10.0, 0, true));
+        assert_eq!(1000.0, model.charge);
+    }
+
+    #[test]
+    fn test_own_publishes_are_not_counted() {
+        let properties: Properties = serde_json::from_value(json!({
+            "capacity": 1000.0,
+            "idleCurrent": 0.0,
+            "publishCharge": 10.0,
+        }))
+        .unwrap();
+        let mut state = BatteryGenerator::make_state(&properties, None);
+
+        // the engine counts every event, including those of the battery itself
+        let published = Rc::new(Cell::new(0u64));
+        let mut ctx = {
+            let published = published.clone();
+            Context::new(
+                Handler::from(move |_: PublishEvent| published.set(published.get() + 1)),
+                Handler::<SimulationState>::noop(),
+                Handler::<Control>::noop(),
+                published,
+            )
+        };
+
+        for i in 0..10 {
+            BatteryGenerator::tick(i as f64 * 1000.0, &mut state, &mut ctx);
+        }
+        assert_eq!(published.get(), 10);
+        assert_eq!(state.model.charge, 1000.0);
+
+        // messages of other simulations do drain it
+        published.set(published.get() + 5);
+        BatteryGenerator::tick(10_000.0, &mut state, &mut ctx);
+        assert_eq!(state.model.charge, 950.0);
+    }
+}
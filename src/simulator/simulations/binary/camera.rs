@@ -0,0 +1,150 @@
+//! Capture snapshots from the browser camera.
+
+use anyhow::anyhow;
+use gloo_utils::{document, window};
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{
+    CanvasRenderingContext2d, HtmlCanvasElement, HtmlVideoElement, MediaStream,
+    MediaStreamConstraints, MediaStreamTrack,
+};
+
+const JPEG_PREFIX: &str = "data:image/jpeg;base64,";
+
+#[derive(Clone, Debug)]
+pub enum CameraState {
+    Requesting,
+    Streaming(MediaStream),
+    Failed(String),
+}
+
+pub struct Camera {
+    video: HtmlVideoElement,
+    canvas: HtmlCanvasElement,
+    state: Rc<RefCell<CameraState>>,
+}
+
+impl Camera {
+    /// Request access to the camera, and start streaming once it is granted.
+    pub fn new() -> anyhow::Result<Self> {
+        let video: HtmlVideoElement = document()
+            .create_element("video")
+            .map_err(|err| anyhow!("Failed to create video element: {err:?}"))?
+            .unchecked_into();
+        video.set_autoplay(true);
+        video.set_muted(true);
+
+        let canvas: HtmlCanvasElement = document()
+            .create_element("canvas")
+            .map_err(|err| anyhow!("Failed to create canvas element: {err:?}"))?
+            .unchecked_into();
+
+        let state = Rc::new(RefCell::new(CameraState::Requesting));
+
+        {
+            let video = video.clone();
+            let state = state.clone();
+            spawn_local(async move {
+                let result = Self::request(&video).await;
+                if let (Ok(stream), 1) = (&result, Rc::strong_count(&state)) {
+                    // the camera was dropped in the meantime
+                    stop(stream);
+                    return;
+                }
+                *state.borrow_mut() = match result {
+                    Ok(stream) => CameraState::Streaming(stream),
+                    Err(err) => {
+                        log::warn!("Failed to access camera: {err}");
+                        CameraState::Failed(err.to_string())
+                    }
+                };
+            });
+        }
+
+        Ok(Self {
+            video,
+            canvas,
+            state,
+        })
+    }
+
+    async fn request(video: &HtmlVideoElement) -> anyhow::Result<MediaStream> {
+        let promise = window()
+            .navigator()
+            .media_devices()
+            .map_err(|err| anyhow!("No media devices: {err:?}"))?
+            .get_user_media_with_constraints(MediaStreamConstraints::new().video(&true.into()))
+            .map_err(|err| anyhow!("Failed to request camera: {err:?}"))?;
+
+        let stream: MediaStream = JsFuture::from(promise)
+            .await
+            .map_err(|err| anyhow!("Access to camera denied: {err:?}"))?
+            .unchecked_into();
+
+        video.set_src_object(Some(&stream));
+
+        Ok(stream)
+    }
+
+    pub fn state(&self) -> CameraState {
+        self.state.borrow().clone()
+    }
+
+    /// Capture a snapshot, encoded as JPEG.
+    ///
+    /// Returns `None` if the camera isn't streaming (yet).
+    pub fn capture(&self, width: u32, height: u32) -> anyhow::Result<Option<Vec<u8>>> {
+        if !matches!(*self.state.borrow(), CameraState::Streaming(_))
+            || self.video.video_width() == 0
+        {
+            return Ok(None);
+        }
+
+        self.canvas.set_width(width);
+        self.canvas.set_height(height);
+
+        let ctx: CanvasRenderingContext2d = self
+            .canvas
+            .get_context("2d")
+            .map_err(|err| anyhow!("Failed to get context: {err:?}"))?
+            .ok_or_else(|| anyhow!("Missing 2D context"))?
+            .unchecked_into();
+
+        ctx.draw_image_with_html_video_element_and_dw_and_dh(
+            &self.video,
+            0.0,
+            0.0,
+            width as f64,
+            height as f64,
+        )
+        .map_err(|err| anyhow!("Failed to capture image: {err:?}"))?;
+
+        let url = self
+            .canvas
+            .to_data_url_with_type("image/jpeg")
+            .map_err(|err| anyhow!("Failed to encode image: {err:?}"))?;
+
+        let data = url
+            .strip_prefix(JPEG_PREFIX)
+            .ok_or_else(|| anyhow!("Unexpected image encoding"))?;
+
+        Ok(Some(base64::decode(data)?))
+    }
+}
+
+impl Drop for Camera {
+    fn drop(&mut self) {
+        if let CameraState::Streaming(stream) = &*self.state.borrow() {
+            stop(stream);
+        }
+        self.video.set_src_object(None);
+    }
+}
+
+/// Stop all tracks of a stream, releasing the camera.
+fn stop(stream: &MediaStream) {
+    for track in stream.get_tracks().iter() {
+        track.unchecked_into::<MediaStreamTrack>().stop();
+    }
+}
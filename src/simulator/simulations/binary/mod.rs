@@ -0,0 +1,364 @@
+#[cfg(target_arch = "wasm32")]
+mod camera;
+pub mod pattern;
+
+/// The browser camera, which can't be created outside the browser.
+#[cfg(not(target_arch = "wasm32"))]
+mod camera {
+    #[allow(dead_code)]
+    pub enum CameraState {
+        Requesting,
+        Streaming(()),
+        Failed(String),
+    }
+
+    pub enum Camera {}
+
+    impl Camera {
+        pub fn new() -> anyhow::Result<Self> {
+            anyhow::bail!("The camera is only available in the browser")
+        }
+
+        pub fn state(&self) -> CameraState {
+            match *self {}
+        }
+
+        pub fn capture(&self, _width: u32, _height: u32) -> anyhow::Result<Option<Vec<u8>>> {
+            match *self {}
+        }
+    }
+}
+
+use super::default_period;
+use crate::{
+    simulator::{
+        publish::PublishEvent,
+        simulations::{
+            tick::{TickState, TickedGenerator},
+            Context, SimulationDescription, SimulationState, Upload, View,
+        },
+        Claim,
+    },
+    utils::{handler::Handler, hex, random::random},
+};
+use anyhow::bail;
+use camera::{Camera, CameraState};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Properties {
+    /// The interval in which payloads are published.
+    #[serde(default = "default_period", with = "humantime_serde")]
+    #[schemars(with = "String")]
+    pub period: Duration,
+
+    /// The channel to publish payloads to.
+    #[serde(default = "default_binary_channel")]
+    pub channel: String,
+
+    pub source: Source,
+}
+
+/// The source of the binary payloads.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Source {
+    /// Random bytes.
+    Random {
+        /// Size of the payload, in bytes.
+        size: u32,
+    },
+    /// A test pattern image (BMP), changing with every frame.
+    Pattern { width: u32, height: u32 },
+    /// A file, uploaded on the simulation page.
+    #[serde(rename_all = "camelCase")]
+    File {
+        /// Publish the file in chunks of this size, instead of all at once.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        chunk_size: Option<u32>,
+    },
+    /// A snapshot (JPEG) of the browser camera.
+    Camera { width: u32, height: u32 },
+}
+
+/// Maximum size of a generated payload, in bytes.
+pub const MAX_PAYLOAD_SIZE: usize = 4 * 1024 * 1024;
+
+impl Properties {
+    /// Validate the size of the generated payloads.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self.source {
+            Source::Random { size } => {
+                if size as usize > MAX_PAYLOAD_SIZE {
+                    bail!("The size of random payloads must not exceed {MAX_PAYLOAD_SIZE} bytes");
+                }
+            }
+            Source::Pattern { width, height } => {
+                validate_dimensions(width, height)?;
+                if pattern::size(width, height) > MAX_PAYLOAD_SIZE {
+                    bail!("The pattern image must not exceed {MAX_PAYLOAD_SIZE} bytes");
+                }
+            }
+            Source::Camera { width, height } => validate_dimensions(width, height)?,
+            Source::File { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+fn validate_dimensions(width: u32, height: u32) -> anyhow::Result<()> {
+    if width > pattern::MAX_DIMENSION || height > pattern::MAX_DIMENSION {
+        bail!(
+            "The image must not exceed {0}x{0} pixels",
+            pattern::MAX_DIMENSION
+        );
+    }
+    Ok(())
+}
+
+pub fn default_binary_channel() -> String {
+    "binary".into()
+}
+
+/// Get the next chunk of `data`, starting at `offset`.
+///
+/// Returns the chunk, and the offset of the next chunk, starting over at the end of the data.
+pub fn chunk(data: &[u8], offset: usize, size: Option<u32>) -> (&[u8], usize) {
+    let size = match size {
+        Some(size) if size > 0 => size as usize,
+        _ => return (data, 0),
+    };
+
+    let offset = if offset >= data.len() { 0 } else { offset };
+    let end = (offset + size).min(data.len());
+    let next = if end >= data.len() { 0 } else { end };
+
+    (&data[offset..end], next)
+}
+
+pub struct State {
+    properties: Properties,
+    count: u64,
+    last: Option<Vec<u8>>,
+
+    frame: u32,
+    upload: Rc<RefCell<Option<Upload>>>,
+    offset: usize,
+    camera: Option<Result<Camera, String>>,
+}
+
+impl TickState for State {
+    fn period(&self) -> Duration {
+        self.properties.period
+    }
+}
+
+impl State {
+    fn next(&mut self) -> Option<Vec<u8>> {
+        match &self.properties.source {
+            Source::Random { size } => Some((0..*size).map(|_| (random() * 256.0) as u8).collect()),
+            Source::Pattern { width, height } => {
+                let data = pattern::bmp(*width, *height, self.frame);
+                self.frame = self.frame.wrapping_add(1);
+                Some(data)
+            }
+            Source::File { chunk_size } => {
+                let upload = self.upload.borrow();
+                let upload = upload.as_ref()?;
+                let (data, next) = chunk(&upload.data, self.offset, *chunk_size);
+                self.offset = next;
+                Some(data.to_vec())
+            }
+            Source::Camera { width, height } => match &self.camera {
+                Some(Ok(camera)) => camera.capture(*width, *height).unwrap_or_else(|err| {
+                    log::info!("Failed to capture image: {err}");
+                    None
+                }),
+                _ => None,
+            },
+        }
+    }
+
+    fn render(&self) -> View {
+        let last = self
+            .last
+            .as_ref()
+            .map(|last| format!("{} bytes: {}", last.len(), hex::preview(last, 16)))
+            .unwrap_or_else(|| "-".into());
+
+        let input = match &self.properties.source {
+            Source::File { .. } => {
+                let upload = self.upload.clone();
+                let onupload = Handler::from(move |file: Upload| {
+                    *upload.borrow_mut() = Some(file);
+                });
+                let current = self
+                    .upload
+                    .borrow()
+                    .as_ref()
+                    .map(|upload| format!("{} ({} bytes)", upload.name, upload.data.len()))
+                    .unwrap_or_else(|| "None".into());
+                View::Form(vec![("File".into(), View::File { current, onupload })])
+            }
+            Source::Camera { .. } => {
+                let camera = match &self.camera {
+                    Some(Ok(camera)) => match camera.state() {
+                        CameraState::Requesting => "Requesting access…".into(),
+                        CameraState::Streaming(_) => "Streaming".into(),
+                        CameraState::Failed(err) => err,
+                    },
+                    Some(Err(err)) => err.clone(),
+                    None => "-".into(),
+                };
+                View::details([("Camera", camera)])
+            }
+            _ => View::Empty,
+        };
+
+        View::Stack(vec![
+            input,
+            View::details([("Payloads", self.count.to_string()), ("Last payload", last)]),
+        ])
+    }
+}
+
+pub struct BinaryGenerator;
+
+impl TickedGenerator for BinaryGenerator {
+    type Properties = Properties;
+    type State = State;
+
+    fn make_state(
+        properties: &Self::Properties,
+        current_state: Option<Self::State>,
+    ) -> Self::State {
+        let mut state = match current_state {
+            Some(current) => State {
+                properties: properties.clone(),
+                ..current
+            },
+            None => State {
+                properties: properties.clone(),
+                count: 0,
+                last: None,
+                frame: 0,
+                upload: Default::default(),
+                offset: 0,
+                camera: None,
+            },
+        };
+
+        match properties.source {
+            Source::Camera { .. } => {
+                if state.camera.is_none() {
+                    state.camera = Some(Camera::new().map_err(|err| err.to_string()));
+                }
+            }
+            _ => {
+                // release the camera
+                state.camera = None;
+            }
+        }
+
+        state
+    }
+
+    fn make_claims(properties: &Self::Properties) -> Vec<Claim> {
+        vec![Claim::Channel {
+            channel: properties.channel.clone(),
+        }]
+    }
+
+    fn tick(_: f64, state: &mut Self::State, ctx: &mut Context) {
+        // the settings might not have been validated, never generate oversized payloads
+        if let Err(err) = state.properties.validate() {
+            ctx.update(SimulationState {
+                description: SimulationDescription {
+                    label: format!("Binary ({})", state.properties.channel),
+                },
+                view: View::Error {
+                    title: "Invalid configuration".into(),
+                    message: err.to_string(),
+                },
+            });
+            return;
+        }
+
+        if let Some(payload) = state.next() {
+            ctx.publisher().publish(PublishEvent::Raw {
+                channel: state.properties.channel.clone(),
+                payload: payload.clone(),
+            });
+            state.count += 1;
+            state.last = Some(payload);
+        }
+
+        ctx.update(SimulationState {
+            description: SimulationDescription {
+                label: format!("Binary ({})", state.properties.channel),
+            },
+            view: state.render(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_chunk_all() {
+        let data = [1, 2, 3, 4, 5];
+        assert_eq!((&data[..], 0), chunk(&data, 0, None));
+        assert_eq!((&data[..], 0), chunk(&data, 0, Some(0)));
+    }
+
+    #[test]
+    fn test_chunks() {
+        let data = [1, 2, 3, 4, 5];
+
+        let (c, next) = chunk(&data, 0, Some(2));
+        assert_eq!((&[1, 2][..], 2), (c, next));
+        let (c, next) = chunk(&data, next, Some(2));
+        assert_eq!((&[3, 4][..], 4), (c, next));
+        // the last chunk is shorter, and starts over
+        let (c, next) = chunk(&data, next, Some(2));
+        assert_eq!((&[5][..], 0), (c, next));
+    }
+
+    #[test]
+    fn test_chunk_out_of_range() {
+        // e.g. after uploading a smaller file
+        let data = [1, 2, 3];
+        assert_eq!((&[1, 2][..], 2), chunk(&data, 10, Some(2)));
+    }
+
+    fn properties(source: Source) -> Properties {
+        Properties {
+            period: default_period(),
+            channel: default_binary_channel(),
+            source,
+        }
+    }
+
+    #[test]
+    fn test_validate_size() {
+        assert!(properties(Source::Random { size: 1024 }).validate().is_ok());
+        assert!(properties(Source::Random { size: u32::MAX })
+            .validate()
+            .is_err());
+
+        let pattern = |width, height| properties(Source::Pattern { width, height });
+        assert!(pattern(640, 480).validate().is_ok());
+        assert!(pattern(4096, 1).validate().is_ok());
+        assert!(pattern(4097, 1).validate().is_err());
+        assert!(pattern(4096, 4096).validate().is_err());
+
+        let camera = |width, height| properties(Source::Camera { width, height });
+        assert!(camera(4096, 4096).validate().is_ok());
+        assert!(camera(640, u32::MAX).validate().is_err());
+    }
+}
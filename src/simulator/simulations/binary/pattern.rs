@@ -0,0 +1,121 @@
+//! Generate test pattern images.
+
+/// Colors of the bars, from left to right.
+const BARS: [[u8; 3]; 8] = [
+    [0xff, 0xff, 0xff],
+    [0xff, 0xff, 0x00],
+    [0x00, 0xff, 0xff],
+    [0x00, 0xff, 0x00],
+    [0xff, 0x00, 0xff],
+    [0xff, 0x00, 0x00],
+    [0x00, 0x00, 0xff],
+    [0x00, 0x00, 0x00],
+];
+
+/// Size of the file and info header.
+const HEADER_SIZE: usize = 14 + 40;
+
+/// Maximum width and height of an image.
+pub const MAX_DIMENSION: u32 = 4096;
+
+/// Get the color of a pixel of the pattern, as RGB.
+///
+/// The pattern consists of color bars, with a black marker line moving with every frame, so
+/// that consecutive images differ.
+pub fn pixel(x: u32, y: u32, width: u32, height: u32, frame: u32) -> [u8; 3] {
+    let width = width.max(1);
+    let height = height.max(1);
+
+    if x == frame % width || y == frame % height {
+        return [0x00, 0x00, 0x00];
+    }
+
+    BARS[(x as usize * BARS.len()) / width as usize]
+}
+
+/// Size of a row of pixels, which are padded to multiples of 4 bytes.
+fn stride(width: u32) -> usize {
+    (width as usize * 3 + 3) & !3
+}
+
+/// The size of a BMP image, in bytes.
+pub fn size(width: u32, height: u32) -> usize {
+    HEADER_SIZE + stride(width) * height as usize
+}
+
+/// Render a frame of the test pattern, as a 24 bit BMP image.
+///
+/// The dimensions must not exceed [`MAX_DIMENSION`].
+pub fn bmp(width: u32, height: u32, frame: u32) -> Vec<u8> {
+    let stride = stride(width);
+    let size = size(width, height);
+
+    let mut data = Vec::with_capacity(size);
+
+    // file header
+    data.extend_from_slice(b"BM");
+    data.extend_from_slice(&(size as u32).to_le_bytes());
+    data.extend_from_slice(&[0; 4]);
+    data.extend_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+
+    // info header
+    data.extend_from_slice(&40u32.to_le_bytes());
+    data.extend_from_slice(&(width as i32).to_le_bytes());
+    // a negative height stores the rows top down
+    data.extend_from_slice(&(-(height as i32)).to_le_bytes());
+    data.extend_from_slice(&1u16.to_le_bytes());
+    data.extend_from_slice(&24u16.to_le_bytes());
+    // no compression, default size, resolution and palette
+    data.extend_from_slice(&[0; 24]);
+
+    for y in 0..height {
+        let row = data.len();
+        for x in 0..width {
+            let [r, g, b] = pixel(x, y, width, height, frame);
+            data.extend_from_slice(&[b, g, r]);
+        }
+        data.resize(row + stride, 0);
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_header() {
+        let data = bmp(5, 3, 0);
+
+        // 5 pixels take 15 bytes, padded to 16
+        assert_eq!(HEADER_SIZE + 16 * 3, data.len());
+        assert_eq!(b"BM", &data[0..2]);
+        assert_eq!(
+            data.len() as u32,
+            u32::from_le_bytes(data[2..6].try_into().unwrap())
+        );
+        assert_eq!(5, i32::from_le_bytes(data[18..22].try_into().unwrap()));
+        assert_eq!(-3, i32::from_le_bytes(data[22..26].try_into().unwrap()));
+    }
+
+    #[test]
+    fn test_pixels() {
+        // the marker of frame 0 is in the first row and column
+        assert_eq!([0, 0, 0], pixel(0, 1, 16, 16, 0));
+        assert_eq!([0, 0, 0], pixel(1, 0, 16, 16, 0));
+        // first bar is white, second one yellow
+        assert_eq!([0xff, 0xff, 0xff], pixel(1, 1, 16, 16, 0));
+        assert_eq!([0xff, 0xff, 0x00], pixel(2, 1, 16, 16, 0));
+
+        // the marker moves
+        assert_eq!([0xff, 0xff, 0xff], pixel(0, 2, 16, 16, 1));
+        assert_eq!([0, 0, 0], pixel(1, 2, 16, 16, 1));
+    }
+
+    #[test]
+    fn test_frames_differ() {
+        assert_ne!(bmp(8, 8, 0), bmp(8, 8, 1));
+        assert_eq!(bmp(8, 8, 0), bmp(8, 8, 8));
+    }
+}
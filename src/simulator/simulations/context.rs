@@ -2,11 +2,26 @@ use crate::simulator::{
     publish::{Publisher, SimulatorStateUpdate},
     simulations::SimulationState,
 };
-use std::rc::Rc;
+use std::{cell::Cell, rc::Rc};
+
+/// Requests from a simulation, controlling the simulator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    /// The device lost its power, all other simulations should stop.
+    PowerLoss,
+    /// The device has power again.
+    PowerRestored,
+}
+
+pub trait SimulatorControl {
+    fn control(&self, control: Control);
+}
 
 struct Inner {
     publisher: Box<dyn Publisher>,
     updater: Box<dyn SimulatorStateUpdate>,
+    controller: Box<dyn SimulatorControl>,
+    published: Rc<Cell<u64>>,
 }
 
 #[derive(Clone)]
@@ -15,15 +30,18 @@ pub struct Context {
 }
 
 impl Context {
-    pub fn new<P, U>(publisher: P, updater: U) -> Self
+    pub fn new<P, U, C>(publisher: P, updater: U, controller: C, published: Rc<Cell<u64>>) -> Self
     where
         P: Publisher + 'static,
         U: SimulatorStateUpdate + 'static,
+        C: SimulatorControl + 'static,
     {
         Self {
             inner: Rc::new(Inner {
                 publisher: Box::new(publisher),
                 updater: Box::new(updater),
+                controller: Box::new(controller),
+                published,
             }),
         }
     }
@@ -35,4 +53,13 @@ impl Context {
     pub fn update(&self, state: SimulationState) {
         self.inner.updater.state(state)
     }
+
+    pub fn control(&self, control: Control) {
+        self.inner.controller.control(control)
+    }
+
+    /// The total number of events published by all simulations of the device.
+    pub fn published(&self) -> u64 {
+        self.inner.published.get()
+    }
 }
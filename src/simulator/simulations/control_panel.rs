@@ -0,0 +1,420 @@
+use crate::simulator::simulations::{Sender, SenderConfiguration, SenderHandle};
+use crate::{
+    runtime,
+    simulator::{
+        publish::PublisherExt,
+        simulations::{
+            default_period, slider::Step, Context, FeatureTarget, Generator, SimulationState, View,
+        },
+        Claim,
+    },
+    utils::{
+        float::{ApproxF64, Zero},
+        handler::Handler,
+    },
+};
+use anyhow::bail;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashSet},
+    time::Duration,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct Properties {
+    #[serde(default = "default_period")]
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
+    pub delay: Duration,
+    #[serde(default)]
+    pub target: FeatureTarget,
+
+    /// The controls, each bound to a property of the target feature.
+    #[serde(default)]
+    pub controls: Vec<Control>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct Control {
+    /// The property the control is bound to.
+    pub property: String,
+    /// The label, defaults to the name of the property.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(flatten)]
+    pub r#type: ControlType,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum ControlType {
+    /// A slider, starting at its minimum.
+    Slider { min: Step, max: Step },
+    /// An on/off toggle.
+    Toggle {
+        #[serde(default)]
+        initial: bool,
+    },
+    /// A number, changed in steps.
+    Number {
+        #[serde(default = "default_number_initial")]
+        initial: ApproxF64<Zero, 2>,
+        #[serde(default = "default_number_step")]
+        step: ApproxF64<Zero, 2>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<ApproxF64<Zero, 2>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<ApproxF64<Zero, 2>>,
+    },
+    /// A selection of options, starting with the first one.
+    Dropdown { options: Vec<String> },
+    /// A button, setting the property to a value when being pressed.
+    Button {
+        #[serde(default = "default_button_value")]
+        value: Value,
+    },
+}
+
+fn default_number_initial() -> ApproxF64<Zero, 2> {
+    0f64.into()
+}
+
+fn default_number_step() -> ApproxF64<Zero, 2> {
+    1f64.into()
+}
+
+fn default_button_value() -> Value {
+    Value::Bool(true)
+}
+
+impl SenderConfiguration for Properties {
+    fn delay(&self) -> Duration {
+        self.delay
+    }
+}
+
+impl Properties {
+    /// Validate the panel.
+    ///
+    /// Each property may only be bound to a single control.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut seen = HashSet::new();
+        for control in &self.controls {
+            if !seen.insert(&control.property) {
+                bail!("Duplicate control for property: {}", control.property);
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the values of all controls, falling back to their initial value.
+    ///
+    /// Buttons only have a value once they have been pressed.
+    pub fn values(&self, state: &BTreeMap<String, Value>) -> BTreeMap<String, Value> {
+        self.controls
+            .iter()
+            .filter_map(|control| {
+                state
+                    .get(&control.property)
+                    .cloned()
+                    .or_else(|| control.r#type.initial())
+                    .map(|value| (control.property.clone(), value))
+            })
+            .collect()
+    }
+}
+
+impl Control {
+    pub fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.property)
+    }
+}
+
+impl ControlType {
+    pub fn initial(&self) -> Option<Value> {
+        match self {
+            Self::Slider { min, .. } => Some(json!(min.value())),
+            Self::Toggle { initial } => Some(json!(initial)),
+            Self::Number {
+                initial, min, max, ..
+            } => Some(json!(clamp(initial.0, min, max))),
+            Self::Dropdown { options } => options.first().map(|option| json!(option)),
+            Self::Button { .. } => None,
+        }
+    }
+}
+
+/// Clamp a value to optional limits.
+fn clamp(value: f64, min: &Option<ApproxF64<Zero, 2>>, max: &Option<ApproxF64<Zero, 2>>) -> f64 {
+    let value = match min {
+        Some(min) => value.max(min.0),
+        None => value,
+    };
+    match max {
+        Some(max) => value.min(max.0),
+        None => value,
+    }
+}
+
+pub struct ControlPanelSimulation {
+    claims: Vec<Claim>,
+    properties: Properties,
+    paused: bool,
+
+    sender: Option<SenderHandle<BTreeMap<String, Value>, Properties>>,
+}
+
+impl Generator for ControlPanelSimulation {
+    type Properties = Properties;
+
+    fn new(properties: Self::Properties) -> Self {
+        let claims = properties.target.claims();
+        Self {
+            claims,
+            properties,
+            paused: false,
+            sender: None,
+        }
+    }
+
+    fn claims(&self) -> &[Claim] {
+        &self.claims
+    }
+
+    fn update(&mut self, properties: Self::Properties) {
+        self.claims = properties.target.claims();
+        if self.properties != properties {
+            self.properties = properties.clone();
+            if let Some(sender) = &mut self.sender {
+                let mut sender = sender.clone();
+                runtime::spawn(async move {
+                    if let Err(err) = sender.configure(properties).await {
+                        log::warn!("Failed to update configuration: {err}");
+                    }
+                })
+            }
+        }
+    }
+
+    fn start(&mut self, ctx: Context) {
+        let (handle, sender) = Sender::new(
+            ctx,
+            self.properties.clone(),
+            BTreeMap::new(),
+            |handle, ctx, config, state| {
+                let values = config.values(state);
+
+                ctx.publisher().publish_feature(
+                    &config.target.channel,
+                    &config.target.feature,
+                    values.clone(),
+                );
+
+                // apply changes to the most recent values, not the ones which were last published
+                let set = {
+                    let handle = handle.to_sync();
+                    let config = config.clone();
+                    Handler::from(move |(property, change): (String, Change)| {
+                        let config = config.clone();
+                        handle.modify(move |values: &mut BTreeMap<String, Value>| {
+                            let value = change(config.values(values).get(&property));
+                            values.insert(property, value);
+                        });
+                    })
+                };
+
+                ctx.update(SimulationState {
+                    description: config.target.describe("Control panel"),
+                    view: View::Form(
+                        config
+                            .controls
+                            .iter()
+                            .map(|control| {
+                                (
+                                    control.label().to_string(),
+                                    render_control(control, values.get(&control.property), &set),
+                                )
+                            })
+                            .collect(),
+                    ),
+                });
+            },
+        );
+
+        sender.paused(self.paused).start();
+
+        self.sender = Some(handle);
+    }
+
+    fn stop(&mut self) {
+        self.sender = None;
+    }
+
+    fn pause(&mut self) {
+        self.paused = true;
+        if let Some(sender) = &self.sender {
+            sender.to_sync().pause();
+        }
+    }
+
+    fn resume(&mut self) {
+        self.paused = false;
+        if let Some(sender) = &self.sender {
+            sender.to_sync().resume();
+        }
+    }
+
+    fn step(&mut self) {
+        if let Some(sender) = &self.sender {
+            sender.to_sync().step();
+        }
+    }
+}
+
+/// A change of a value, based on its most recent value.
+type Change = Box<dyn FnOnce(Option<&Value>) -> Value>;
+
+fn render_control(
+    control: &Control,
+    value: Option<&Value>,
+    set: &Handler<(String, Change)>,
+) -> View {
+    let property = control.property.clone();
+    let modify = set.reform(move |change: Change| (property.clone(), change));
+    let set = modify.reform(|value: Value| Box::new(move |_: Option<&Value>| value) as Change);
+
+    match &control.r#type {
+        ControlType::Slider { min, max } => {
+            let value = value
+                .and_then(|v| v.as_f64())
+                .unwrap_or_else(|| min.value());
+            View::Slider {
+                min: min.clone(),
+                max: max.clone(),
+                value,
+                onchange: set.reform(|value: f64| json!(value)),
+            }
+        }
+        ControlType::Toggle { .. } => {
+            let checked = value.and_then(|v| v.as_bool()).unwrap_or_default();
+            View::Switch {
+                checked,
+                onchange: set.reform(|value: bool| json!(value)),
+            }
+        }
+        ControlType::Number { step, min, max, .. } => {
+            let current = value.and_then(|v| v.as_f64()).unwrap_or_default();
+            let change = |label: &str, delta: f64| {
+                let (min, max) = (*min, *max);
+                View::Button {
+                    label: label.into(),
+                    primary: false,
+                    onclick: modify.reform(move |_| {
+                        Box::new(move |value: Option<&Value>| {
+                            let current = value.and_then(|v| v.as_f64()).unwrap_or_default();
+                            json!(clamp(current + delta, &min, &max))
+                        }) as Change
+                    }),
+                }
+            };
+            let onchange = {
+                let (min, max) = (*min, *max);
+                let set = set.clone();
+                Handler::from(move |value: String| {
+                    if let Ok(value) = value.parse::<f64>() {
+                        set.emit(json!(clamp(value, &min, &max)));
+                    }
+                })
+            };
+            View::Toolbar(vec![
+                change("-", -step.0),
+                View::TextInput {
+                    value: current.to_string(),
+                    onchange,
+                },
+                change("+", step.0),
+            ])
+        }
+        ControlType::Dropdown { options } => View::Select {
+            options: options.clone(),
+            selected: value.and_then(|v| v.as_str()).map(ToString::to_string),
+            onselect: set.reform(|value: String| json!(value)),
+        },
+        ControlType::Button { value: pressed } => {
+            let pressed = pressed.clone();
+            View::Button {
+                label: control.label().to_string(),
+                primary: true,
+                onclick: set.reform(move |_| pressed.clone()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn properties() -> Properties {
+        serde_json::from_value(json!({
+            "controls": [
+                {"property": "level", "type": "slider", "min": 10, "max": 100},
+                {"property": "on", "label": "Power", "type": "toggle", "initial": true},
+                {"property": "setpoint", "type": "number", "initial": 30, "max": 25},
+                {"property": "mode", "type": "dropdown", "options": ["eco", "comfort"]},
+                {"property": "reset", "type": "button"},
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_initial_values() {
+        let properties = properties();
+        assert_eq!(
+            json!({
+                "level": 10.0,
+                "on": true,
+                "setpoint": 25.0,
+                "mode": "eco",
+            }),
+            json!(properties.values(&BTreeMap::new()))
+        );
+    }
+
+    #[test]
+    fn test_values() {
+        let properties = properties();
+        let state = [
+            ("mode".to_string(), json!("comfort")),
+            ("reset".to_string(), json!(true)),
+            ("removed".to_string(), json!(42)),
+        ]
+        .into_iter()
+        .collect();
+
+        let values = properties.values(&state);
+        assert_eq!(Some(&json!("comfort")), values.get("mode"));
+        assert_eq!(Some(&json!(true)), values.get("reset"));
+        // no longer bound to a control
+        assert_eq!(None, values.get("removed"));
+    }
+
+    #[test]
+    fn test_label() {
+        let properties = properties();
+        assert_eq!("level", properties.controls[0].label());
+        assert_eq!("Power", properties.controls[1].label());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut properties = properties();
+        assert!(properties.validate().is_ok());
+
+        properties.controls.push(properties.controls[0].clone());
+        assert!(properties.validate().is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use super::default_period;
+use crate::{
+    simulator::{
+        publish::PublisherExt,
+        simulations::{
+            tick::{TickState, TickedGenerator},
+            Context, SimulationState, SingleTarget, View,
+        },
+        Claim,
+    },
+    utils::{
+        float::{ApproxF64, Zero},
+        random::Distribution,
+    },
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Properties {
+    #[serde(default = "default_period", with = "humantime_serde")]
+    #[schemars(with = "String")]
+    pub period: Duration,
+
+    /// The initial value.
+    #[serde(default = "default_initial")]
+    pub initial: ApproxF64<Zero, 2>,
+    /// The increment per period. Negative samples are ignored, so that the counter stays monotonic.
+    #[serde(default)]
+    pub increment: Distribution,
+    /// Wrap around to zero when reaching this value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollover: Option<ApproxF64<Zero, 2>>,
+
+    #[serde(default)]
+    pub target: SingleTarget,
+}
+
+fn default_initial() -> ApproxF64<Zero, 2> {
+    0f64.into()
+}
+
+/// Advance the counter by an increment, returns the new value and if it rolled over.
+pub fn advance(value: f64, increment: f64, rollover: Option<f64>) -> (f64, bool) {
+    let value = value + increment.max(0.0);
+    match rollover {
+        Some(rollover) if rollover > 0.0 && value >= rollover => (value % rollover, true),
+        _ => (value, false),
+    }
+}
+
+pub struct State {
+    properties: Properties,
+    value: f64,
+    rollovers: u64,
+    started: bool,
+}
+
+impl TickState for State {
+    fn period(&self) -> Duration {
+        self.properties.period
+    }
+}
+
+pub struct CounterGenerator;
+
+impl TickedGenerator for CounterGenerator {
+    type Properties = Properties;
+    type State = State;
+
+    fn make_state(
+        properties: &Self::Properties,
+        current_state: Option<Self::State>,
+    ) -> Self::State {
+        let (value, rollovers, started) = match current_state {
+            // keep counting, unless the initial value was changed
+            Some(current) if current.properties.initial == properties.initial => {
+                (current.value, current.rollovers, current.started)
+            }
+            _ => (properties.initial.0, 0, false),
+        };
+
+        Self::State {
+            properties: properties.clone(),
+            value,
+            rollovers,
+            started,
+        }
+    }
+
+    fn make_claims(properties: &Self::Properties) -> Vec<Claim> {
+        properties.target.claims()
+    }
+
+    fn tick(_now: f64, state: &mut Self::State, ctx: &mut Context) {
+        if state.started {
+            let (value, rolled) = advance(
+                state.value,
+                state.properties.increment.sample(),
+                state.properties.rollover.map(|r| r.0),
+            );
+            state.value = value;
+            if rolled {
+                state.rollovers += 1;
+            }
+        } else {
+            // publish the initial value first
+            state.started = true;
+        }
+
+        let target = &state.properties.target;
+
+        ctx.update(SimulationState {
+            description: target.describe("Counter"),
+            view: View::details([
+                ("Value", format!("{:.2}", state.value)),
+                ("Rollovers", state.rollovers.to_string()),
+            ]),
+        });
+
+        ctx.publisher().publish_single(
+            &target.channel,
+            &target.feature,
+            &target.property,
+            state.value,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_advance() {
+        assert_eq!((3.5, false), advance(1.0, 2.5, None));
+        assert_eq!((3.5, false), advance(1.0, 2.5, Some(10.0)));
+    }
+
+    #[test]
+    fn test_monotonic() {
+        assert_eq!((1.0, false), advance(1.0, -2.0, None));
+    }
+
+    #[test]
+    fn test_rollover() {
+        assert_eq!((1.0, true), advance(9.0, 2.0, Some(10.0)));
+        assert_eq!((0.0, true), advance(9.0, 1.0, Some(10.0)));
+        // ignore an invalid rollover
+        assert_eq!((11.0, false), advance(9.0, 2.0, Some(0.0)));
+    }
+}
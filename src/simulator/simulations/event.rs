@@ -0,0 +1,166 @@
+use super::default_period;
+use crate::{
+    simulator::{
+        publish::PublishEvent,
+        simulations::{
+            tick::{TickState, TickedGenerator},
+            Context, SimulationDescription, SimulationState, View,
+        },
+        Claim,
+    },
+    utils::{
+        float::{ApproxF64, Zero},
+        random::{poisson, random},
+    },
+};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+const MILLIS_PER_MINUTE: f64 = 60_000.0;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Properties {
+    /// How often to check for new events.
+    #[serde(default = "default_period", with = "humantime_serde")]
+    #[schemars(with = "String")]
+    pub period: Duration,
+
+    /// The mean number of events per minute.
+    pub rate: ApproxF64<Zero, 2>,
+
+    /// The name of the event.
+    pub event: String,
+    /// Additional data, sent with every event.
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub data: Value,
+
+    /// The channel to publish events to.
+    #[serde(default = "default_event_channel")]
+    pub channel: String,
+}
+
+pub fn default_event_channel() -> String {
+    "events".into()
+}
+
+/// Create the payload of a single event.
+pub fn payload(event: &str, data: &Value, timestamp: DateTime<Utc>) -> Value {
+    let mut payload = json!({
+        "event": event,
+        "timestamp": timestamp.to_rfc3339(),
+    });
+    if !data.is_null() {
+        payload["data"] = data.clone();
+    }
+    payload
+}
+
+pub struct State {
+    properties: Properties,
+    last: Option<f64>,
+    count: u64,
+    last_event: Option<DateTime<Utc>>,
+}
+
+impl TickState for State {
+    fn period(&self) -> Duration {
+        self.properties.period
+    }
+}
+
+pub struct EventGenerator;
+
+impl TickedGenerator for EventGenerator {
+    type Properties = Properties;
+    type State = State;
+
+    fn make_state(
+        properties: &Self::Properties,
+        current_state: Option<Self::State>,
+    ) -> Self::State {
+        let (last, count, last_event) = match current_state {
+            Some(current) => (current.last, current.count, current.last_event),
+            None => (None, 0, None),
+        };
+
+        Self::State {
+            properties: properties.clone(),
+            last,
+            count,
+            last_event,
+        }
+    }
+
+    fn make_claims(properties: &Self::Properties) -> Vec<Claim> {
+        vec![Claim::Channel {
+            channel: properties.channel.clone(),
+        }]
+    }
+
+    fn tick(now: f64, state: &mut Self::State, ctx: &mut Context) {
+        if let Some(last) = state.last {
+            let minutes = (now - last).max(0.0) / MILLIS_PER_MINUTE;
+            let events = poisson(state.properties.rate.0 * minutes, &mut random);
+
+            for _ in 0..events {
+                let timestamp = Utc::now();
+                let payload = payload(&state.properties.event, &state.properties.data, timestamp);
+
+                match serde_json::to_vec(&payload) {
+                    Ok(payload) => {
+                        ctx.publisher().publish(PublishEvent::Raw {
+                            channel: state.properties.channel.clone(),
+                            payload,
+                        });
+                        state.count += 1;
+                        state.last_event = Some(timestamp);
+                    }
+                    Err(err) => log::info!("Failed to encode event: {err}"),
+                }
+            }
+        }
+        state.last = Some(now);
+
+        ctx.update(SimulationState {
+            description: SimulationDescription {
+                label: format!(
+                    "Event '{}' ({})",
+                    state.properties.event, state.properties.channel
+                ),
+            },
+            view: View::details([
+                ("Events", state.count.to_string()),
+                (
+                    "Last event",
+                    state
+                        .last_event
+                        .map(|t| t.to_rfc3339())
+                        .unwrap_or_else(|| "-".into()),
+                ),
+            ]),
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        let timestamp = "2022-01-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        assert_eq!(
+            json!({"event": "doorOpened", "timestamp": "2022-01-01T12:00:00+00:00"}),
+            payload("doorOpened", &Value::Null, timestamp)
+        );
+        assert_eq!(
+            json!({"event": "alarm", "timestamp": "2022-01-01T12:00:00+00:00", "data": {"level": 2}}),
+            payload("alarm", &json!({"level": 2}), timestamp)
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use crate::simulator::{
+    publish::PublisherExt,
+    simulations::{Context, Generator, SimulationState, SingleTarget, View},
+    Claim, InternalState,
+};
+use anyhow::{anyhow, bail};
+use evalexpr::{ContextWithMutableVariables, HashMapContext, Node, Operator};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Properties {
+    /// The expression to evaluate, e.g. `voltage * current`.
+    pub expression: String,
+    /// The inputs, by the name of the variable in the expression.
+    #[serde(default)]
+    pub inputs: BTreeMap<String, SingleTarget>,
+
+    #[serde(default)]
+    pub target: SingleTarget,
+}
+
+impl Properties {
+    /// Validate the formula.
+    ///
+    /// The expression must be valid, and only use known inputs. That the target isn't one of its
+    /// own inputs is checked through the claims, see [`Properties::inputs`].
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let tree = evalexpr::build_operator_tree(&self.expression)?;
+        check_operands(&tree)?;
+
+        // variables assigned by the expression itself
+        let local: Vec<_> = tree.iter_write_variable_identifiers().collect();
+
+        for variable in tree.iter_read_variable_identifiers() {
+            if !self.inputs.contains_key(variable) && !local.contains(&variable) {
+                bail!("Unknown input: {variable}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The claims of the inputs, which the target must not overlap with.
+    pub fn inputs(&self) -> Vec<Claim> {
+        self.inputs
+            .values()
+            .flat_map(|input| input.claims())
+            .collect()
+    }
+}
+
+/// Check that all operators have their operands.
+///
+/// The parser accepts incomplete expressions, like `voltage *`, which only fail when being evaluated.
+fn check_operands(node: &Node) -> anyhow::Result<()> {
+    let expected = match node.operator() {
+        Operator::Neg | Operator::Not => Some(1),
+        Operator::Add
+        | Operator::Sub
+        | Operator::Mul
+        | Operator::Div
+        | Operator::Mod
+        | Operator::Exp
+        | Operator::Eq
+        | Operator::Neq
+        | Operator::Gt
+        | Operator::Lt
+        | Operator::Geq
+        | Operator::Leq
+        | Operator::And
+        | Operator::Or
+        | Operator::Assign
+        | Operator::AddAssign
+        | Operator::SubAssign
+        | Operator::MulAssign
+        | Operator::DivAssign
+        | Operator::ModAssign
+        | Operator::ExpAssign
+        | Operator::AndAssign
+        | Operator::OrAssign => Some(2),
+        _ => None,
+    };
+
+    if let Some(expected) = expected {
+        if node.children().len() != expected {
+            bail!("Missing operand for '{}'", node.operator());
+        }
+    }
+
+    node.children().iter().try_for_each(check_operands)
+}
+
+/// Look up the current value of a property.
+fn lookup<'a>(state: &'a InternalState, target: &SingleTarget) -> Option<&'a Value> {
+    state
+        .0
+        .get(&target.channel)?
+        .features
+        .get(&target.feature)?
+        .properties
+        .get(&target.property)
+}
+
+/// Evaluate an expression, using the provided values for its variables.
+pub fn evaluate(expression: &str, values: &BTreeMap<String, Value>) -> anyhow::Result<Value> {
+    let mut context = HashMapContext::new();
+    for (name, value) in values {
+        let value = match value {
+            Value::Bool(value) => evalexpr::Value::Boolean(*value),
+            Value::Number(value) => evalexpr::Value::Float(
+                value
+                    .as_f64()
+                    .ok_or_else(|| anyhow!("Invalid number: {value}"))?,
+            ),
+            Value::String(value) => evalexpr::Value::String(value.clone()),
+            _ => bail!("Unsupported value for '{name}': {value}"),
+        };
+        context.set_value(name.clone(), value)?;
+    }
+
+    Ok(
+        match evalexpr::eval_with_context_mut(expression, &mut context)? {
+            evalexpr::Value::Boolean(value) => Value::Bool(value),
+            evalexpr::Value::Float(value) => value.into(),
+            evalexpr::Value::Int(value) => value.into(),
+            evalexpr::Value::String(value) => value.into(),
+            value => bail!("Unsupported result: {value}"),
+        },
+    )
+}
+
+pub struct FormulaSimulation {
+    claims: Vec<Claim>,
+    inputs: Vec<Claim>,
+    properties: Properties,
+    paused: bool,
+
+    context: Option<Context>,
+    /// The values of the inputs, of the last evaluation.
+    values: Option<BTreeMap<String, Value>>,
+    result: Option<Result<Value, String>>,
+}
+
+impl FormulaSimulation {
+    /// Re-evaluate the formula, if its inputs changed.
+    fn evaluate(&mut self, state: &InternalState) {
+        let mut values = BTreeMap::new();
+        let mut missing = vec![];
+        for (name, input) in &self.properties.inputs {
+            match lookup(state, input) {
+                Some(value) => {
+                    values.insert(name.clone(), value.clone());
+                }
+                None => missing.push(name.as_str()),
+            }
+        }
+
+        if !missing.is_empty() {
+            let missing = missing.join(", ");
+            self.result = Some(Err(format!("Waiting for inputs: {missing}")));
+            self.values = None;
+            self.notify(false);
+            return;
+        }
+
+        if self.values.as_ref() == Some(&values) {
+            // nothing changed
+            return;
+        }
+
+        self.result = Some(
+            self.properties
+                .validate()
+                .and_then(|()| evaluate(&self.properties.expression, &values))
+                .map_err(|err| err.to_string()),
+        );
+        self.values = Some(values);
+        self.notify(true);
+    }
+
+    fn notify(&self, publish: bool) {
+        let ctx = match &self.context {
+            Some(ctx) => ctx,
+            None => return,
+        };
+
+        if publish && !self.paused {
+            self.publish(ctx);
+        }
+
+        let result = match &self.result {
+            Some(Ok(value)) => value.to_string(),
+            Some(Err(err)) => err.clone(),
+            None => "-".to_string(),
+        };
+
+        ctx.update(SimulationState {
+            description: self.properties.target.describe("Formula"),
+            view: View::details([
+                ("Expression", self.properties.expression.clone()),
+                ("Result", result),
+            ]),
+        });
+    }
+
+    fn publish(&self, ctx: &Context) {
+        if let Some(Ok(value)) = &self.result {
+            ctx.publisher().publish_single(
+                &self.properties.target.channel,
+                &self.properties.target.feature,
+                &self.properties.target.property,
+                value.clone(),
+            );
+        }
+    }
+}
+
+impl Generator for FormulaSimulation {
+    type Properties = Properties;
+
+    fn new(properties: Self::Properties) -> Self {
+        let claims = properties.target.claims();
+        let inputs = properties.inputs();
+        Self {
+            claims,
+            inputs,
+            properties,
+            paused: false,
+            context: None,
+            values: None,
+            result: None,
+        }
+    }
+
+    fn claims(&self) -> &[Claim] {
+        &self.claims
+    }
+
+    fn inputs(&self) -> &[Claim] {
+        &self.inputs
+    }
+
+    fn update(&mut self, properties: Self::Properties) {
+        self.claims = properties.target.claims();
+        self.inputs = properties.inputs();
+        if self.properties != properties {
+            self.properties = properties;
+            // force re-evaluation with the next state
+            self.values = None;
+        }
+    }
+
+    fn start(&mut self, ctx: Context) {
+        self.context = Some(ctx);
+        self.values = None;
+        self.notify(false);
+    }
+
+    fn stop(&mut self) {
+        self.context = None;
+    }
+
+    fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    fn resume(&mut self) {
+        self.paused = false;
+        if let Some(ctx) = &self.context {
+            self.publish(ctx);
+        }
+    }
+
+    fn step(&mut self) {
+        if let Some(ctx) = &self.context {
+            self.publish(ctx);
+        }
+    }
+
+    fn data(&mut self, state: &InternalState) {
+        if self.context.is_some() {
+            self.evaluate(state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::simulator::conflicting_inputs;
+    use serde_json::json;
+
+    fn values<const N: usize>(values: [(&str, Value); N]) -> BTreeMap<String, Value> {
+        values
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect()
+    }
+
+    #[test]
+    fn test_power() {
+        let result = evaluate(
+            "voltage * current",
+            &values([("voltage", json!(230)), ("current", json!(0.5))]),
+        )
+        .unwrap();
+        assert_eq!(json!(115.0), result);
+    }
+
+    #[test]
+    fn test_dew_point() {
+        // Magnus formula
+        let expression =
+            "a = 17.62; b = 243.12; g = math::ln(h / 100.0) + a * t / (b + t); b * g / (a - g)";
+        let result = evaluate(expression, &values([("t", json!(25)), ("h", json!(60))]))
+            .unwrap()
+            .as_f64()
+            .unwrap();
+        assert!((result - 16.69).abs() < 0.05, "Dew point: {result}");
+    }
+
+    #[test]
+    fn test_boolean() {
+        let result = evaluate("temperature > 30.0", &values([("temperature", json!(35))])).unwrap();
+        assert_eq!(json!(true), result);
+    }
+
+    #[test]
+    fn test_missing_variable() {
+        assert!(evaluate("a + b", &values([("a", json!(1))])).is_err());
+    }
+
+    fn properties(expression: &str) -> Properties {
+        Properties {
+            expression: expression.into(),
+            inputs: [
+                ("voltage", SingleTarget::new("state", "power", "voltage")),
+                ("current", SingleTarget::new("state", "power", "current")),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+            target: SingleTarget::new("state", "power", "watts"),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(properties("voltage * current").validate().is_ok());
+        assert!(properties("voltage * current * foo").validate().is_err());
+        assert!(properties("voltage *").validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_local_variables() {
+        assert!(properties("p = voltage * current; p / 1000.0")
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_self_reference_conflicts() {
+        let mut properties = properties("voltage * current");
+        let simulation = FormulaSimulation::new(properties.clone());
+        assert!(conflicting_inputs(simulation.claims(), simulation.inputs()).is_empty());
+
+        properties.target = SingleTarget::new("state", "power", "voltage");
+        let simulation = FormulaSimulation::new(properties);
+        assert_eq!(
+            conflicting_inputs(simulation.claims(), simulation.inputs()),
+            SingleTarget::new("state", "power", "voltage").claims()
+        );
+    }
+
+    #[test]
+    fn test_lookup() {
+        let mut state = InternalState::default();
+        assert_eq!(
+            None,
+            lookup(&state, &SingleTarget::new("state", "power", "voltage"))
+        );
+
+        let mut properties = BTreeMap::new();
+        properties.insert("voltage".to_string(), json!(230));
+        state.0.insert(
+            "state".into(),
+            crate::simulator::publish::ChannelState {
+                features: [(
+                    "power".to_string(),
+                    crate::simulator::publish::Feature { properties },
+                )]
+                .into_iter()
+                .collect(),
+            },
+        );
+
+        assert_eq!(
+            Some(&json!(230)),
+            lookup(&state, &SingleTarget::new("state", "power", "voltage"))
+        );
+    }
+}
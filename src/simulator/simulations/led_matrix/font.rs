@@ -0,0 +1,106 @@
+//! A tiny 3x5 pixel font, for scrolling text.
+
+/// Height of a glyph.
+pub const HEIGHT: usize = 5;
+/// Width of a glyph.
+pub const WIDTH: usize = 3;
+
+/// Get the glyph of a character, each row as bits, the most significant bit being the left column.
+///
+/// Unknown characters are rendered as `?`.
+pub fn glyph(c: char) -> [u8; HEIGHT] {
+    match c.to_ascii_uppercase() {
+        'A' => [2, 5, 7, 5, 5],
+        'B' => [6, 5, 6, 5, 6],
+        'C' => [3, 4, 4, 4, 3],
+        'D' => [6, 5, 5, 5, 6],
+        'E' => [7, 4, 6, 4, 7],
+        'F' => [7, 4, 6, 4, 4],
+        'G' => [3, 4, 5, 5, 3],
+        'H' => [5, 5, 7, 5, 5],
+        'I' => [7, 2, 2, 2, 7],
+        'J' => [1, 1, 1, 5, 2],
+        'K' => [5, 5, 6, 5, 5],
+        'L' => [4, 4, 4, 4, 7],
+        'M' => [5, 7, 7, 5, 5],
+        'N' => [6, 5, 5, 5, 5],
+        'O' => [2, 5, 5, 5, 2],
+        'P' => [6, 5, 6, 4, 4],
+        'Q' => [2, 5, 5, 6, 3],
+        'R' => [6, 5, 6, 5, 5],
+        'S' => [3, 4, 2, 1, 6],
+        'T' => [7, 2, 2, 2, 2],
+        'U' => [5, 5, 5, 5, 7],
+        'V' => [5, 5, 5, 5, 2],
+        'W' => [5, 5, 7, 7, 5],
+        'X' => [5, 5, 2, 5, 5],
+        'Y' => [5, 5, 2, 2, 2],
+        'Z' => [7, 1, 2, 4, 7],
+        '0' => [7, 5, 5, 5, 7],
+        '1' => [2, 6, 2, 2, 7],
+        '2' => [6, 1, 2, 4, 7],
+        '3' => [6, 1, 2, 1, 6],
+        '4' => [5, 5, 7, 1, 1],
+        '5' => [7, 4, 6, 1, 6],
+        '6' => [3, 4, 7, 5, 7],
+        '7' => [7, 1, 2, 2, 2],
+        '8' => [7, 5, 7, 5, 7],
+        '9' => [7, 5, 7, 1, 6],
+        ' ' => [0, 0, 0, 0, 0],
+        '!' => [2, 2, 2, 0, 2],
+        '.' => [0, 0, 0, 0, 2],
+        ',' => [0, 0, 0, 2, 4],
+        '-' => [0, 0, 7, 0, 0],
+        '+' => [0, 2, 7, 2, 0],
+        ':' => [0, 2, 0, 2, 0],
+        '%' => [5, 1, 2, 4, 5],
+        _ => [6, 1, 2, 0, 2],
+    }
+}
+
+/// Render a text into columns of pixels, with a blank column between characters.
+pub fn columns(text: &str) -> Vec<[bool; HEIGHT]> {
+    let mut result = Vec::new();
+
+    for (i, c) in text.chars().enumerate() {
+        if i > 0 {
+            result.push([false; HEIGHT]);
+        }
+        let glyph = glyph(c);
+        for x in 0..WIDTH {
+            let mut column = [false; HEIGHT];
+            for (y, row) in glyph.iter().enumerate() {
+                column[y] = row & (1 << (WIDTH - 1 - x)) != 0;
+            }
+            result.push(column);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_columns() {
+        let columns = columns("HI");
+
+        // two glyphs, and one spacing column
+        assert_eq!(7, columns.len());
+        // H: left column fully on, middle only in the center
+        assert_eq!([true; HEIGHT], columns[0]);
+        assert_eq!([false, false, true, false, false], columns[1]);
+        // spacing
+        assert_eq!([false; HEIGHT], columns[3]);
+        // I: middle column fully on
+        assert_eq!([true; HEIGHT], columns[5]);
+    }
+
+    #[test]
+    fn test_unknown() {
+        assert_eq!(glyph('?'), glyph('§'));
+        assert_eq!(glyph('a'), glyph('A'));
+    }
+}
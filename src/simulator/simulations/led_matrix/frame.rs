@@ -0,0 +1,442 @@
+use super::font;
+use serde_json::{json, Value};
+
+/// The state of a single LED.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pixel {
+    #[default]
+    Off,
+    /// On, using the configured color, with a brightness.
+    On(u8),
+    /// On, using a specific color.
+    Rgb(u8, u8, u8),
+}
+
+impl Pixel {
+    /// Parse a pixel from its JSON representation.
+    ///
+    /// Accepts a boolean, a brightness (`0` to `255`), an RGB color (`"#rrggbb"` or
+    /// `[r, g, b]`), or `null` for off.
+    pub fn parse(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null | Value::Bool(false) => Some(Self::Off),
+            Value::Bool(true) => Some(Self::On(255)),
+            Value::Number(brightness) => {
+                let brightness = brightness.as_f64()?.clamp(0.0, 255.0) as u8;
+                Some(match brightness {
+                    0 => Self::Off,
+                    b => Self::On(b),
+                })
+            }
+            Value::String(color) => match color.as_str() {
+                "" | "off" => Some(Self::Off),
+                "on" => Some(Self::On(255)),
+                color => {
+                    let hex = color.strip_prefix('#')?;
+                    if hex.len() != 6 {
+                        return None;
+                    }
+                    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+                    Some(Self::Rgb(channel(0)?, channel(2)?, channel(4)?))
+                }
+            },
+            Value::Array(rgb) => match rgb.as_slice() {
+                [r, g, b] => {
+                    let channel = |v: &Value| v.as_u64().map(|v| v.min(255) as u8);
+                    Some(Self::Rgb(channel(r)?, channel(g)?, channel(b)?))
+                }
+                _ => None,
+            },
+            Value::Object(_) => None,
+        }
+    }
+
+    /// The JSON representation: `0` when off, the brightness when using the configured color,
+    /// or `"#rrggbb"`.
+    pub fn to_json(self) -> Value {
+        match self {
+            Self::Off => json!(0),
+            Self::On(brightness) => json!(brightness),
+            Self::Rgb(r, g, b) => json!(format!("#{r:02x}{g:02x}{b:02x}")),
+        }
+    }
+}
+
+/// The pixels of the matrix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    rows: usize,
+    columns: usize,
+    pixels: Vec<Pixel>,
+}
+
+impl Frame {
+    pub fn new(rows: usize, columns: usize) -> Self {
+        Self {
+            rows,
+            columns,
+            pixels: vec![Pixel::Off; rows * columns],
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Change the size, keeping the overlapping part.
+    pub fn resize(&mut self, rows: usize, columns: usize) {
+        if rows == self.rows && columns == self.columns {
+            return;
+        }
+
+        let mut frame = Self::new(rows, columns);
+        for row in 0..rows.min(self.rows) {
+            for column in 0..columns.min(self.columns) {
+                frame.set(row, column, self.get(row, column));
+            }
+        }
+        *self = frame;
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Pixel {
+        if row < self.rows && column < self.columns {
+            self.pixels[row * self.columns + column]
+        } else {
+            Pixel::Off
+        }
+    }
+
+    /// Set a pixel, ignoring pixels outside the matrix.
+    pub fn set(&mut self, row: usize, column: usize, pixel: Pixel) {
+        if row < self.rows && column < self.columns {
+            self.pixels[row * self.columns + column] = pixel;
+        }
+    }
+
+    pub fn fill(&mut self, pixel: Pixel) {
+        self.pixels.fill(pixel);
+    }
+
+    /// Iterate over the rows.
+    pub fn iter_rows(&self) -> impl Iterator<Item = &[Pixel]> {
+        self.pixels.chunks(self.columns.max(1))
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::Array(
+            self.iter_rows()
+                .map(|row| Value::Array(row.iter().copied().map(Pixel::to_json).collect()))
+                .collect(),
+        )
+    }
+
+    fn set_row(&mut self, row: usize, value: &Value) -> bool {
+        match value {
+            Value::Array(pixels) => {
+                for (column, pixel) in pixels.iter().enumerate() {
+                    if let Some(pixel) = Pixel::parse(pixel) {
+                        self.set(row, column, pixel);
+                    }
+                }
+                true
+            }
+            // a string, each character a pixel
+            Value::String(pixels) => {
+                for (column, c) in pixels.chars().enumerate() {
+                    let pixel = match c {
+                        ' ' | '.' | '0' => Pixel::Off,
+                        _ => Pixel::On(255),
+                    };
+                    self.set(row, column, pixel);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Scrolling text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Text {
+    pub text: String,
+    pub pixel: Pixel,
+    /// Milliseconds per column.
+    pub speed: u32,
+}
+
+pub const DEFAULT_SPEED: u32 = 150;
+
+impl Text {
+    fn parse(value: &Value) -> Option<Option<Self>> {
+        match value {
+            Value::Null => Some(None),
+            Value::String(text) if text.is_empty() => Some(None),
+            Value::String(text) => Some(Some(Self {
+                text: text.clone(),
+                pixel: Pixel::On(255),
+                speed: DEFAULT_SPEED,
+            })),
+            Value::Object(_) => {
+                let text = value["text"].as_str()?;
+                if text.is_empty() {
+                    return Some(None);
+                }
+                Some(Some(Self {
+                    text: text.to_string(),
+                    pixel: match &value["color"] {
+                        Value::Null => Pixel::On(255),
+                        color => Pixel::parse(color)?,
+                    },
+                    speed: value["speed"]
+                        .as_u64()
+                        .map(|speed| speed.clamp(10, 10_000) as u32)
+                        .unwrap_or(DEFAULT_SPEED),
+                }))
+            }
+            _ => None,
+        }
+    }
+
+    /// Number of steps for one full scroll, moving the text in and out of the display.
+    pub fn steps(&self, columns: usize) -> usize {
+        font::columns(&self.text).len() + columns
+    }
+
+    /// Render the text onto a frame, at a scroll position.
+    pub fn render(&self, frame: &mut Frame, step: usize) {
+        let glyphs = font::columns(&self.text);
+        let columns = frame.columns();
+        let offset = frame.rows().saturating_sub(font::HEIGHT) / 2;
+
+        for column in 0..columns {
+            // the text starts right of the display
+            let index = (step + column).checked_sub(columns);
+            let glyph = index.and_then(|index| glyphs.get(index));
+            for y in 0..font::HEIGHT {
+                let on = glyph.map(|glyph| glyph[y]).unwrap_or_default();
+                frame.set(offset + y, column, if on { self.pixel } else { Pixel::Off });
+            }
+        }
+    }
+}
+
+/// The state of the display.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Display {
+    pub frame: Frame,
+    pub text: Option<Text>,
+}
+
+impl Display {
+    pub fn new(rows: usize, columns: usize) -> Self {
+        Self {
+            frame: Frame::new(rows, columns),
+            text: None,
+        }
+    }
+
+    /// Apply a command, returns `true` if the state changed.
+    ///
+    /// Commands are either a single pixel value (filling the whole matrix), or an object with
+    /// any of the fields: `fill`, `bitmap`, `row`, `pixel` (or `pixels`), and `text`.
+    pub fn apply(&mut self, command: &Value) -> bool {
+        let before = self.clone();
+
+        match command {
+            Value::Object(_) => {
+                if let Some(pixel) = command.get("fill").and_then(Pixel::parse) {
+                    self.text = None;
+                    self.frame.fill(pixel);
+                }
+
+                if let Some(Value::Array(rows)) = command.get("bitmap") {
+                    self.text = None;
+                    self.frame.fill(Pixel::Off);
+                    for (row, value) in rows.iter().enumerate() {
+                        self.frame.set_row(row, value);
+                    }
+                }
+
+                for row in Self::many(command.get("row").or_else(|| command.get("rows"))) {
+                    if let Some(index) = row["index"].as_u64() {
+                        let index = index as usize;
+                        self.text = None;
+                        if let Some(pixel) = row.get("color").and_then(Pixel::parse) {
+                            for column in 0..self.frame.columns() {
+                                self.frame.set(index, column, pixel);
+                            }
+                        } else {
+                            self.frame.set_row(index, &row["pixels"]);
+                        }
+                    }
+                }
+
+                for pixel in Self::many(command.get("pixel").or_else(|| command.get("pixels"))) {
+                    if let (Some(row), Some(column), Some(value)) = (
+                        pixel["row"].as_u64(),
+                        pixel["column"].as_u64(),
+                        Pixel::parse(&pixel["color"]),
+                    ) {
+                        self.text = None;
+                        self.frame.set(row as usize, column as usize, value);
+                    }
+                }
+
+                if let Some(text) = command.get("text").and_then(Text::parse) {
+                    self.text = text;
+                }
+            }
+            value => {
+                if let Some(pixel) = Pixel::parse(value) {
+                    self.text = None;
+                    self.frame.fill(pixel);
+                }
+            }
+        }
+
+        before != *self
+    }
+
+    /// A single value, or an array of values.
+    fn many(value: Option<&Value>) -> Vec<&Value> {
+        match value {
+            Some(Value::Array(values)) => values.iter().collect(),
+            Some(value) => vec![value],
+            None => vec![],
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut result = json!({
+            "rows": self.frame.rows(),
+            "columns": self.frame.columns(),
+            "frame": self.frame.to_json(),
+        });
+        if let Some(text) = &self.text {
+            result["text"] = json!({
+                "text": text.text,
+                "color": text.pixel.to_json(),
+                "speed": text.speed,
+            });
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_pixel() {
+        assert_eq!(Some(Pixel::Off), Pixel::parse(&json!(false)));
+        assert_eq!(Some(Pixel::Off), Pixel::parse(&json!(0)));
+        assert_eq!(Some(Pixel::On(255)), Pixel::parse(&json!(true)));
+        assert_eq!(Some(Pixel::On(128)), Pixel::parse(&json!(128)));
+        assert_eq!(Some(Pixel::On(255)), Pixel::parse(&json!(1000)));
+        assert_eq!(
+            Some(Pixel::Rgb(255, 0, 16)),
+            Pixel::parse(&json!("#ff0010"))
+        );
+        assert_eq!(Some(Pixel::Rgb(1, 2, 3)), Pixel::parse(&json!([1, 2, 3])));
+        assert_eq!(None, Pixel::parse(&json!("#ff00")));
+        assert_eq!(None, Pixel::parse(&json!("red")));
+    }
+
+    #[test]
+    fn test_pixel_roundtrip() {
+        for pixel in [Pixel::Off, Pixel::On(42), Pixel::Rgb(1, 128, 255)] {
+            assert_eq!(Some(pixel), Pixel::parse(&pixel.to_json()));
+        }
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut frame = Frame::new(2, 2);
+        frame.set(1, 1, Pixel::On(1));
+        frame.resize(3, 3);
+        assert_eq!(Pixel::On(1), frame.get(1, 1));
+        frame.resize(1, 1);
+        assert_eq!(Pixel::Off, frame.get(0, 0));
+        assert_eq!(1, frame.to_json().as_array().unwrap().len());
+    }
+
+    #[test]
+    fn test_legacy_fill() {
+        let mut display = Display::new(2, 2);
+        assert!(display.apply(&json!(true)));
+        assert!(display
+            .frame
+            .iter_rows()
+            .flatten()
+            .all(|p| *p == Pixel::On(255)));
+        assert!(!display.apply(&json!(true)));
+        assert!(display.apply(&json!(false)));
+        assert!(display
+            .frame
+            .iter_rows()
+            .flatten()
+            .all(|p| *p == Pixel::Off));
+    }
+
+    #[test]
+    fn test_pixels() {
+        let mut display = Display::new(8, 8);
+        assert!(display.apply(&json!({
+            "pixel": {"row": 1, "column": 2, "color": "#00ff00"},
+        })));
+        assert_eq!(Pixel::Rgb(0, 255, 0), display.frame.get(1, 2));
+
+        assert!(display.apply(&json!({
+            "pixels": [
+                {"row": 0, "column": 0, "color": true},
+                {"row": 99, "column": 0, "color": true},
+            ],
+        })));
+        assert_eq!(Pixel::On(255), display.frame.get(0, 0));
+    }
+
+    #[test]
+    fn test_rows_and_bitmap() {
+        let mut display = Display::new(3, 3);
+        display.apply(&json!({"row": {"index": 1, "color": 10}}));
+        assert_eq!(
+            json!([[0, 0, 0], [10, 10, 10], [0, 0, 0]]),
+            display.frame.to_json()
+        );
+
+        display.apply(&json!({"bitmap": ["x.x", [0, "#010203"]]}));
+        assert_eq!(
+            json!([[255, 0, 255], [0, "#010203", 0], [0, 0, 0]]),
+            display.frame.to_json()
+        );
+    }
+
+    #[test]
+    fn test_text() {
+        let mut display = Display::new(5, 5);
+        assert!(display.apply(&json!({"text": "HI"})));
+        assert_eq!(Some("HI"), display.text.as_ref().map(|t| t.text.as_str()));
+
+        let text = display.text.clone().unwrap();
+        assert_eq!(7 + 5, text.steps(5));
+
+        // at the start, the display is empty
+        let mut frame = Frame::new(5, 5);
+        text.render(&mut frame, 0);
+        assert!(frame.iter_rows().flatten().all(|p| *p == Pixel::Off));
+
+        // after scrolling in, the first column of the "H" is on the left
+        text.render(&mut frame, 5);
+        assert!((0..5).all(|row| frame.get(row, 0) == Pixel::On(255)));
+
+        // setting pixels stops the text
+        display.apply(&json!({"fill": 0}));
+        assert_eq!(None, display.text);
+    }
+}
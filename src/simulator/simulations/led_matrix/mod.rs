@@ -0,0 +1,156 @@
+mod font;
+mod frame;
+
+pub use frame::*;
+
+use crate::simulator::publish::PublisherExt;
+use crate::simulator::{
+    simulations::{Context, Generator, SimulationState, SingleTarget, View},
+    Claim, Command,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Properties {
+    #[serde(default)]
+    pub target: SingleTarget,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_off: Option<String>,
+
+    #[serde(default = "default_size")]
+    pub rows: u8,
+    #[serde(default = "default_size")]
+    pub columns: u8,
+}
+
+const fn default_size() -> u8 {
+    5
+}
+
+pub struct LedMatrixSimulation {
+    claims: Vec<Claim>,
+    properties: Properties,
+    paused: bool,
+
+    display: Display,
+    context: Option<Context>,
+}
+
+impl LedMatrixSimulation {
+    fn publish(&self, ctx: &Context) {
+        let mut state = self.display.to_json();
+        state["on"] = Value::Bool(
+            self.display.text.is_some()
+                || self
+                    .display
+                    .frame
+                    .iter_rows()
+                    .flatten()
+                    .any(|pixel| *pixel != Pixel::Off),
+        );
+
+        ctx.publisher().publish_single(
+            &self.properties.target.channel,
+            &self.properties.target.feature,
+            &self.properties.target.property,
+            state,
+        );
+    }
+
+    fn notify(&self, ctx: &Context) {
+        self.publish(ctx);
+        ctx.update(SimulationState {
+            description: self.properties.target.describe("Led Matrix"),
+            view: View::LedMatrix {
+                display: self.display.clone(),
+                color: self.properties.color.clone().unwrap_or_default(),
+                color_off: self.properties.color_off.clone().unwrap_or_default(),
+            },
+        });
+    }
+}
+
+impl Generator for LedMatrixSimulation {
+    type Properties = Properties;
+
+    fn new(properties: Self::Properties) -> Self {
+        let claims = properties.target.claims();
+        let display = Display::new(properties.rows as usize, properties.columns as usize);
+        Self {
+            claims,
+            properties,
+            paused: false,
+            display,
+            context: None,
+        }
+    }
+
+    fn claims(&self) -> &[Claim] {
+        &self.claims
+    }
+
+    fn update(&mut self, properties: Self::Properties) {
+        self.claims = properties.target.claims();
+        if self.properties != properties {
+            self.display
+                .frame
+                .resize(properties.rows as usize, properties.columns as usize);
+            self.properties = properties;
+            if !self.paused {
+                self.step();
+            }
+        }
+    }
+
+    fn start(&mut self, ctx: Context) {
+        if !self.paused {
+            self.notify(&ctx);
+        }
+        self.context = Some(ctx);
+    }
+
+    fn stop(&mut self) {
+        self.context = None;
+    }
+
+    fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    fn resume(&mut self) {
+        self.paused = false;
+        self.step();
+    }
+
+    fn step(&mut self) {
+        if let Some(ctx) = &self.context {
+            self.notify(ctx);
+        }
+    }
+
+    fn command(&mut self, command: &Command) {
+        if command.name != self.properties.target.channel {
+            return;
+        }
+
+        if let Some(json) = command
+            .payload
+            .as_ref()
+            .and_then(|payload| serde_json::from_slice::<Value>(payload).ok())
+        {
+            self.display
+                .apply(&json[&self.properties.target.feature][&self.properties.target.property]);
+        }
+
+        if !self.paused {
+            if let Some(ctx) = &self.context {
+                self.notify(ctx);
+            }
+        }
+    }
+}
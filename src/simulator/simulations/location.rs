@@ -0,0 +1,461 @@
+use super::default_period;
+use crate::{
+    simulator::{
+        publish::PublisherExt,
+        simulations::{
+            tick::{TickState, TickedGenerator},
+            Context, FeatureTarget, SimulationState, View,
+        },
+        Claim,
+    },
+    utils::{
+        float::{ApproxF64, Zero},
+        geo::{self, Path, Point},
+        random::random,
+    },
+};
+#[cfg(target_arch = "wasm32")]
+use gloo_utils::window;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+#[cfg(target_arch = "wasm32")]
+use std::{cell::RefCell, rc::Rc};
+use std::{collections::VecDeque, time::Duration};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::{prelude::*, JsCast};
+
+/// Number of positions to keep for rendering the track.
+const TRACK_LENGTH: usize = 100;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Properties {
+    #[serde(default = "default_period", with = "humantime_serde")]
+    #[schemars(with = "String")]
+    pub period: Duration,
+
+    pub source: Source,
+
+    #[serde(default)]
+    pub target: FeatureTarget,
+}
+
+/// The source of the location.
+///
+/// Speeds are in meters per second, distances in meters.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Source {
+    /// Travel along a list of waypoints.
+    Waypoints {
+        waypoints: Vec<Position>,
+        speed: ApproxF64<Zero, 2>,
+        /// Start over, once the last waypoint is reached.
+        #[serde(default)]
+        repeat: bool,
+    },
+    /// Travel along a GeoJSON `LineString`.
+    #[serde(rename = "geojson")]
+    GeoJson {
+        geojson: Value,
+        speed: ApproxF64<Zero, 2>,
+        /// Start over, once the end of the line is reached.
+        #[serde(default)]
+        repeat: bool,
+    },
+    /// Randomly drift around a point.
+    Drift {
+        center: Position,
+        radius: ApproxF64<Zero, 2>,
+        speed: ApproxF64<Zero, 2>,
+    },
+    /// Use the location of the browser.
+    Browser,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct Position {
+    pub lat: ApproxF64<Zero, 2>,
+    pub lon: ApproxF64<Zero, 2>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alt: Option<ApproxF64<Zero, 2>>,
+}
+
+impl Position {
+    pub fn new(lat: f64, lon: f64) -> Self {
+        Self {
+            lat: lat.into(),
+            lon: lon.into(),
+            alt: None,
+        }
+    }
+}
+
+impl From<&Position> for Point {
+    fn from(position: &Position) -> Self {
+        Point::new(
+            position.lat.0,
+            position.lon.0,
+            position.alt.map(|alt| alt.0).unwrap_or_default(),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fix {
+    pub point: Point,
+    pub speed: Option<f64>,
+    pub heading: Option<f64>,
+}
+
+pub struct State {
+    period: Duration,
+    target: FeatureTarget,
+    source: Source,
+
+    driver: Driver,
+    track: VecDeque<Point>,
+}
+
+enum Driver {
+    Path {
+        path: Path,
+        speed: f64,
+        repeat: bool,
+        travelled: f64,
+    },
+    Drift {
+        center: Point,
+        radius: f64,
+        speed: f64,
+        position: Point,
+        heading: f64,
+    },
+    #[cfg(target_arch = "wasm32")]
+    Browser {
+        fix: Rc<RefCell<Option<Fix>>>,
+        _watcher: Option<Watcher>,
+    },
+    Invalid(String),
+}
+
+impl Driver {
+    fn new(source: &Source) -> Self {
+        match source {
+            Source::Waypoints {
+                waypoints,
+                speed,
+                repeat,
+            } => Self::Path {
+                path: Path::new(waypoints.iter().map(Into::into).collect()),
+                speed: speed.0,
+                repeat: *repeat,
+                travelled: 0.0,
+            },
+            Source::GeoJson {
+                geojson,
+                speed,
+                repeat,
+            } => match geo::from_geojson(geojson) {
+                Ok(points) => Self::Path {
+                    path: Path::new(points),
+                    speed: speed.0,
+                    repeat: *repeat,
+                    travelled: 0.0,
+                },
+                Err(err) => Self::Invalid(format!("Invalid GeoJSON: {err}")),
+            },
+            Source::Drift {
+                center,
+                radius,
+                speed,
+            } => Self::Drift {
+                center: center.into(),
+                radius: radius.0,
+                speed: speed.0,
+                position: center.into(),
+                heading: random() * 360.0,
+            },
+            #[cfg(target_arch = "wasm32")]
+            Source::Browser => {
+                let fix = Rc::new(RefCell::new(None));
+                Self::Browser {
+                    _watcher: Watcher::new(fix.clone()),
+                    fix,
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Source::Browser => {
+                Self::Invalid("The browser location is only available in the browser".into())
+            }
+        }
+    }
+
+    /// Get the current position, and advance by one period.
+    fn next(&mut self, period: Duration) -> Option<Fix> {
+        let period = period.as_secs_f64();
+        match self {
+            Self::Path {
+                path,
+                speed,
+                repeat,
+                travelled,
+            } => {
+                let length = path.length();
+                let (point, heading) = path.position(*travelled)?;
+                let moving = *repeat || *travelled < length;
+
+                *travelled += *speed * period;
+                if *repeat && length > 0.0 {
+                    *travelled %= length;
+                }
+
+                Some(Fix {
+                    point,
+                    speed: Some(if moving { *speed } else { 0.0 }),
+                    heading: Some(heading),
+                })
+            }
+            Self::Drift {
+                center,
+                radius,
+                speed,
+                position,
+                heading,
+            } => {
+                let fix = Fix {
+                    point: *position,
+                    speed: Some(*speed),
+                    heading: Some(*heading),
+                };
+
+                let turn = (random() - 0.5) * 60.0;
+                let (next, next_heading) =
+                    drift(center, *radius, position, *heading, *speed * period, turn);
+                *position = next;
+                *heading = next_heading;
+
+                Some(fix)
+            }
+            #[cfg(target_arch = "wasm32")]
+            Self::Browser { fix, .. } => *fix.borrow(),
+            Self::Invalid(_) => None,
+        }
+    }
+
+    /// The planned route, if there is one.
+    fn route(&self) -> &[Point] {
+        match self {
+            Self::Path { path, .. } => path.points(),
+            _ => &[],
+        }
+    }
+}
+
+/// Perform one step of drifting around a center point.
+///
+/// The heading changes by `turn` degrees, unless the step would leave the radius, in which case
+/// the heading points back to the center.
+fn drift(
+    center: &Point,
+    radius: f64,
+    position: &Point,
+    heading: f64,
+    distance: f64,
+    turn: f64,
+) -> (Point, f64) {
+    let heading = if geo::distance(center, position) + distance > radius {
+        geo::bearing(position, center)
+    } else {
+        (heading + turn + 360.0) % 360.0
+    };
+
+    (geo::destination(position, heading, distance), heading)
+}
+
+/// Watching the location of the browser.
+#[cfg(target_arch = "wasm32")]
+struct Watcher {
+    id: i32,
+    _listener: Closure<dyn FnMut(web_sys::Position)>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Watcher {
+    fn new(fix: Rc<RefCell<Option<Fix>>>) -> Option<Self> {
+        let listener = Closure::wrap(Box::new(move |position: web_sys::Position| {
+            let coords = position.coords();
+            fix.replace(Some(Fix {
+                point: Point::new(
+                    coords.latitude(),
+                    coords.longitude(),
+                    coords.altitude().unwrap_or_default(),
+                ),
+                speed: coords.speed(),
+                heading: coords.heading(),
+            }));
+        }) as Box<dyn FnMut(web_sys::Position)>);
+
+        let geolocation = match window().navigator().geolocation() {
+            Ok(geolocation) => geolocation,
+            Err(err) => {
+                log::warn!("Geolocation is not available: {err:?}");
+                return None;
+            }
+        };
+
+        match geolocation.watch_position(listener.as_ref().unchecked_ref()) {
+            Ok(id) => Some(Self {
+                id,
+                _listener: listener,
+            }),
+            Err(err) => {
+                log::warn!("Failed to watch position: {err:?}");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        if let Ok(geolocation) = window().navigator().geolocation() {
+            geolocation.clear_watch(self.id);
+        }
+    }
+}
+
+impl TickState for State {
+    fn period(&self) -> Duration {
+        self.period
+    }
+}
+
+pub struct LocationGenerator;
+
+impl TickedGenerator for LocationGenerator {
+    type Properties = Properties;
+    type State = State;
+
+    fn make_state(
+        properties: &Self::Properties,
+        current_state: Option<Self::State>,
+    ) -> Self::State {
+        let (driver, track) = match current_state {
+            // keep the progress, if the source didn't change
+            Some(state) if state.source == properties.source => (state.driver, state.track),
+            _ => (Driver::new(&properties.source), VecDeque::new()),
+        };
+
+        Self::State {
+            period: properties.period,
+            target: properties.target.clone(),
+            source: properties.source.clone(),
+            driver,
+            track,
+        }
+    }
+
+    fn make_claims(properties: &Self::Properties) -> Vec<Claim> {
+        properties.target.claims()
+    }
+
+    fn tick(_: f64, state: &mut Self::State, ctx: &mut Context) {
+        let description = state.target.describe("Location");
+
+        let fix = match state.driver.next(state.period) {
+            Some(fix) => fix,
+            None => {
+                let view = match &state.driver {
+                    Driver::Invalid(err) => View::Error {
+                        title: "Invalid configuration".into(),
+                        message: err.clone(),
+                    },
+                    _ => default_details(),
+                };
+                ctx.update(SimulationState { description, view });
+                return;
+            }
+        };
+
+        if state.track.len() >= TRACK_LENGTH {
+            state.track.pop_front();
+        }
+        state.track.push_back(fix.point);
+
+        ctx.publisher().publish_feature(
+            &state.target.channel,
+            &state.target.feature,
+            [
+                ("latitude", json!(fix.point.lat)),
+                ("longitude", json!(fix.point.lon)),
+                ("altitude", json!(fix.point.alt)),
+                ("speed", json!(fix.speed)),
+                ("heading", json!(fix.heading)),
+            ],
+        );
+
+        ctx.update(SimulationState {
+            description,
+            view: View::Row(vec![
+                View::Track {
+                    route: state.driver.route().to_vec(),
+                    track: state.track.iter().copied().collect(),
+                },
+                View::details([
+                    ("Latitude", format!("{:.6}", fix.point.lat)),
+                    ("Longitude", format!("{:.6}", fix.point.lon)),
+                    ("Altitude", format!("{:.1} m", fix.point.alt)),
+                    (
+                        "Speed",
+                        fix.speed.map(|s| format!("{s:.1} m/s")).unwrap_or_default(),
+                    ),
+                    (
+                        "Heading",
+                        fix.heading.map(|h| format!("{h:.0}°")).unwrap_or_default(),
+                    ),
+                ]),
+            ]),
+        });
+    }
+}
+
+fn default_details() -> View {
+    View::Message("No location data available yet. When using the browser location, you might need to grant access to the location in your browser.".into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_drift_stays_in_radius() {
+        let center = Point::new(52.5163, 13.3777, 0.0);
+        let mut position = center;
+        let mut heading = 0.0;
+
+        for i in 0..1000 {
+            // always turn a bit, to the right
+            let (next, next_heading) = drift(&center, 100.0, &position, heading, 15.0, 10.0);
+            position = next;
+            heading = next_heading;
+
+            let distance = geo::distance(&center, &position);
+            assert!(distance <= 100.0, "Left radius in step {i}: {distance}");
+        }
+    }
+
+    #[test]
+    fn test_drift_turns_back() {
+        let center = Point::new(0.0, 0.0, 0.0);
+        // 90m north of the center, heading north
+        let position = geo::destination(&center, 0.0, 90.0);
+
+        let (next, heading) = drift(&center, 100.0, &position, 0.0, 20.0, 0.0);
+
+        assert!((heading - 180.0).abs() < 0.001);
+        assert!(geo::distance(&center, &next) < 90.0);
+    }
+}
@@ -1,31 +1,42 @@
 pub mod accelerometer;
+pub mod actuator;
+pub mod battery;
+pub mod binary;
+pub mod control_panel;
+pub mod counter;
+pub mod event;
+pub mod formula;
 pub mod led_matrix;
+pub mod location;
 pub mod sawtooth;
 pub mod sine;
 pub mod slider;
+pub mod thermostat;
 pub mod tick;
 pub mod wave;
 
 mod context;
 mod sender;
+mod view;
 
 pub use context::*;
 pub use sender::*;
+pub use view::*;
 
-use crate::simulator::Command;
+use crate::simulator::{Command, InternalState};
 use crate::{
     settings::Simulation,
     simulator::{simulations::tick::TickedGenerator, Claim},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
-use yew::Html;
 
 const fn default_period() -> Duration {
     Duration::from_secs(1)
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 pub struct SingleTarget {
     #[serde(default = "default_channel")]
     pub channel: String,
@@ -80,7 +91,7 @@ impl SingleTarget {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 pub struct FeatureTarget {
     #[serde(default = "default_channel")]
     pub channel: String,
@@ -98,7 +109,6 @@ impl Default for FeatureTarget {
 }
 
 impl FeatureTarget {
-    #[allow(unused)]
     pub fn new<C, F>(channel: C, feature: F) -> Self
     where
         C: Into<String>,
@@ -144,7 +154,7 @@ pub struct SimulationDescription {
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct SimulationState {
     pub description: SimulationDescription,
-    pub html: Html,
+    pub view: View,
 }
 
 pub trait Generator {
@@ -153,32 +163,103 @@ pub trait Generator {
     fn new(properties: Self::Properties) -> Self;
     /// get current claims
     fn claims(&self) -> &[Claim];
+    /// get the current inputs, which must not overlap with the claims
+    fn inputs(&self) -> &[Claim] {
+        &[]
+    }
 
     fn update(&mut self, properties: Self::Properties);
 
     fn start(&mut self, ctx: Context);
     fn stop(&mut self);
 
+    /// Pause the simulation, keeping its state, but no longer publishing.
+    fn pause(&mut self) {}
+    /// Resume a paused simulation.
+    fn resume(&mut self) {}
+    /// Perform a single step of a paused simulation.
+    fn step(&mut self) {}
+
     /// Handle incoming commands
     ///
     /// The function will receive all commands and must filter out its own.
     fn command(&mut self, _: &Command) {}
+
+    /// Handle changes of the device state, including values published by other simulations.
+    fn data(&mut self, _: &InternalState) {}
+}
+
+/// Extract the properties of a generator from the simulation configuration.
+pub trait FromSimulation: Sized {
+    /// Get the properties, or [`None`] if the simulation is of a different type.
+    fn from_simulation(simulation: &Simulation) -> Option<Self>;
+}
+
+macro_rules! from_simulation {
+    ($($variant:ident => $properties:ty),* $(,)?) => {
+        $(
+            impl FromSimulation for $properties {
+                fn from_simulation(simulation: &Simulation) -> Option<Self> {
+                    match simulation {
+                        Simulation::$variant(properties) => Some(properties.as_ref().clone()),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_simulation! {
+    Sine => sine::Properties,
+    Sawtooth => sawtooth::Properties,
+    Wave => wave::Properties,
+    Accelerometer => accelerometer::Properties,
+    Slider => slider::Properties,
+    LedMatrix => led_matrix::Properties,
+    Location => location::Properties,
+    Battery => battery::Properties,
+    Thermostat => thermostat::Properties,
+    Switch => actuator::SwitchProperties,
+    Dimmer => actuator::DimmerProperties,
+    ModeSelector => actuator::ModeSelectorProperties,
+    Counter => counter::Properties,
+    Event => event::Properties,
+    Formula => formula::Properties,
+    ControlPanel => control_panel::Properties,
+    Binary => binary::Properties,
 }
 
 pub trait SimulationHandler {
     fn start(&mut self, ctx: Context);
     fn stop(&mut self);
     fn claims(&self) -> &[Claim];
+    fn inputs(&self) -> &[Claim] {
+        &[]
+    }
+
+    /// Update the configuration, keeping the state of the simulation.
+    ///
+    /// Returns `false` if the simulation is of a different type, and must be re-created instead.
+    fn update(&mut self, simulation: &Simulation) -> bool;
+
+    fn pause(&mut self) {}
+    fn resume(&mut self) {}
+    fn step(&mut self) {}
 
     /// Handle incoming commands
     ///
     /// The function will receive all commands and must filter out its own.
     fn command(&mut self, _: &Command) {}
+
+    /// Handle changes of the device state
+    fn data(&mut self, _: &InternalState) {}
 }
 
 impl<G> SimulationHandler for G
 where
     G: Generator,
+    G::Properties: FromSimulation,
 {
     fn start(&mut self, ctx: Context) {
         Generator::start(self, ctx)
@@ -192,9 +273,39 @@ where
         Generator::claims(self)
     }
 
+    fn inputs(&self) -> &[Claim] {
+        Generator::inputs(self)
+    }
+
+    fn update(&mut self, simulation: &Simulation) -> bool {
+        match G::Properties::from_simulation(simulation) {
+            Some(properties) => {
+                Generator::update(self, properties);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn pause(&mut self) {
+        Generator::pause(self)
+    }
+
+    fn resume(&mut self) {
+        Generator::resume(self)
+    }
+
+    fn step(&mut self) {
+        Generator::step(self)
+    }
+
     fn command(&mut self, command: &Command) {
         Generator::command(self, command)
     }
+
+    fn data(&mut self, state: &InternalState) {
+        Generator::data(self, state)
+    }
 }
 
 pub trait SimulationFactory {
@@ -218,6 +329,39 @@ impl SimulationFactory for Simulation {
             Simulation::LedMatrix(props) => {
                 Box::new(led_matrix::LedMatrixSimulation::new(props.as_ref().clone()))
             }
+            Simulation::Location(props) => {
+                Box::new(location::LocationGenerator::new(props.as_ref().clone()))
+            }
+            Simulation::Battery(props) => {
+                Box::new(battery::BatteryGenerator::new(props.as_ref().clone()))
+            }
+            Simulation::Thermostat(props) => {
+                Box::new(thermostat::ThermostatGenerator::new(props.as_ref().clone()))
+            }
+            Simulation::Switch(props) => {
+                Box::new(actuator::SwitchSimulation::new(props.as_ref().clone()))
+            }
+            Simulation::Dimmer(props) => {
+                Box::new(actuator::DimmerSimulation::new(props.as_ref().clone()))
+            }
+            Simulation::ModeSelector(props) => Box::new(actuator::ModeSelectorSimulation::new(
+                props.as_ref().clone(),
+            )),
+            Simulation::Counter(props) => {
+                Box::new(counter::CounterGenerator::new(props.as_ref().clone()))
+            }
+            Simulation::Event(props) => {
+                Box::new(event::EventGenerator::new(props.as_ref().clone()))
+            }
+            Simulation::Formula(props) => {
+                Box::new(formula::FormulaSimulation::new(props.as_ref().clone()))
+            }
+            Simulation::ControlPanel(props) => Box::new(
+                control_panel::ControlPanelSimulation::new(props.as_ref().clone()),
+            ),
+            Simulation::Binary(props) => {
+                Box::new(binary::BinaryGenerator::new(props.as_ref().clone()))
+            }
         }
     }
 }
@@ -4,27 +4,27 @@ use crate::{
         publish::PublisherExt,
         simulations::{
             tick::{TickState, TickedGenerator},
-            Context, SimulationState, SingleTarget,
+            Context, SimulationState, SingleTarget, View,
         },
         Claim,
     },
-    utils::{
-        float::{ApproxF64, Zero},
-        ui::details,
-    },
+    utils::float::{ApproxF64, Zero},
 };
 use num_traits::ToPrimitive;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 pub struct Properties {
     pub max: ApproxF64<Zero, 2>,
 
     #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
     pub length: Duration,
 
     #[serde(default = "default_period", with = "humantime_serde")]
+    #[schemars(with = "String")]
     pub period: Duration,
 
     #[serde(default)]
@@ -71,7 +71,7 @@ impl TickedGenerator for SawtoothGenerator {
 
         ctx.update(SimulationState {
             description: state.target.describe("Sawtooth"),
-            html: details([("Timestamp", now), ("Value", value)]),
+            view: View::details([("Timestamp", now), ("Value", value)]),
         });
 
         ctx.publisher().publish_single(
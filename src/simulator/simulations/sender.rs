@@ -1,13 +1,13 @@
-use crate::simulator::simulations::Context;
+use crate::{
+    runtime::{now, sleep, spawn},
+    simulator::simulations::Context,
+};
 use futures::channel::mpsc;
 use futures::channel::mpsc::{SendError, UnboundedReceiver, UnboundedSender};
 use futures::{select, FutureExt};
 use futures::{SinkExt, StreamExt};
-use gloo_timers::future::TimeoutFuture;
-use js_sys::Date;
 use num_traits::ToPrimitive;
 use std::time::Duration;
-use wasm_bindgen_futures::spawn_local;
 
 pub trait SenderConfiguration: Clone + 'static {
     fn delay(&self) -> Duration;
@@ -18,8 +18,12 @@ where
     C: SenderConfiguration,
     S: Clone + 'static,
 {
-    Update(S),
+    /// Update the state, applied to the most recent one.
+    Update(Box<dyn FnOnce(&mut S)>),
     Configure(C),
+    Pause,
+    Resume,
+    Step,
 }
 
 pub struct Sender<S, C>
@@ -33,6 +37,7 @@ where
     config: C,
     delay: Duration,
     initial_state: S,
+    paused: bool,
     f: Box<dyn Fn(&SenderHandle<S, C>, &Context, &C, &S)>,
 }
 
@@ -55,7 +60,27 @@ where
     }
 
     pub async fn update(&mut self, state: S) -> Result<(), SendError> {
-        self.tx.send(Msg::Update(state)).await
+        self.modify(move |current| *current = state).await
+    }
+
+    /// Modify the most recent state, including updates which haven't been sent yet.
+    pub async fn modify<F>(&mut self, f: F) -> Result<(), SendError>
+    where
+        F: FnOnce(&mut S) + 'static,
+    {
+        self.tx.send(Msg::Update(Box::new(f))).await
+    }
+
+    pub async fn pause(&mut self) -> Result<(), SendError> {
+        self.tx.send(Msg::Pause).await
+    }
+
+    pub async fn resume(&mut self) -> Result<(), SendError> {
+        self.tx.send(Msg::Resume).await
+    }
+
+    pub async fn step(&mut self) -> Result<(), SendError> {
+        self.tx.send(Msg::Step).await
     }
 
     pub fn to_sync(&self) -> SyncSenderHandle<S, C> {
@@ -81,17 +106,48 @@ where
 {
     pub fn configure(&self, config: C) {
         let mut handle = self.inner.clone();
-        spawn_local(async move {
+        spawn(async move {
             handle.configure(config).await.ok();
         });
     }
 
     pub fn update(&self, state: S) {
         let mut handle = self.inner.clone();
-        spawn_local(async move {
+        spawn(async move {
             handle.update(state).await.ok();
         });
     }
+
+    pub fn modify<F>(&self, f: F)
+    where
+        F: FnOnce(&mut S) + 'static,
+    {
+        let mut handle = self.inner.clone();
+        spawn(async move {
+            handle.modify(f).await.ok();
+        });
+    }
+
+    pub fn pause(&self) {
+        let mut handle = self.inner.clone();
+        spawn(async move {
+            handle.pause().await.ok();
+        });
+    }
+
+    pub fn resume(&self) {
+        let mut handle = self.inner.clone();
+        spawn(async move {
+            handle.resume().await.ok();
+        });
+    }
+
+    pub fn step(&self) {
+        let mut handle = self.inner.clone();
+        spawn(async move {
+            handle.step().await.ok();
+        });
+    }
 }
 
 impl<S, C> Sender<S, C>
@@ -117,13 +173,20 @@ where
                 config,
                 delay,
                 initial_state,
+                paused: false,
                 f: Box::new(f),
             },
         )
     }
 
+    /// Start the sender in paused mode.
+    pub fn paused(mut self, paused: bool) -> Self {
+        self.paused = paused;
+        self
+    }
+
     pub fn start(self) {
-        spawn_local(async move { self.run().await });
+        spawn(async move { self.run().await });
     }
 
     async fn run(mut self) {
@@ -137,33 +200,55 @@ where
         const INFINITY: u32 = i32::MAX as u32;
 
         let mut state = self.initial_state;
-        let mut next = Date::now();
-        let mut timer = TimeoutFuture::new(INFINITY).fuse();
+        let mut paused = self.paused;
+        let mut next = now();
+        let mut timer = sleep(INFINITY).fuse();
 
         // send an initial state
 
-        f(&tx, &ctx, &config, &state);
+        if !paused {
+            f(&tx, &ctx, &config, &state);
+        }
 
         // now loop
 
         loop {
             select! {
                 msg = self.rx.next() => match msg {
-                    Some(Msg::Update(s)) => {
-                        state = s;
-                        let now = Date::now();
-                        let rem = next - now;
-                        if rem < 0f64 {
-                            f(&tx, &ctx, &config, &state);
-                            next = now + delay;
-                        }  else {
-                            timer = TimeoutFuture::new(rem.to_u32().unwrap_or(INFINITY)).fuse();
+                    Some(Msg::Update(update)) => {
+                        update(&mut state);
+                        // when paused, keep the state, until we resume or step
+                        if !paused {
+                            let now = now();
+                            let rem = next - now;
+                            if rem < 0f64 {
+                                f(&tx, &ctx, &config, &state);
+                                next = now + delay;
+                            }  else {
+                                timer = sleep(rem.to_u32().unwrap_or(INFINITY)).fuse();
+                            }
                         }
                     }
                     Some(Msg::Configure(new_config)) => {
                         delay = new_config.delay().as_millis().to_f64().unwrap_or(f64::MAX);
                         config = new_config;
                     }
+                    Some(Msg::Pause) => {
+                        paused = true;
+                        timer = sleep(INFINITY).fuse();
+                    }
+                    Some(Msg::Resume) => {
+                        if paused {
+                            paused = false;
+                            f(&tx, &ctx, &config, &state);
+                            next = now() + delay;
+                        }
+                    }
+                    Some(Msg::Step) => {
+                        if paused {
+                            f(&tx, &ctx, &config, &state);
+                        }
+                    }
                     None => {
                         self.rx.close();
                         break;
@@ -172,7 +257,7 @@ where
                 () = timer => {
                     f(&tx, &ctx, &config, &state);
                     next += delay;
-                    timer = TimeoutFuture::new(INFINITY).fuse();
+                    timer = sleep(INFINITY).fuse();
                 }
             }
         }
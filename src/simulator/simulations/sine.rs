@@ -4,28 +4,27 @@ use crate::{
         publish::PublisherExt,
         simulations::{
             tick::{TickState, TickedGenerator},
-            Context, SimulationState, SingleTarget,
+            Context, SimulationState, SingleTarget, View,
         },
         Claim,
     },
-    utils::{
-        float::{ApproxF64, Zero},
-        ui::details,
-    },
+    utils::float::{ApproxF64, Zero},
 };
-use js_sys::Math::sin;
 use num_traits::ToPrimitive;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::{f64::consts::TAU, time::Duration};
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 pub struct Properties {
     pub amplitude: ApproxF64<Zero, 2>,
 
     #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
     pub length: Duration,
 
     #[serde(default = "default_period", with = "humantime_serde")]
+    #[schemars(with = "String")]
     pub period: Duration,
 
     #[serde(default)]
@@ -70,11 +69,11 @@ impl TickedGenerator for SineGenerator {
     }
 
     fn tick(now: f64, state: &mut Self::State, ctx: &mut Context) {
-        let value = sin(now * (TAU / state.length)) * state.amplitude;
+        let value = (now * (TAU / state.length)).sin() * state.amplitude;
 
         ctx.update(SimulationState {
             description: state.target.describe("Sine"),
-            html: details([("Timestamp", now), ("Value", value)]),
+            view: View::details([("Timestamp", now), ("Value", value)]),
         });
 
         ctx.publisher().publish_single(
@@ -1,23 +1,21 @@
 use crate::simulator::simulations::{Sender, SenderConfiguration, SenderHandle};
 use crate::{
+    runtime,
     simulator::{
         publish::PublisherExt,
-        simulations::{default_period, Context, Generator, SimulationState, SingleTarget},
+        simulations::{default_period, Context, Generator, SimulationState, SingleTarget, View},
         Claim,
     },
     utils::{
         float::{ApproxF64, Zero},
-        ui::details,
+        handler::Handler,
     },
 };
-use patternfly_yew::*;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
-use wasm_bindgen_futures::spawn_local;
-use yew::html::IntoPropValue;
-use yew::prelude::*;
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(untagged)]
 pub enum Step {
     Value(ApproxF64<Zero, 2>),
@@ -27,15 +25,6 @@ pub enum Step {
     },
 }
 
-impl IntoPropValue<patternfly_yew::Step> for &Step {
-    fn into_prop_value(self) -> patternfly_yew::Step {
-        patternfly_yew::Step {
-            value: self.value(),
-            label: self.label(),
-        }
-    }
-}
-
 impl Step {
     pub fn value(&self) -> f64 {
         match self {
@@ -69,10 +58,11 @@ impl Step {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 pub struct Properties {
     #[serde(default = "default_period")]
     #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
     pub delay: Duration,
     #[serde(default)]
     pub target: SingleTarget,
@@ -90,6 +80,7 @@ impl SenderConfiguration for Properties {
 pub struct SliderSimulation {
     claims: Vec<Claim>,
     properties: Properties,
+    paused: bool,
 
     sender: Option<SenderHandle<f64, Properties>>,
 }
@@ -102,6 +93,7 @@ impl Generator for SliderSimulation {
         Self {
             claims,
             properties,
+            paused: false,
             sender: None,
         }
     }
@@ -116,7 +108,7 @@ impl Generator for SliderSimulation {
             self.properties = properties.clone();
             if let Some(sender) = &mut self.sender {
                 let mut sender = sender.clone();
-                spawn_local(async move {
+                runtime::spawn(async move {
                     if let Err(err) = sender.configure(properties).await {
                         log::warn!("Failed to update configuration: {err}");
                     }
@@ -139,31 +131,24 @@ impl Generator for SliderSimulation {
                 );
 
                 let handle = handle.to_sync();
-                let onchange = Callback::from(move |value: f64| handle.update(value));
+                let onchange = Handler::from(move |value: f64| handle.update(value));
 
                 ctx.update(SimulationState {
                     description: config.target.describe("Slider"),
-                    html: html!(
-                        <div>
-                            <div>
-                                { details([(
-                                    "Value", state,
-                                )]) }
-                            </div>
-                        <Slider
-                            suppress_initial_change=true
-                            min={&config.min}
-                            max={&config.max}
-                            value={*state}
-                            {onchange}
-                            />
-                        </div>
-                    ),
+                    view: View::Stack(vec![
+                        View::details([("Value", state)]),
+                        View::Slider {
+                            min: config.min.clone(),
+                            max: config.max.clone(),
+                            value: *state,
+                            onchange,
+                        },
+                    ]),
                 });
             },
         );
 
-        sender.start();
+        sender.paused(self.paused).start();
 
         self.sender = Some(handle);
     }
@@ -171,4 +156,24 @@ impl Generator for SliderSimulation {
     fn stop(&mut self) {
         self.sender = None;
     }
+
+    fn pause(&mut self) {
+        self.paused = true;
+        if let Some(sender) = &self.sender {
+            sender.to_sync().pause();
+        }
+    }
+
+    fn resume(&mut self) {
+        self.paused = false;
+        if let Some(sender) = &self.sender {
+            sender.to_sync().resume();
+        }
+    }
+
+    fn step(&mut self) {
+        if let Some(sender) = &self.sender {
+            sender.to_sync().step();
+        }
+    }
 }
@@ -0,0 +1,383 @@
+use super::default_period;
+use crate::{
+    simulator::{
+        publish::PublisherExt,
+        simulations::{
+            tick::{TickState, TickedGenerator},
+            Context, FeatureTarget, SimulationState, View,
+        },
+        Claim, Command,
+    },
+    utils::float::{ApproxF64, Zero},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+use strum::{Display, EnumString};
+
+const MILLIS_PER_MINUTE: f64 = 60_000.0;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Properties {
+    #[serde(default = "default_period", with = "humantime_serde")]
+    #[schemars(with = "String")]
+    pub period: Duration,
+
+    /// Ambient temperature, in °C.
+    pub ambient: ApproxF64<Zero, 2>,
+    /// Initial temperature, in °C. Defaults to the ambient temperature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial: Option<ApproxF64<Zero, 2>>,
+    /// Initial setpoint, in °C.
+    pub setpoint: ApproxF64<Zero, 2>,
+    /// Initial mode.
+    #[serde(default)]
+    pub mode: Mode,
+
+    /// Heating rate, in °C per minute.
+    pub heating_rate: ApproxF64<Zero, 2>,
+    /// Cooling rate, in °C per minute.
+    pub cooling_rate: ApproxF64<Zero, 2>,
+    /// Heat exchange with the environment, as the fraction of the difference to the ambient
+    /// temperature, per minute.
+    pub loss: ApproxF64<Zero, 2>,
+    /// Allowed deviation from the setpoint, before switching the actuator, in °C.
+    #[serde(default = "default_hysteresis")]
+    pub hysteresis: ApproxF64<Zero, 2>,
+
+    /// Speed up time.
+    #[serde(default = "default_acceleration")]
+    pub acceleration: ApproxF64<Zero, 2>,
+
+    #[serde(default)]
+    pub target: FeatureTarget,
+}
+
+fn default_hysteresis() -> ApproxF64<Zero, 2> {
+    0.5f64.into()
+}
+
+fn default_acceleration() -> ApproxF64<Zero, 2> {
+    1f64.into()
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    Display,
+    EnumString,
+)]
+#[serde(rename_all = "camelCase")]
+#[strum(serialize_all = "camelCase")]
+pub enum Mode {
+    Off,
+    Heat,
+    Cool,
+    #[default]
+    Auto,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display)]
+#[strum(serialize_all = "camelCase")]
+pub enum Actuator {
+    Idle,
+    Heating,
+    Cooling,
+}
+
+/// The physical model of the room and HVAC.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Model {
+    pub ambient: f64,
+    pub temperature: f64,
+    pub setpoint: f64,
+    pub mode: Mode,
+    pub actuator: Actuator,
+
+    pub heating_rate: f64,
+    pub cooling_rate: f64,
+    pub loss: f64,
+    pub hysteresis: f64,
+}
+
+impl Model {
+    /// Decide on the actuator state, based on mode, setpoint and the current temperature.
+    fn control(&self) -> Actuator {
+        let low = self.setpoint - self.hysteresis;
+        let high = self.setpoint + self.hysteresis;
+
+        let heat = matches!(self.mode, Mode::Heat | Mode::Auto);
+        let cool = matches!(self.mode, Mode::Cool | Mode::Auto);
+
+        match self.actuator {
+            _ if heat && self.temperature < low => Actuator::Heating,
+            _ if cool && self.temperature > high => Actuator::Cooling,
+            // keep running until we pass the setpoint
+            Actuator::Heating if heat && self.temperature < self.setpoint => Actuator::Heating,
+            Actuator::Cooling if cool && self.temperature > self.setpoint => Actuator::Cooling,
+            _ => Actuator::Idle,
+        }
+    }
+
+    /// Advance the model by a number of minutes.
+    pub fn step(&mut self, minutes: f64) {
+        self.actuator = self.control();
+
+        // exchange with the environment
+        let decay = (-self.loss * minutes).exp();
+        self.temperature = self.ambient + (self.temperature - self.ambient) * decay;
+
+        // actuator
+        match self.actuator {
+            Actuator::Heating => self.temperature += self.heating_rate * minutes,
+            Actuator::Cooling => self.temperature -= self.cooling_rate * minutes,
+            Actuator::Idle => {}
+        }
+    }
+
+    /// Apply a command payload, returns `true` if something changed.
+    pub fn apply(&mut self, command: &Value) -> bool {
+        let mut changed = false;
+
+        if let Some(setpoint) = command["setpoint"].as_f64() {
+            self.setpoint = setpoint;
+            changed = true;
+        }
+
+        if let Some(mode) = command["mode"].as_str().and_then(|mode| mode.parse().ok()) {
+            self.mode = mode;
+            changed = true;
+        }
+
+        changed
+    }
+}
+
+pub struct State {
+    properties: Properties,
+    model: Model,
+    last: Option<f64>,
+}
+
+impl TickState for State {
+    fn period(&self) -> Duration {
+        self.properties.period
+    }
+}
+
+pub struct ThermostatGenerator;
+
+impl ThermostatGenerator {
+    fn publish(state: &State, ctx: &Context) {
+        let model = &state.model;
+
+        ctx.update(SimulationState {
+            description: state.properties.target.describe("Thermostat"),
+            view: View::details([
+                ("Temperature", format!("{:.2} °C", model.temperature)),
+                ("Setpoint", format!("{:.1} °C", model.setpoint)),
+                ("Mode", model.mode.to_string()),
+                ("Actuator", model.actuator.to_string()),
+                ("Ambient", format!("{:.1} °C", model.ambient)),
+            ]),
+        });
+
+        ctx.publisher().publish_feature(
+            &state.properties.target.channel,
+            &state.properties.target.feature,
+            [
+                ("temperature", json!(model.temperature)),
+                ("setpoint", json!(model.setpoint)),
+                ("mode", json!(model.mode.to_string())),
+                ("actuator", json!(model.actuator.to_string())),
+            ],
+        );
+    }
+}
+
+impl TickedGenerator for ThermostatGenerator {
+    type Properties = Properties;
+    type State = State;
+
+    fn make_state(
+        properties: &Self::Properties,
+        current_state: Option<Self::State>,
+    ) -> Self::State {
+        let mut model = Model {
+            ambient: properties.ambient.0,
+            temperature: properties.initial.unwrap_or(properties.ambient).0,
+            setpoint: properties.setpoint.0,
+            mode: properties.mode,
+            actuator: Actuator::Idle,
+            heating_rate: properties.heating_rate.0,
+            cooling_rate: properties.cooling_rate.0,
+            loss: properties.loss.0,
+            hysteresis: properties.hysteresis.0,
+        };
+
+        let last = match current_state {
+            Some(current) => {
+                model.temperature = current.model.temperature;
+                model.actuator = current.model.actuator;
+                // keep values set by commands, unless the configuration changed them
+                if current.properties.setpoint == properties.setpoint {
+                    model.setpoint = current.model.setpoint;
+                }
+                if current.properties.mode == properties.mode {
+                    model.mode = current.model.mode;
+                }
+                current.last
+            }
+            None => None,
+        };
+
+        Self::State {
+            properties: properties.clone(),
+            model,
+            last,
+        }
+    }
+
+    fn make_claims(properties: &Self::Properties) -> Vec<Claim> {
+        properties.target.claims()
+    }
+
+    fn tick(now: f64, state: &mut Self::State, ctx: &mut Context) {
+        if let Some(last) = state.last {
+            let minutes =
+                (now - last).max(0.0) / MILLIS_PER_MINUTE * state.properties.acceleration.0;
+            state.model.step(minutes);
+        }
+        state.last = Some(now);
+
+        Self::publish(state, ctx);
+    }
+
+    fn command(command: &Command, state: &mut Self::State, ctx: &mut Context) {
+        let target = &state.properties.target;
+        if command.name != target.channel {
+            return;
+        }
+
+        let json = match command
+            .payload
+            .as_ref()
+            .and_then(|payload| serde_json::from_slice::<Value>(payload).ok())
+        {
+            Some(json) => json,
+            None => return,
+        };
+
+        if state.model.apply(&json[&target.feature]) {
+            // report the change right away
+            Self::publish(state, ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn model(mode: Mode) -> Model {
+        Model {
+            ambient: 10.0,
+            temperature: 10.0,
+            setpoint: 20.0,
+            mode,
+            actuator: Actuator::Idle,
+            heating_rate: 1.0,
+            cooling_rate: 1.0,
+            loss: 0.01,
+            hysteresis: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_off_approaches_ambient() {
+        let mut model = model(Mode::Off);
+        model.temperature = 30.0;
+
+        for _ in 0..1000 {
+            model.step(1.0);
+            assert_eq!(Actuator::Idle, model.actuator);
+        }
+
+        assert!((model.temperature - 10.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_heat_reaches_setpoint() {
+        let mut model = model(Mode::Heat);
+
+        model.step(1.0);
+        assert_eq!(Actuator::Heating, model.actuator);
+
+        for _ in 0..60 {
+            model.step(1.0);
+        }
+
+        // regulated around the setpoint
+        assert!(
+            (model.temperature - 20.0).abs() <= 1.5,
+            "Temperature: {}",
+            model.temperature
+        );
+    }
+
+    #[test]
+    fn test_cool_mode_does_not_heat() {
+        let mut model = model(Mode::Cool);
+
+        model.step(1.0);
+        assert_eq!(Actuator::Idle, model.actuator);
+        assert!((model.temperature - 10.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_auto_cools() {
+        let mut model = model(Mode::Auto);
+        model.temperature = 25.0;
+
+        model.step(1.0);
+        assert_eq!(Actuator::Cooling, model.actuator);
+        assert!(model.temperature < 25.0);
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let mut model = model(Mode::Heat);
+
+        // inside the band, and idle: stay idle
+        model.temperature = 19.8;
+        assert_eq!(Actuator::Idle, model.control());
+
+        // inside the band, but heating: keep heating until the setpoint
+        model.actuator = Actuator::Heating;
+        assert_eq!(Actuator::Heating, model.control());
+
+        model.temperature = 20.1;
+        assert_eq!(Actuator::Idle, model.control());
+    }
+
+    #[test]
+    fn test_apply_command() {
+        let mut model = model(Mode::Off);
+
+        assert!(model.apply(&json!({"setpoint": 22.5, "mode": "cool"})));
+        assert_eq!(22.5, model.setpoint);
+        assert_eq!(Mode::Cool, model.mode);
+
+        assert!(!model.apply(&json!({"mode": "unknown"})));
+        assert_eq!(Mode::Cool, model.mode);
+    }
+}
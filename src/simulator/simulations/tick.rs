@@ -1,13 +1,16 @@
-use crate::simulator::{
-    simulations::{Context, Generator},
-    Claim,
+use crate::{
+    runtime::{now, sleep, spawn},
+    simulator::{
+        simulations::{Context, Generator},
+        Claim, Command,
+    },
 };
-use futures::{channel::mpsc, select, FutureExt, SinkExt, StreamExt};
-use gloo_timers::future::TimeoutFuture;
-use js_sys::Date;
+use futures::{channel::mpsc, select, FutureExt, StreamExt};
 use num_traits::ToPrimitive;
 use std::time::Duration;
-use wasm_bindgen_futures::spawn_local;
+
+// internally this is a i32, so infinity is i32::MAX, but as u32
+const INFINITY: u32 = i32::MAX as u32;
 
 pub trait TickedGenerator: Sized {
     type Properties: 'static + Clone + PartialEq;
@@ -18,6 +21,11 @@ pub trait TickedGenerator: Sized {
     fn make_claims(properties: &Self::Properties) -> Vec<Claim>;
     fn tick(now: f64, state: &mut Self::State, ctx: &mut Context);
 
+    /// Handle incoming commands
+    ///
+    /// The function will receive all commands and must filter out its own.
+    fn command(_command: &Command, _state: &mut Self::State, _ctx: &mut Context) {}
+
     fn new(properties: Self::Properties) -> TickingGenerator<Self> {
         TickingGenerator::new(properties)
     }
@@ -33,11 +41,30 @@ where
 {
     properties: G::Properties,
     claims: Vec<Claim>,
+    paused: bool,
     tx: Option<mpsc::UnboundedSender<Msg<G::Properties>>>,
 }
 
 pub enum Msg<P> {
     Update(P),
+    Pause,
+    Resume,
+    Step,
+    Command(Command),
+}
+
+impl<G> TickingGenerator<G>
+where
+    G: TickedGenerator,
+{
+    /// send to loop, if it is running
+    fn send(&self, msg: Msg<G::Properties>) {
+        if let Some(tx) = &self.tx {
+            if let Err(err) = tx.unbounded_send(msg) {
+                log::info!("Failed to deliver message: {err}");
+            }
+        }
+    }
 }
 
 impl<G> Generator for TickingGenerator<G>
@@ -51,6 +78,7 @@ where
         Self {
             properties,
             tx: None,
+            paused: false,
             claims,
         }
     }
@@ -70,14 +98,25 @@ where
         self.claims = G::make_claims(&properties);
 
         // send to loop
-        if let Some(tx) = &self.tx {
-            let mut tx = tx.clone();
-            spawn_local(async move {
-                if let Err(err) = tx.send(Msg::Update(properties)).await {
-                    log::info!("Failed to deliver message: {err}");
-                };
-            });
-        }
+        self.send(Msg::Update(properties));
+    }
+
+    fn pause(&mut self) {
+        self.paused = true;
+        self.send(Msg::Pause);
+    }
+
+    fn resume(&mut self) {
+        self.paused = false;
+        self.send(Msg::Resume);
+    }
+
+    fn step(&mut self) {
+        self.send(Msg::Step);
+    }
+
+    fn command(&mut self, command: &Command) {
+        self.send(Msg::Command(command.clone()));
     }
 
     fn start(&mut self, mut ctx: Context) {
@@ -87,11 +126,12 @@ where
 
         let mut state = G::make_state(&self.properties, None);
         let mut period = state.period().as_millis().to_f64().unwrap_or(f64::MAX);
+        let mut paused = self.paused;
 
-        spawn_local(async move {
-            // we start with a zero delay
-            let mut tick = TimeoutFuture::new(0).fuse();
-            let mut last = Date::now();
+        spawn(async move {
+            // we start with a zero delay, unless we are paused
+            let mut tick = sleep(if paused { INFINITY } else { 0 }).fuse();
+            let mut last = now();
 
             loop {
                 select! {
@@ -104,12 +144,33 @@ where
                             let new_period = state.period().as_millis().to_f64().unwrap_or(f64::MAX);
                             if period != new_period {
                                 period = new_period;
-                                tick = TimeoutFuture::new(period.to_u32().unwrap_or(u32::MAX)).fuse();
+                                if !paused {
+                                    tick = sleep(period.to_u32().unwrap_or(u32::MAX)).fuse();
+                                }
                             }
                         }
+                        Some(Msg::Pause) => {
+                            paused = true;
+                            tick = sleep(INFINITY).fuse();
+                        }
+                        Some(Msg::Resume) => {
+                            if paused {
+                                paused = false;
+                                last = now();
+                                tick = sleep(0).fuse();
+                            }
+                        }
+                        Some(Msg::Step) => {
+                            if paused {
+                                G::tick(now(), &mut state, &mut ctx);
+                            }
+                        }
+                        Some(Msg::Command(command)) => {
+                            G::command(&command, &mut state, &mut ctx);
+                        }
                     },
                     () = tick => {
-                        let now = Date::now();
+                        let now = now();
                         let next = last + period;
                         let delay = if next < now {
                             0f64
@@ -119,7 +180,7 @@ where
                         last = next;
                         G::tick(now, &mut state, &mut ctx);
                         log::trace!("Next delay: {delay}");
-                        tick = TimeoutFuture::new(delay.to_u32().unwrap_or(u32::MAX)).fuse();
+                        tick = sleep(delay.to_u32().unwrap_or(u32::MAX)).fuse();
                     }
                 }
             }
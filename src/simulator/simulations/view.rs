@@ -0,0 +1,102 @@
+use crate::{
+    simulator::simulations::{led_matrix::Display, slider::Step},
+    utils::{geo::Point, handler::Handler},
+};
+
+/// The view of a simulation, independent of the frontend rendering it.
+///
+/// Interactive elements carry a [`Handler`], which gets called with the new value when the
+/// user changes it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum View {
+    #[default]
+    Empty,
+    /// Views, shown below each other.
+    Stack(Vec<View>),
+    /// Views, shown next to each other. The last one takes up the remaining space.
+    Row(Vec<View>),
+    /// A form, of labeled views.
+    Form(Vec<(String, View)>),
+    /// A toolbar, of views.
+    Toolbar(Vec<View>),
+
+    /// Read-only values, with a label.
+    Details(Vec<(String, String)>),
+    /// An informational text.
+    Message(String),
+    /// An error.
+    Error { title: String, message: String },
+
+    Slider {
+        min: Step,
+        max: Step,
+        value: f64,
+        onchange: Handler<f64>,
+    },
+    Switch {
+        checked: bool,
+        onchange: Handler<bool>,
+    },
+    Button {
+        label: String,
+        primary: bool,
+        onclick: Handler<()>,
+    },
+    TextInput {
+        value: String,
+        onchange: Handler<String>,
+    },
+    Select {
+        options: Vec<String>,
+        selected: Option<String>,
+        onselect: Handler<String>,
+    },
+    /// A file input, passing on the uploaded file.
+    File {
+        /// Description of the current file.
+        current: String,
+        onupload: Handler<Upload>,
+    },
+
+    /// The planned route, and the recent track of a location.
+    Track {
+        route: Vec<Point>,
+        track: Vec<Point>,
+    },
+    /// The display of an LED matrix.
+    LedMatrix {
+        display: Display,
+        color: String,
+        color_off: String,
+    },
+}
+
+/// A file, uploaded by the user.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Upload {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+pub trait ToDetail {
+    fn to_details(&self) -> (String, String);
+}
+
+impl<V> ToDetail for (&str, V)
+where
+    V: ToString,
+{
+    fn to_details(&self) -> (String, String) {
+        (self.0.into(), self.1.to_string())
+    }
+}
+
+impl View {
+    pub fn details<D, I>(details: I) -> Self
+    where
+        D: ToDetail + Sized,
+        I: IntoIterator<Item = D>,
+    {
+        Self::Details(details.into_iter().map(|d| d.to_details()).collect())
+    }
+}
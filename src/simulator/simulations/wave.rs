@@ -4,29 +4,28 @@ use crate::{
         publish::PublisherExt,
         simulations::{
             tick::{TickState, TickedGenerator},
-            Context, SimulationState, SingleTarget,
+            Context, SimulationState, SingleTarget, View,
         },
         Claim,
     },
-    utils::{
-        float::{ApproxF64, Zero},
-        ui::details,
-    },
+    utils::float::{ApproxF64, Zero},
 };
 use humantime_serde::Serde;
-use js_sys::Math::sin;
 use num_traits::ToPrimitive;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::{f64::consts::TAU, time::Duration};
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 pub struct Properties {
+    #[schemars(with = "Vec<String>")]
     pub lengths: Vec<Serde<Duration>>,
     pub amplitudes: Vec<ApproxF64<Zero, 2>>,
 
     pub offset: ApproxF64<Zero, 2>,
 
     #[serde(default = "default_period", with = "humantime_serde")]
+    #[schemars(with = "String")]
     pub period: Duration,
 
     #[serde(default)]
@@ -78,12 +77,12 @@ impl TickedGenerator for WaveGenerator {
         let mut value = state.offset;
 
         for [l, a] in &state.parameters {
-            value += sin(now * (TAU / l)) * a;
+            value += (now * (TAU / l)).sin() * a;
         }
 
         ctx.update(SimulationState {
             description: state.target.describe("Wave"),
-            html: details([("Timestamp", now), ("Value", value)]),
+            view: View::details([("Timestamp", now), ("Value", value)]),
         });
 
         ctx.publisher().publish_single(
@@ -0,0 +1,195 @@
+use super::Connector;
+use crate::connector::{mqtt::QoS, socket::str_err};
+use crate::runtime;
+use crate::simulator::{
+    websocket::Framing, Command, ConnectOptions, ConnectorOptions, SubscribeOptions,
+};
+use crate::utils::handler::Handler;
+use anyhow::bail;
+use std::{cell::Cell, rc::Rc};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Event, EventSource, Headers, MessageEvent, Request, RequestInit, Response};
+
+/// Post events via HTTP, and receive commands as Server-Sent Events.
+///
+/// Events and commands use the same framing as the WebSocket connector. Browsers don't allow
+/// setting headers for an `EventSource`, so the credentials are only sent when posting events.
+pub struct ServerSentEventsConnector {
+    url: String,
+    commands: String,
+    framing: Rc<Framing>,
+    authorization: Option<String>,
+
+    on_command: Handler<Command>,
+    on_connection_lost: Handler<String>,
+
+    source: Option<Source>,
+}
+
+impl ServerSentEventsConnector {
+    pub fn new(opts: ConnectorOptions, commands: &str, framing: Framing) -> Self {
+        let authorization = opts.credentials.to_authorization(opts.settings);
+
+        Self {
+            url: opts.url.to_string(),
+            commands: commands.to_string(),
+            framing: Rc::new(framing),
+            authorization,
+            on_command: opts.on_command,
+            on_connection_lost: opts.on_connection_lost,
+            source: None,
+        }
+    }
+
+    fn request(&self, body: &str) -> anyhow::Result<Request> {
+        let headers = Headers::new().map_err(str_err)?;
+        headers
+            .set("Content-Type", "application/json")
+            .map_err(str_err)?;
+        if let Some(authorization) = &self.authorization {
+            headers
+                .set("Authorization", authorization)
+                .map_err(str_err)?;
+        }
+
+        let mut init = RequestInit::new();
+        init.method("POST")
+            .headers(&headers)
+            .body(Some(&JsValue::from_str(body)));
+
+        Request::new_with_str_and_init(&self.url, &init).map_err(str_err)
+    }
+}
+
+async fn post(request: Request) -> anyhow::Result<()> {
+    let response: Response = JsFuture::from(gloo_utils::window().fetch_with_request(&request))
+        .await
+        .map_err(str_err)?
+        .dyn_into()
+        .map_err(str_err)?;
+
+    if !response.ok() {
+        bail!("{} {}", response.status(), response.status_text());
+    }
+
+    Ok(())
+}
+
+impl Connector for ServerSentEventsConnector {
+    fn connect(&mut self, opts: ConnectOptions) -> anyhow::Result<()> {
+        let source = EventSource::new(&self.commands).map_err(str_err)?;
+
+        // an error before being opened is a failure to connect
+        let opened = Rc::new(Cell::new(false));
+
+        let on_open = {
+            let opened = opened.clone();
+            let on_success = opts.on_success;
+            move || {
+                opened.set(true);
+                on_success.emit(());
+            }
+        };
+
+        let on_message = {
+            let framing = self.framing.clone();
+            let on_command = self.on_command.clone();
+            move |event: MessageEvent| match event.data().as_string() {
+                Some(text) => match framing.parse_command(&text) {
+                    Some(command) => on_command.emit(command),
+                    None => log::info!("Ignoring invalid command: {text}"),
+                },
+                None => log::info!("Ignoring non-text event"),
+            }
+        };
+
+        // the event source would retry on its own, but reconnecting is up to the engine
+        let on_error = {
+            let source = source.clone();
+            let on_failure = opts.on_failure;
+            let on_connection_lost = self.on_connection_lost.clone();
+            move |_: Event| {
+                // closing prevents any further events
+                source.close();
+                if opened.get() {
+                    on_connection_lost.emit("Event stream failed".into());
+                } else {
+                    on_failure.emit("Failed to open the event stream".into());
+                }
+            }
+        };
+
+        self.source = Some(Source::new(source, on_open, on_message, on_error));
+
+        Ok(())
+    }
+
+    fn subscribe(&mut self, opts: SubscribeOptions) -> anyhow::Result<()> {
+        // commands are received on the event stream
+        opts.on_success.emit(());
+        Ok(())
+    }
+
+    fn publish(&mut self, channel: &str, payload: Vec<u8>, _qos: QoS) {
+        if self.source.is_none() {
+            return;
+        }
+
+        let request = serde_json::to_string(&self.framing.encode_event(channel, &payload))
+            .map_err(anyhow::Error::from)
+            .and_then(|body| self.request(&body));
+
+        match request {
+            Ok(request) => runtime::spawn(async move {
+                if let Err(err) = post(request).await {
+                    log::info!("Failed to publish: {err}");
+                }
+            }),
+            Err(err) => log::info!("Failed to prepare event: {err}"),
+        }
+    }
+}
+
+/// An event source, keeping its event handlers alive.
+///
+/// Dropping it unregisters the handlers, and closes the event source.
+struct Source {
+    source: EventSource,
+    _on_open: Closure<dyn Fn()>,
+    _on_message: Closure<dyn Fn(MessageEvent)>,
+    _on_error: Closure<dyn Fn(Event)>,
+}
+
+impl Source {
+    fn new<O, M, E>(source: EventSource, on_open: O, on_message: M, on_error: E) -> Self
+    where
+        O: Fn() + 'static,
+        M: Fn(MessageEvent) + 'static,
+        E: Fn(Event) + 'static,
+    {
+        let on_open = Closure::wrap(Box::new(on_open) as Box<dyn Fn()>);
+        let on_message = Closure::wrap(Box::new(on_message) as Box<dyn Fn(MessageEvent)>);
+        let on_error = Closure::wrap(Box::new(on_error) as Box<dyn Fn(Event)>);
+
+        source.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        source.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        source.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+        Self {
+            source,
+            _on_open: on_open,
+            _on_message: on_message,
+            _on_error: on_error,
+        }
+    }
+}
+
+impl Drop for Source {
+    fn drop(&mut self) {
+        self.source.set_onopen(None);
+        self.source.set_onmessage(None);
+        self.source.set_onerror(None);
+        self.source.close();
+    }
+}
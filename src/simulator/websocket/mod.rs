@@ -0,0 +1,231 @@
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+
+#[cfg(target_arch = "wasm32")]
+pub use wasm::*;
+
+use crate::simulator::Command;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// Encoding of a payload, which isn't JSON.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Encoding {
+    /// The payload is a string, containing the data as is.
+    Text,
+    /// The payload is a string, containing the base64 encoded data.
+    Base64,
+}
+
+/// The framing of events and commands, each being one JSON object per message.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Framing {
+    /// The field carrying the channel of an event.
+    #[serde(default = "default_channel_field")]
+    pub channel: String,
+    /// The field carrying the name of a command.
+    #[serde(default = "default_command_field")]
+    pub command: String,
+    /// The field carrying the payload.
+    #[serde(default = "default_payload_field")]
+    pub payload: String,
+    /// The field carrying the encoding of a payload which isn't JSON.
+    #[serde(default = "default_encoding_field")]
+    pub encoding: String,
+    /// Wrap the object into an envelope, as the value of this field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub envelope: Option<String>,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Self {
+            channel: default_channel_field(),
+            command: default_command_field(),
+            payload: default_payload_field(),
+            encoding: default_encoding_field(),
+            envelope: None,
+        }
+    }
+}
+
+fn default_channel_field() -> String {
+    "channel".into()
+}
+
+fn default_command_field() -> String {
+    "command".into()
+}
+
+fn default_payload_field() -> String {
+    "payload".into()
+}
+
+fn default_encoding_field() -> String {
+    "encoding".into()
+}
+
+/// Sent as first message, as browsers don't allow setting headers for WebSockets.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthFrame {
+    /// The value of the `Authorization` header.
+    pub authorization: String,
+}
+
+impl Framing {
+    /// Encode an event.
+    pub fn encode_event(&self, channel: &str, payload: &[u8]) -> Value {
+        let (payload, encoding) = match serde_json::from_slice(payload) {
+            Ok(json) => (json, None),
+            Err(_) => match std::str::from_utf8(payload) {
+                Ok(text) => (Value::String(text.to_string()), Some(Encoding::Text)),
+                Err(_) => (
+                    Value::String(base64::encode(payload)),
+                    Some(Encoding::Base64),
+                ),
+            },
+        };
+
+        let mut frame = Map::new();
+        frame.insert(self.channel.clone(), json!(channel));
+        frame.insert(self.payload.clone(), payload);
+        if let Some(encoding) = encoding {
+            frame.insert(self.encoding.clone(), json!(encoding));
+        }
+
+        match &self.envelope {
+            Some(envelope) => {
+                let mut wrapped = Map::new();
+                wrapped.insert(envelope.clone(), Value::Object(frame));
+                Value::Object(wrapped)
+            }
+            None => Value::Object(frame),
+        }
+    }
+
+    /// Parse a command, from the text of a received message.
+    ///
+    /// Returns `None` if it isn't a command, or the payload can't be decoded.
+    pub fn parse_command(&self, text: &str) -> Option<Command> {
+        let mut frame = serde_json::from_str::<Value>(text).ok()?;
+        if let Some(envelope) = &self.envelope {
+            frame = frame.get_mut(envelope)?.take();
+        }
+
+        let name = frame.get(&self.command)?.as_str()?.to_string();
+        let payload = frame.get(&self.payload).cloned().unwrap_or_default();
+        let encoding = match frame.get(&self.encoding) {
+            None | Some(Value::Null) => None,
+            Some(encoding) => Some(serde_json::from_value(encoding.clone()).ok()?),
+        };
+
+        let payload = match (payload, encoding) {
+            (Value::Null, None) => None,
+            (Value::String(text), Some(Encoding::Text)) => Some(text.into_bytes()),
+            (Value::String(data), Some(Encoding::Base64)) => Some(base64::decode(data).ok()?),
+            (_, Some(_)) => return None,
+            (json, None) => Some(serde_json::to_vec(&json).ok()?),
+        };
+
+        Some(Command { name, payload })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode_event() {
+        let framing = Framing::default();
+        assert_eq!(
+            framing.encode_event("state", br#"{"features":{}}"#),
+            json!({"channel": "state", "payload": {"features": {}}})
+        );
+        assert_eq!(
+            framing.encode_event("log", b"hello"),
+            json!({"channel": "log", "payload": "hello", "encoding": "text"})
+        );
+        assert_eq!(
+            framing.encode_event("image", &[0xff, 0x00]),
+            json!({"channel": "image", "payload": "/wA=", "encoding": "base64"})
+        );
+    }
+
+    #[test]
+    fn test_parse_command() {
+        let framing = Framing::default();
+        assert_eq!(
+            framing.parse_command(r#"{"command": "set", "payload": {"value": true}}"#),
+            Some(Command {
+                name: "set".into(),
+                payload: Some(br#"{"value":true}"#.to_vec())
+            })
+        );
+        assert_eq!(
+            framing.parse_command(r#"{"command": "reboot"}"#),
+            Some(Command {
+                name: "reboot".into(),
+                payload: None
+            })
+        );
+        assert_eq!(
+            framing.parse_command(r#"{"command": "say", "payload": "hi", "encoding": "text"}"#),
+            Some(Command {
+                name: "say".into(),
+                payload: Some(b"hi".to_vec())
+            })
+        );
+        assert_eq!(
+            framing.parse_command(r#"{"command": "raw", "payload": "/wA=", "encoding": "base64"}"#),
+            Some(Command {
+                name: "raw".into(),
+                payload: Some(vec![0xff, 0x00])
+            })
+        );
+
+        assert_eq!(framing.parse_command(r#"{"channel": "state"}"#), None);
+        assert_eq!(
+            framing.parse_command(r#"{"command": "raw", "payload": 1, "encoding": "base64"}"#),
+            None
+        );
+        assert_eq!(framing.parse_command("not json"), None);
+    }
+
+    #[test]
+    fn test_custom_framing() {
+        let framing: Framing = serde_json::from_value(json!({
+            "channel": "topic",
+            "command": "name",
+            "payload": "data",
+            "encoding": "contentEncoding",
+            "envelope": "message",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            framing.encode_event("state", br#"{"features":{}}"#),
+            json!({"message": {"topic": "state", "data": {"features": {}}}})
+        );
+        assert_eq!(
+            framing.encode_event("log", b"hello"),
+            json!({"message": {"topic": "log", "data": "hello", "contentEncoding": "text"}})
+        );
+
+        assert_eq!(
+            framing.parse_command(r#"{"message": {"name": "set", "data": {"value": true}}}"#),
+            Some(Command {
+                name: "set".into(),
+                payload: Some(br#"{"value":true}"#.to_vec())
+            })
+        );
+        // not in the envelope
+        assert_eq!(
+            framing.parse_command(r#"{"name": "set", "data": {"value": true}}"#),
+            None
+        );
+    }
+}
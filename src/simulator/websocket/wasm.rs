@@ -0,0 +1,121 @@
+use super::{AuthFrame, Framing};
+use crate::connector::{
+    mqtt::QoS,
+    socket::{close_reason, str_err, Socket},
+};
+use crate::simulator::{Command, ConnectOptions, Connector, ConnectorOptions, SubscribeOptions};
+use crate::utils::handler::Handler;
+use std::{cell::Cell, rc::Rc};
+use web_sys::{CloseEvent, MessageEvent, WebSocket};
+
+/// Send and receive JSON messages, using a plain WebSocket.
+pub struct WebSocketConnector {
+    url: String,
+    protocol: Option<String>,
+    framing: Rc<Framing>,
+    authorization: Option<String>,
+
+    on_command: Handler<Command>,
+    on_connection_lost: Handler<String>,
+
+    socket: Option<Socket>,
+}
+
+impl WebSocketConnector {
+    pub fn new(opts: ConnectorOptions, protocol: Option<String>, framing: Framing) -> Self {
+        let authorization = opts.credentials.to_authorization(opts.settings);
+
+        Self {
+            url: opts.url.to_string(),
+            protocol,
+            framing: Rc::new(framing),
+            authorization,
+            on_command: opts.on_command,
+            on_connection_lost: opts.on_connection_lost,
+            socket: None,
+        }
+    }
+}
+
+impl Connector for WebSocketConnector {
+    fn connect(&mut self, opts: ConnectOptions) -> anyhow::Result<()> {
+        let socket = match &self.protocol {
+            Some(protocol) => WebSocket::new_with_str(&self.url, protocol),
+            None => WebSocket::new(&self.url),
+        }
+        .map_err(str_err)?;
+
+        // a close before being opened is a failure to connect
+        let opened = Rc::new(Cell::new(false));
+
+        let on_open = {
+            let opened = opened.clone();
+            let socket = socket.clone();
+            let authorization = self.authorization.clone();
+            let on_success = opts.on_success;
+            move || {
+                opened.set(true);
+                if let Some(authorization) = &authorization {
+                    let auth = AuthFrame {
+                        authorization: authorization.clone(),
+                    };
+                    if let Ok(auth) = serde_json::to_string(&auth) {
+                        let _ = socket.send_with_str(&auth);
+                    }
+                }
+                on_success.emit(());
+            }
+        };
+
+        let on_message = {
+            let framing = self.framing.clone();
+            let on_command = self.on_command.clone();
+            move |event: MessageEvent| match event.data().as_string() {
+                Some(text) => match framing.parse_command(&text) {
+                    Some(command) => on_command.emit(command),
+                    None => log::info!("Ignoring invalid command: {text}"),
+                },
+                None => log::info!("Ignoring binary message"),
+            }
+        };
+
+        let on_close = {
+            let on_failure = opts.on_failure;
+            let on_connection_lost = self.on_connection_lost.clone();
+            move |event: CloseEvent| {
+                let reason = close_reason(&event);
+                if opened.get() {
+                    on_connection_lost.emit(reason);
+                } else {
+                    on_failure.emit(reason);
+                }
+            }
+        };
+
+        self.socket = Some(Socket::new(socket, on_open, on_message, on_close));
+
+        Ok(())
+    }
+
+    fn subscribe(&mut self, opts: SubscribeOptions) -> anyhow::Result<()> {
+        // commands are received on the same socket
+        opts.on_success.emit(());
+        Ok(())
+    }
+
+    fn publish(&mut self, channel: &str, payload: Vec<u8>, _qos: QoS) {
+        let socket = match self.socket.as_ref().and_then(Socket::open) {
+            Some(socket) => socket,
+            None => return,
+        };
+
+        match serde_json::to_string(&self.framing.encode_event(channel, &payload)) {
+            Ok(frame) => {
+                if let Err(err) = socket.send_with_str(&frame) {
+                    log::info!("Failed to publish: {err:?}");
+                }
+            }
+            Err(err) => log::info!("Failed to encode event: {err}"),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use float_cmp::ApproxEq;
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
 use serde::de::{Error, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
@@ -66,6 +67,20 @@ impl<'de, E: Epsilon64, const U: i64> Deserialize<'de> for ApproxF64<E, U> {
     }
 }
 
+impl<E: Epsilon64, const U: i64> JsonSchema for ApproxF64<E, U> {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        f64::schema_name()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        f64::json_schema(gen)
+    }
+}
+
 impl<E: Epsilon64, const U: i64> Deref for ApproxF64<E, U> {
     type Target = f64;
 
@@ -0,0 +1,254 @@
+use anyhow::{anyhow, bail};
+use serde_json::Value;
+
+/// Mean earth radius, in meters.
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Point {
+    pub lat: f64,
+    pub lon: f64,
+    pub alt: f64,
+}
+
+impl Point {
+    pub const fn new(lat: f64, lon: f64, alt: f64) -> Self {
+        Self { lat, lon, alt }
+    }
+}
+
+/// Great circle distance between two points, in meters.
+pub fn distance(a: &Point, b: &Point) -> f64 {
+    let (lat1, lat2) = (a.lat.to_radians(), b.lat.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (b.lon - a.lon).to_radians();
+
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * h.sqrt().asin()
+}
+
+/// Initial bearing from one point to another, in degrees (0 to 360, clockwise from north).
+pub fn bearing(a: &Point, b: &Point) -> f64 {
+    let (lat1, lat2) = (a.lat.to_radians(), b.lat.to_radians());
+    let dlon = (b.lon - a.lon).to_radians();
+
+    let y = dlon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+    (y.atan2(x).to_degrees() + 360.0) % 360.0
+}
+
+/// The point reached when travelling a distance (in meters) along a bearing (in degrees).
+pub fn destination(a: &Point, bearing: f64, distance: f64) -> Point {
+    let lat1 = a.lat.to_radians();
+    let lon1 = a.lon.to_radians();
+    let bearing = bearing.to_radians();
+    let d = distance / EARTH_RADIUS;
+
+    let lat2 = (lat1.sin() * d.cos() + lat1.cos() * d.sin() * bearing.cos()).asin();
+    let lon2 =
+        lon1 + (bearing.sin() * d.sin() * lat1.cos()).atan2(d.cos() - lat1.sin() * lat2.sin());
+
+    Point {
+        lat: lat2.to_degrees(),
+        lon: (lon2.to_degrees() + 540.0) % 360.0 - 180.0,
+        alt: a.alt,
+    }
+}
+
+/// A path along a list of points.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path {
+    points: Vec<Point>,
+    /// The distance from the start, for each point.
+    offsets: Vec<f64>,
+}
+
+impl Path {
+    pub fn new(points: Vec<Point>) -> Self {
+        let mut offsets = Vec::with_capacity(points.len());
+        let mut total = 0.0;
+        for (i, point) in points.iter().enumerate() {
+            if i > 0 {
+                total += distance(&points[i - 1], point);
+            }
+            offsets.push(total);
+        }
+        Self { points, offsets }
+    }
+
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    /// Total length of the path, in meters.
+    pub fn length(&self) -> f64 {
+        self.offsets.last().copied().unwrap_or_default()
+    }
+
+    /// The position and heading after travelling a distance along the path.
+    ///
+    /// The distance is clamped to the length of the path.
+    pub fn position(&self, distance: f64) -> Option<(Point, f64)> {
+        let first = self.points.first()?;
+        if self.points.len() < 2 {
+            return Some((*first, 0.0));
+        }
+
+        let distance = distance.clamp(0.0, self.length());
+
+        // find the segment, skipping zero-length segments at the end
+        let idx = self
+            .offsets
+            .iter()
+            .rposition(|offset| *offset <= distance)
+            .unwrap_or_default()
+            .min(self.points.len() - 2);
+
+        let (a, b) = (&self.points[idx], &self.points[idx + 1]);
+        let len = self.offsets[idx + 1] - self.offsets[idx];
+        let fraction = if len > 0.0 {
+            (distance - self.offsets[idx]) / len
+        } else {
+            0.0
+        };
+
+        let point = Point {
+            lat: a.lat + (b.lat - a.lat) * fraction,
+            lon: a.lon + (b.lon - a.lon) * fraction,
+            alt: a.alt + (b.alt - a.alt) * fraction,
+        };
+
+        Some((point, bearing(a, b)))
+    }
+}
+
+/// Extract the points of a GeoJSON `LineString`.
+///
+/// This accepts a plain geometry, a `Feature`, or a `FeatureCollection`, in which case the first
+/// `LineString` is used.
+pub fn from_geojson(value: &Value) -> anyhow::Result<Vec<Point>> {
+    match value["type"].as_str() {
+        Some("LineString") => value["coordinates"]
+            .as_array()
+            .ok_or_else(|| anyhow!("Missing coordinates"))?
+            .iter()
+            .map(to_point)
+            .collect(),
+        Some("Feature") => from_geojson(&value["geometry"]),
+        Some("FeatureCollection") => value["features"]
+            .as_array()
+            .ok_or_else(|| anyhow!("Missing features"))?
+            .iter()
+            .find_map(|feature| from_geojson(feature).ok())
+            .ok_or_else(|| anyhow!("No LineString feature found")),
+        Some(other) => bail!("Unsupported GeoJSON type: {other}"),
+        None => bail!("Missing GeoJSON type"),
+    }
+}
+
+/// Convert a GeoJSON position (`[lon, lat, alt?]`) into a point.
+fn to_point(value: &Value) -> anyhow::Result<Point> {
+    let coordinates = value
+        .as_array()
+        .ok_or_else(|| anyhow!("Position must be an array"))?;
+    let get = |idx: usize| coordinates.get(idx).and_then(|v| v.as_f64());
+
+    match (get(0), get(1)) {
+        (Some(lon), Some(lat)) => Ok(Point::new(lat, lon, get(2).unwrap_or_default())),
+        _ => bail!("Position requires longitude and latitude"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn assert_close(expected: f64, actual: f64, epsilon: f64) {
+        assert!(
+            (expected - actual).abs() < epsilon,
+            "expected: {expected}, actual: {actual}"
+        );
+    }
+
+    #[test]
+    fn test_distance() {
+        // one degree of latitude, along a meridian
+        let d = distance(&Point::new(0.0, 0.0, 0.0), &Point::new(1.0, 0.0, 0.0));
+        assert_close(111_195.0, d, 1.0);
+    }
+
+    #[test]
+    fn test_bearing() {
+        let origin = Point::new(0.0, 0.0, 0.0);
+        assert_close(0.0, bearing(&origin, &Point::new(1.0, 0.0, 0.0)), 0.001);
+        assert_close(90.0, bearing(&origin, &Point::new(0.0, 1.0, 0.0)), 0.001);
+        assert_close(180.0, bearing(&origin, &Point::new(-1.0, 0.0, 0.0)), 0.001);
+        assert_close(270.0, bearing(&origin, &Point::new(0.0, -1.0, 0.0)), 0.001);
+    }
+
+    #[test]
+    fn test_destination() {
+        let origin = Point::new(52.52, 13.405, 0.0);
+        let target = destination(&origin, 45.0, 1000.0);
+
+        assert_close(1000.0, distance(&origin, &target), 0.01);
+        assert_close(45.0, bearing(&origin, &target), 0.1);
+    }
+
+    #[test]
+    fn test_path() {
+        let path = Path::new(vec![
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 100.0),
+            Point::new(1.0, 1.0, 100.0),
+        ]);
+
+        let (start, heading) = path.position(0.0).unwrap();
+        assert_eq!(Point::new(0.0, 0.0, 0.0), start);
+        assert_close(0.0, heading, 0.001);
+
+        let (middle, _) = path.position(path.length() / 4.0).unwrap();
+        assert_close(0.5, middle.lat, 0.001);
+        assert_close(50.0, middle.alt, 0.1);
+
+        let (end, heading) = path.position(path.length() * 2.0).unwrap();
+        assert_close(1.0, end.lat, 0.000_001);
+        assert_close(1.0, end.lon, 0.000_001);
+        assert_close(90.0, heading, 0.1);
+    }
+
+    #[test]
+    fn test_empty_path() {
+        assert_eq!(None, Path::new(vec![]).position(0.0));
+    }
+
+    #[test]
+    fn test_geojson() {
+        let points = from_geojson(&json!({
+            "type": "FeatureCollection",
+            "features": [{
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": [1.0, 2.0] }
+            }, {
+                "type": "Feature",
+                "geometry": {
+                    "type": "LineString",
+                    "coordinates": [[13.4, 52.5], [13.5, 52.6, 42.0]]
+                }
+            }]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            vec![Point::new(52.5, 13.4, 0.0), Point::new(52.6, 13.5, 42.0)],
+            points
+        );
+    }
+
+    #[test]
+    fn test_geojson_invalid() {
+        assert!(from_geojson(&json!({"type": "Polygon"})).is_err());
+        assert!(from_geojson(&json!({"type": "LineString", "coordinates": [[1.0]]})).is_err());
+    }
+}
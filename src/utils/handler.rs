@@ -0,0 +1,90 @@
+use std::{
+    fmt::{Debug, Formatter},
+    rc::Rc,
+};
+
+/// A handler of values, independent of the frontend.
+///
+/// This is a plain `Rc<dyn Fn>`, with an API similar to yew's `Callback`. Two handlers are equal
+/// if they point to the same function.
+pub struct Handler<T>(Rc<dyn Fn(T)>);
+
+impl<T> Handler<T> {
+    pub fn emit(&self, value: T) {
+        (self.0)(value)
+    }
+}
+
+impl<T: 'static> Handler<T> {
+    /// A handler, ignoring all values.
+    pub fn noop() -> Self {
+        Self::from(|_| {})
+    }
+
+    /// Create a new handler, converting values before passing them on to this one.
+    pub fn reform<U, F>(&self, f: F) -> Handler<U>
+    where
+        F: Fn(U) -> T + 'static,
+    {
+        let handler = self.clone();
+        Handler::from(move |value| handler.emit(f(value)))
+    }
+}
+
+impl<T, F> From<F> for Handler<T>
+where
+    F: Fn(T) + 'static,
+{
+    fn from(f: F) -> Self {
+        Self(Rc::new(f))
+    }
+}
+
+impl<T> Clone for Handler<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> PartialEq for Handler<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<T> Debug for Handler<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Handler<_>")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_reform() {
+        let received = Rc::new(RefCell::new(vec![]));
+        let handler = {
+            let received = received.clone();
+            Handler::from(move |value: String| received.borrow_mut().push(value))
+        };
+
+        let reformed = handler.reform(|value: u32| value.to_string());
+        reformed.emit(42);
+        handler.emit("foo".into());
+
+        assert_eq!(
+            vec!["42".to_string(), "foo".to_string()],
+            *received.borrow()
+        );
+    }
+
+    #[test]
+    fn test_eq() {
+        let handler = Handler::<()>::noop();
+        assert_eq!(handler, handler.clone());
+        assert_ne!(handler, Handler::noop());
+    }
+}
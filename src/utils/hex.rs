@@ -0,0 +1,73 @@
+//! Rendering binary data as hex.
+
+use std::fmt::Write;
+
+/// A single line preview, of up to `max` bytes.
+pub fn preview(data: &[u8], max: usize) -> String {
+    let mut result = data
+        .iter()
+        .take(max)
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if data.len() > max {
+        result.push_str(" …");
+    }
+
+    result
+}
+
+/// A classic hex dump, with offset, hex and ASCII columns, of up to `max` bytes.
+pub fn dump(data: &[u8], max: usize) -> String {
+    let mut result = String::new();
+
+    for (i, line) in data[..data.len().min(max)].chunks(16).enumerate() {
+        let hex = line
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let ascii: String = line
+            .iter()
+            .map(|&b| match b {
+                0x20..=0x7e => b as char,
+                _ => '.',
+            })
+            .collect();
+        let _ = writeln!(result, "{:08x}  {hex:<47}  |{ascii}|", i * 16);
+    }
+
+    if data.len() > max {
+        let _ = writeln!(result, "… {} more bytes", data.len() - max);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_preview() {
+        assert_eq!("", preview(&[], 4));
+        assert_eq!("00 01 ff", preview(&[0x00, 0x01, 0xff], 4));
+        assert_eq!("00 01 …", preview(&[0x00, 0x01, 0xff], 2));
+    }
+
+    #[test]
+    fn test_dump() {
+        assert_eq!(
+            "00000000  48 65 6c 6c 6f 00                                |Hello.|\n",
+            dump(b"Hello\0", 64)
+        );
+
+        let data: Vec<u8> = (0..40).collect();
+        let dump = dump(&data, 32);
+        let lines: Vec<_> = dump.lines().collect();
+        assert_eq!(3, lines.len());
+        assert!(lines[1].starts_with("00000010  10 11 12"));
+        assert_eq!("… 8 more bytes", lines[2]);
+    }
+}
@@ -1,7 +1,12 @@
 use serde::Serialize;
 
 pub mod float;
+pub mod geo;
+pub mod handler;
+pub mod hex;
 pub mod monaco;
+pub mod random;
+#[cfg(target_arch = "wasm32")]
 pub mod ui;
 
 pub fn to_yaml<T>(content: &T) -> String
@@ -0,0 +1,300 @@
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+
+#[cfg(target_arch = "wasm32")]
+pub use wasm::*;
+
+use anyhow::anyhow;
+use jsonschema::{paths::PathChunk, JSONSchema};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{iter::Peekable, marker::PhantomData};
+use yaml_rust::{
+    parser::{Event, MarkedEventReceiver, Parser},
+    scanner::Marker as ScanMarker,
+};
+
+/// Marker severity "error", as defined by monaco's `MarkerSeverity`.
+const MARKER_SEVERITY_ERROR: u32 = 8;
+
+/// A validation marker, serialized as monaco's `IMarkerData`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Marker {
+    pub severity: u32,
+    pub message: String,
+    pub start_line_number: usize,
+    pub start_column: usize,
+    pub end_line_number: usize,
+    pub end_column: usize,
+}
+
+/// Create a marker, spanning from the position to the end of its line.
+fn marker(content: &str, message: String, line: usize, column: usize) -> Marker {
+    let end_column = content
+        .lines()
+        .nth(line - 1)
+        .map(|l| l.chars().count() + 1)
+        .unwrap_or(column)
+        .max(column + 1);
+
+    Marker {
+        severity: MARKER_SEVERITY_ERROR,
+        message,
+        start_line_number: line,
+        start_column: column,
+        end_line_number: line,
+        end_column,
+    }
+}
+
+/// Validate YAML content by deserializing it into `T`, reporting the errors as markers.
+pub fn validate_yaml<T>(content: &str) -> Vec<Marker>
+where
+    T: DeserializeOwned,
+{
+    match serde_yaml::from_str::<T>(content) {
+        Ok(_) => vec![],
+        Err(err) => {
+            let (line, column) = err
+                .location()
+                .map(|l| (l.line().max(1), l.column().max(1)))
+                .unwrap_or((1, 1));
+            vec![marker(content, err.to_string(), line, column)]
+        }
+    }
+}
+
+/// Validates YAML content against the JSON schema of `T`, as well as by deserializing it.
+pub struct YamlValidator<T> {
+    schema: JSONSchema,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> YamlValidator<T>
+where
+    T: DeserializeOwned,
+{
+    pub fn new(schema: &Value) -> anyhow::Result<Self> {
+        let schema =
+            JSONSchema::compile(schema).map_err(|err| anyhow!("Invalid JSON schema: {err}"))?;
+        Ok(Self {
+            schema,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Validate the content, reporting the errors as markers.
+    ///
+    /// The schema covers constraints the deserializer doesn't check, and reports all errors
+    /// instead of only the first one.
+    pub fn validate(&self, content: &str) -> Vec<Marker> {
+        let mut markers = vec![];
+
+        // syntax errors are reported when deserializing
+        if let Ok(document) = serde_yaml::from_str::<Value>(content) {
+            if let Err(errors) = self.schema.validate(&document) {
+                let nodes = Node::parse(content);
+                for err in errors {
+                    let path = err
+                        .instance_path
+                        .iter()
+                        .map(|chunk| match chunk {
+                            PathChunk::Property(name) => name.to_string(),
+                            PathChunk::Index(index) => index.to_string(),
+                            PathChunk::Keyword(keyword) => keyword.to_string(),
+                        })
+                        .collect::<Vec<_>>();
+                    let (line, column) = nodes
+                        .as_ref()
+                        .and_then(|nodes| nodes.locate(&path))
+                        .unwrap_or((1, 1));
+                    markers.push(marker(content, err.to_string(), line, column));
+                }
+            }
+        }
+
+        markers.extend(validate_yaml::<T>(content));
+        markers
+    }
+}
+
+/// A node of a YAML document, with the position (line and column) it starts at.
+struct Node {
+    position: (usize, usize),
+    content: Content,
+}
+
+enum Content {
+    Scalar(String),
+    Sequence(Vec<Node>),
+    Mapping(Vec<(Node, Node)>),
+}
+
+/// Collects the events of the YAML parser.
+#[derive(Default)]
+struct Events(Vec<(Event, ScanMarker)>);
+
+impl MarkedEventReceiver for Events {
+    fn on_event(&mut self, event: Event, mark: ScanMarker) {
+        self.0.push((event, mark));
+    }
+}
+
+impl Node {
+    /// Parse the (first) document of the content.
+    fn parse(content: &str) -> Option<Self> {
+        let mut events = Events::default();
+        Parser::new(content.chars()).load(&mut events, false).ok()?;
+        let mut events = events.0.into_iter().peekable();
+
+        // skip to the start of the document
+        while matches!(
+            events.peek(),
+            Some((Event::StreamStart | Event::DocumentStart, _))
+        ) {
+            events.next();
+        }
+        Self::next(&mut events)
+    }
+
+    fn next<I>(events: &mut Peekable<I>) -> Option<Self>
+    where
+        I: Iterator<Item = (Event, ScanMarker)>,
+    {
+        let (event, mark) = events.next()?;
+        let content = match event {
+            Event::Scalar(value, ..) => Content::Scalar(value),
+            Event::SequenceStart(_) => {
+                let mut items = vec![];
+                while !matches!(events.peek(), None | Some((Event::SequenceEnd, _))) {
+                    items.push(Self::next(events)?);
+                }
+                events.next();
+                Content::Sequence(items)
+            }
+            Event::MappingStart(_) => {
+                let mut entries = vec![];
+                while !matches!(events.peek(), None | Some((Event::MappingEnd, _))) {
+                    entries.push((Self::next(events)?, Self::next(events)?));
+                }
+                events.next();
+                Content::Mapping(entries)
+            }
+            // aliases can't be followed, but are also not used by the settings
+            _ => Content::Scalar(String::new()),
+        };
+
+        Some(Self {
+            position: (mark.line().max(1), mark.col() + 1),
+            content,
+        })
+    }
+
+    /// Locate the node of a path, pointing to the key of mapping entries.
+    fn locate(&self, path: &[String]) -> Option<(usize, usize)> {
+        let (first, rest) = match path.split_first() {
+            Some(split) => split,
+            None => return Some(self.position),
+        };
+
+        let (position, node) = match &self.content {
+            Content::Sequence(items) => {
+                let item = items.get(first.parse::<usize>().ok()?)?;
+                (item.position, item)
+            }
+            Content::Mapping(entries) => {
+                entries.iter().find_map(|(key, value)| match &key.content {
+                    Content::Scalar(name) if name == first => Some((key.position, value)),
+                    _ => None,
+                })?
+            }
+            Content::Scalar(_) => return None,
+        };
+
+        if rest.is_empty() {
+            Some(position)
+        } else {
+            node.locate(rest)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{settings::Settings, utils::to_yaml};
+
+    #[test]
+    fn test_validate_valid() {
+        let yaml = to_yaml(&Settings::default());
+        assert_eq!(validate_yaml::<Settings>(&yaml), vec![]);
+    }
+
+    #[test]
+    fn test_validate_invalid() {
+        let yaml = to_yaml(&Settings::default()).replace("autoConnect: false", "autoConnect: 42");
+        let markers = validate_yaml::<Settings>(&yaml);
+
+        assert_eq!(markers.len(), 1);
+        assert_eq!(markers[0].severity, MARKER_SEVERITY_ERROR);
+        assert_eq!(
+            yaml.lines().nth(markers[0].start_line_number - 1),
+            Some("autoConnect: 42")
+        );
+    }
+
+    fn validator() -> YamlValidator<Settings> {
+        YamlValidator::new(&serde_json::to_value(Settings::schema()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_schema_valid() {
+        let yaml = to_yaml(&Settings::default());
+        assert_eq!(validator().validate(&yaml), vec![]);
+    }
+
+    #[test]
+    fn test_schema_invalid() {
+        let yaml = to_yaml(&Settings::default()).replace("autoConnect: false", "autoConnect: 42");
+        let markers = validator().validate(&yaml);
+
+        assert!(!markers.is_empty());
+        for marker in markers {
+            assert_eq!(
+                yaml.lines().nth(marker.start_line_number - 1),
+                Some("autoConnect: 42")
+            );
+        }
+    }
+
+    #[test]
+    fn test_schema_only() {
+        // deserializes fine, but is rejected by the schema
+        let yaml = to_yaml(&Settings::default()).replace(
+            "cloudEvents: false",
+            "cloudEvents: true\n  lorawan:\n    network: ttn\n    devEui: '0011223344556677'",
+        );
+        assert_eq!(validate_yaml::<Settings>(&yaml), vec![]);
+
+        let markers = validator().validate(&yaml);
+        assert_eq!(markers.len(), 1);
+        assert_eq!(
+            yaml.lines().nth(markers[0].start_line_number - 1),
+            Some("payload:")
+        );
+    }
+
+    #[test]
+    fn test_locate() {
+        let yaml = "a:\n  b:\n    - 1\n    - c: 2\n";
+        let node = Node::parse(yaml).unwrap();
+        let path = |path: &[&str]| path.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(node.locate(&[]), Some((1, 1)));
+        assert_eq!(node.locate(&path(&["a", "b"])), Some((2, 3)));
+        assert_eq!(node.locate(&path(&["a", "b", "1"])), Some((4, 7)));
+        assert_eq!(node.locate(&path(&["a", "b", "1", "c"])), Some((4, 7)));
+        assert_eq!(node.locate(&path(&["a", "x"])), None);
+    }
+}
@@ -0,0 +1,67 @@
+use super::YamlValidator;
+use crate::utils::to_yaml;
+use gloo_utils::format::JsValueSerdeExt;
+use monaco::{api::TextModel, sys::editor::ITextModel};
+use serde::de::DeserializeOwned;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(module = "/js/monaco/wrapper.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = "watchYamlModel")]
+    fn watch_yaml_model(
+        model: &ITextModel,
+        validator: &Closure<dyn Fn(String) -> JsValue>,
+    ) -> JsValue;
+
+    #[wasm_bindgen(js_name = "unwatchYamlModel")]
+    fn unwatch_yaml_model(model: &ITextModel, subscription: &JsValue);
+}
+
+/// Convert content to YAML
+pub fn to_yaml_model<T>(content: &T) -> Result<TextModel, JsValue>
+where
+    T: serde::Serialize,
+{
+    to_model(Some("yaml"), to_yaml(content))
+}
+
+/// Convert content to TextModel
+pub fn to_model<S>(language: Option<&str>, text: S) -> Result<TextModel, JsValue>
+where
+    S: AsRef<str>,
+{
+    TextModel::create(text.as_ref(), language, None)
+}
+
+/// Validates a YAML model for as long as it is alive, showing the result as editor markers.
+pub struct YamlValidation {
+    model: TextModel,
+    subscription: JsValue,
+    _validator: Closure<dyn Fn(String) -> JsValue>,
+}
+
+impl YamlValidation {
+    pub fn new<T>(model: TextModel, validator: Rc<YamlValidator<T>>) -> Self
+    where
+        T: DeserializeOwned + 'static,
+    {
+        let validator = Closure::wrap(Box::new(move |content: String| {
+            JsValue::from_serde(&validator.validate(&content)).unwrap_or(JsValue::NULL)
+        }) as Box<dyn Fn(String) -> JsValue>);
+
+        let subscription = watch_yaml_model(model.as_ref(), &validator);
+
+        Self {
+            model,
+            subscription,
+            _validator: validator,
+        }
+    }
+}
+
+impl Drop for YamlValidation {
+    fn drop(&mut self) {
+        unwatch_yaml_model(self.model.as_ref(), &self.subscription);
+    }
+}
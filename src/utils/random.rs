@@ -0,0 +1,184 @@
+use crate::utils::float::{ApproxF64, Zero};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// A random distribution of values.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Distribution {
+    /// Always the same value.
+    Constant { value: ApproxF64<Zero, 2> },
+    /// Uniformly distributed between `min` (inclusive) and `max` (exclusive).
+    Uniform {
+        min: ApproxF64<Zero, 2>,
+        max: ApproxF64<Zero, 2>,
+    },
+    /// Normally distributed.
+    #[serde(rename_all = "camelCase")]
+    Normal {
+        mean: ApproxF64<Zero, 2>,
+        std_dev: ApproxF64<Zero, 2>,
+    },
+    /// Exponentially distributed, with the provided mean.
+    Exponential { mean: ApproxF64<Zero, 2> },
+    /// Poisson distributed (integers), with the provided mean.
+    Poisson { mean: ApproxF64<Zero, 2> },
+}
+
+impl Default for Distribution {
+    fn default() -> Self {
+        Self::Constant { value: 1f64.into() }
+    }
+}
+
+impl Distribution {
+    /// Draw a sample, using the random number generator of the platform runtime.
+    pub fn sample(&self) -> f64 {
+        self.sample_with(&mut random)
+    }
+
+    /// Draw a sample, using the provided source of uniformly distributed values in `[0, 1)`.
+    pub fn sample_with<R>(&self, random: &mut R) -> f64
+    where
+        R: FnMut() -> f64,
+    {
+        match self {
+            Self::Constant { value } => value.0,
+            Self::Uniform { min, max } => min.0 + (max.0 - min.0) * random(),
+            Self::Normal { mean, std_dev } => mean.0 + std_dev.0 * standard_normal(random),
+            Self::Exponential { mean } => exponential(mean.0, random),
+            Self::Poisson { mean } => poisson(mean.0, random) as f64,
+        }
+    }
+}
+
+/// A uniformly distributed value in `[0, 1)`.
+pub fn random() -> f64 {
+    crate::runtime::random()
+}
+
+/// A standard normal distributed value, using the Box-Muller transform.
+pub fn standard_normal<R>(random: &mut R) -> f64
+where
+    R: FnMut() -> f64,
+{
+    // avoid ln(0)
+    let u1 = 1.0 - random();
+    let u2 = random();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+/// An exponentially distributed value, with the provided mean.
+pub fn exponential<R>(mean: f64, random: &mut R) -> f64
+where
+    R: FnMut() -> f64,
+{
+    -mean * (1.0 - random()).ln()
+}
+
+/// A Poisson distributed value, with the provided mean.
+///
+/// Uses Knuth's algorithm for small values, and a normal approximation for larger ones.
+pub fn poisson<R>(mean: f64, random: &mut R) -> u64
+where
+    R: FnMut() -> f64,
+{
+    if mean <= 0.0 || !mean.is_finite() {
+        return 0;
+    }
+
+    if mean > 30.0 {
+        let value = mean + mean.sqrt() * standard_normal(random);
+        return value.round().max(0.0) as u64;
+    }
+
+    let limit = (-mean).exp();
+    let mut k = 0;
+    let mut p = random();
+    while p > limit {
+        k += 1;
+        p *= random();
+    }
+    k
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A simple, deterministic source of uniform values.
+    fn lcg(seed: u64) -> impl FnMut() -> f64 {
+        let mut state = seed;
+        move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        }
+    }
+
+    fn mean<F: FnMut() -> f64>(n: usize, mut f: F) -> f64 {
+        (0..n).map(|_| f()).sum::<f64>() / n as f64
+    }
+
+    #[test]
+    fn test_constant() {
+        let d = Distribution::Constant { value: 2f64.into() };
+        assert_eq!(2.0, d.sample_with(&mut || 0.5));
+    }
+
+    #[test]
+    fn test_uniform() {
+        let d = Distribution::Uniform {
+            min: 1f64.into(),
+            max: 3f64.into(),
+        };
+        assert_eq!(1.0, d.sample_with(&mut || 0.0));
+        assert_eq!(2.0, d.sample_with(&mut || 0.5));
+    }
+
+    #[test]
+    fn test_means() {
+        let mut random = lcg(42);
+
+        let d = Distribution::Normal {
+            mean: 5f64.into(),
+            std_dev: 1f64.into(),
+        };
+        let m = mean(10_000, || d.sample_with(&mut random));
+        assert!((m - 5.0).abs() < 0.1, "Normal: {m}");
+
+        let d = Distribution::Exponential { mean: 2f64.into() };
+        let m = mean(10_000, || d.sample_with(&mut random));
+        assert!((m - 2.0).abs() < 0.1, "Exponential: {m}");
+
+        for lambda in [0.5, 3.0, 50.0] {
+            let m = mean(10_000, || poisson(lambda, &mut random) as f64);
+            assert!(
+                (m - lambda).abs() < lambda.sqrt() * 0.1 + 0.05,
+                "Poisson({lambda}): {m}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_poisson_zero() {
+        assert_eq!(0, poisson(0.0, &mut || 0.5));
+        assert_eq!(0, poisson(-1.0, &mut || 0.5));
+    }
+
+    #[test]
+    fn test_serde() {
+        let d: Distribution =
+            serde_json::from_value(serde_json::json!({"type": "normal", "mean": 1, "stdDev": 0.5}))
+                .unwrap();
+        assert_eq!(
+            Distribution::Normal {
+                mean: 1f64.into(),
+                std_dev: 0.5f64.into()
+            },
+            d
+        );
+    }
+}
@@ -1,7 +1,12 @@
-use patternfly_yew::*;
+use crate::utils::hex;
 use serde_json::Value;
 use yew::prelude::*;
 
+/// Number of bytes shown when previewing binary data.
+const MAX_PREVIEW: usize = 16;
+/// Number of bytes shown when expanding binary data.
+const MAX_DUMP: usize = 1024;
+
 pub fn render_payload(data: &[u8], expanded: bool) -> Html {
     if let Ok(json) = serde_json::from_slice::<Value>(data) {
         let json = match expanded {
@@ -23,39 +28,16 @@ pub fn render_payload(data: &[u8], expanded: bool) -> Html {
         );
     }
 
-    html!("Binary data")
-}
-
-pub trait ToDetail {
-    fn to_details(&self) -> (String, String);
-}
-
-impl<V> ToDetail for (&str, V)
-where
-    V: ToString,
-{
-    fn to_details(&self) -> (String, String) {
-        (self.0.into(), self.1.to_string())
+    match expanded {
+        true => html!(
+            <code><pre>
+                { hex::dump(data, MAX_DUMP) }
+            </pre></code>
+        ),
+        false => html!(
+            <code>
+                { format!("{} bytes: {}", data.len(), hex::preview(data, MAX_PREVIEW)) }
+            </code>
+        ),
     }
 }
-
-pub fn details<'d, D, I>(details: I) -> Html
-where
-    D: ToDetail + Sized,
-    I: IntoIterator<Item = D>,
-{
-    html!(
-        <Form>
-          { for details.into_iter().map(|details|{
-              let (label, value) = details.to_details();
-              html!(
-                  <FormGroup
-                    label={format!("{label}:")}
-                  >
-                    <TextInput value={value} readonly=true />
-                  </FormGroup>
-              )
-          })}
-        </Form>
-    )
-}
@@ -0,0 +1,89 @@
+use crate::simulator::simulations::led_matrix::{Display, Pixel, Text};
+use gloo_timers::callback::Interval;
+use patternfly_yew::{Card, Flex, FlexItem};
+use yew::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, yew::Properties)]
+pub struct LedMatrixProperties {
+    pub display: Display,
+    pub color: String,
+    pub color_off: String,
+}
+
+#[function_component(LedMatrixComponent)]
+pub fn led_matrix(props: &LedMatrixProperties) -> Html {
+    let step = use_state(|| 0usize);
+
+    {
+        // scroll the text, if there is any
+        let step = step.clone();
+        let columns = props.display.frame.columns();
+        use_effect_with_deps(
+            move |text: &Option<Text>| {
+                step.set(0);
+                let interval = text.as_ref().map(|text| {
+                    let steps = text.steps(columns).max(1);
+                    let mut current = 0;
+                    Interval::new(text.speed, move || {
+                        current = (current + 1) % steps;
+                        step.set(current);
+                    })
+                });
+                move || drop(interval)
+            },
+            props.display.text.clone(),
+        );
+    }
+
+    let mut frame = props.display.frame.clone();
+    if let Some(text) = &props.display.text {
+        text.render(&mut frame, *step);
+    }
+
+    let size = (200 / frame.columns().max(1)).clamp(8, 25);
+    let margin = (size / 5).max(1);
+    let base = format!(
+        "height: {size}px; width: {size}px; border-radius: 50%; margin: {margin}px; display: inline-block;"
+    );
+
+    let color = if props.color.is_empty() {
+        "red"
+    } else {
+        &props.color
+    };
+    let color_off = if props.color_off.is_empty() {
+        "lightgray"
+    } else {
+        &props.color_off
+    };
+
+    html!(
+        <>
+        <Flex>
+        <FlexItem>
+        <Card>
+        <div>
+            { for frame.iter_rows().enumerate().map(|(row, pixels)| html!(
+                <div style="line-height: 0;">
+                    { for pixels.iter().enumerate().map(|(column, pixel)| {
+                        let style = match pixel {
+                            Pixel::Off => format!("{base} background-color: {color_off};"),
+                            Pixel::On(brightness) => {
+                                let opacity = *brightness as f32 / 255.0;
+                                format!("{base} opacity: {opacity}; background-color: {color};")
+                            }
+                            Pixel::Rgb(r, g, b) => {
+                                format!("{base} background-color: rgb({r}, {g}, {b});")
+                            }
+                        };
+                        html!(<span id={format!("{row}x{column}")} {style} />)
+                    }) }
+                </div>
+            )) }
+        </div>
+        </Card>
+        </FlexItem>
+        </Flex>
+        </>
+    )
+}
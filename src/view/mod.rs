@@ -0,0 +1,249 @@
+//! Rendering the views of simulations.
+
+mod led_matrix;
+
+pub use led_matrix::*;
+
+use crate::{
+    simulator::simulations::{slider::Step, Upload, View},
+    utils::{geo::Point, handler::Handler},
+};
+use js_sys::Uint8Array;
+use patternfly_yew::*;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// Render the view of a simulation.
+pub fn render_view(view: &View) -> Html {
+    match view {
+        View::Empty => html!(),
+        View::Stack(views) => html!(
+            <>
+                { for views.iter().map(render_view) }
+            </>
+        ),
+        View::Row(views) => match views.split_last() {
+            Some((last, views)) => html!(
+                <Flex>
+                    { for views.iter().map(|view| html_nested!(
+                        <FlexItem>
+                            { render_view(view) }
+                        </FlexItem>
+                    )) }
+                    <FlexItem modifiers={[FlexModifier::Grow]}>
+                        { render_view(last) }
+                    </FlexItem>
+                </Flex>
+            ),
+            None => html!(),
+        },
+        View::Form(groups) => html!(
+            <Form>
+                { for groups.iter().map(|(label, view)| html!(
+                    <FormGroup label={label.clone()}>
+                        { render_view(view) }
+                    </FormGroup>
+                )) }
+            </Form>
+        ),
+        View::Toolbar(views) => html!(
+            <Toolbar>
+                <ToolbarGroup>
+                    { for views.iter().map(|view| html!(
+                        <ToolbarItem>
+                            { render_view(view) }
+                        </ToolbarItem>
+                    )) }
+                </ToolbarGroup>
+            </Toolbar>
+        ),
+
+        View::Details(details) => render_details(details),
+        View::Message(message) => html!(
+            <Content>
+                { message }
+            </Content>
+        ),
+        View::Error { title, message } => html!(
+            <Alert r#type={Type::Danger} title={title.clone()} inline=true>
+                { message }
+            </Alert>
+        ),
+
+        View::Slider {
+            min,
+            max,
+            value,
+            onchange,
+        } => html!(
+            <Slider
+                suppress_initial_change=true
+                min={to_step(min)}
+                max={to_step(max)}
+                value={*value}
+                onchange={callback(onchange)}
+                />
+        ),
+        View::Switch { checked, onchange } => html!(
+            <Switch
+                checked={*checked}
+                on_change={callback(onchange)}
+                />
+        ),
+        View::Button {
+            label,
+            primary,
+            onclick,
+        } => {
+            let variant = match primary {
+                true => Variant::Primary,
+                false => Variant::Secondary,
+            };
+            let onclick = onclick.clone();
+            html!(
+                <Button
+                    label={label.clone()}
+                    {variant}
+                    onclick={Callback::from(move |_| onclick.emit(()))}
+                    />
+            )
+        }
+        View::TextInput { value, onchange } => html!(
+            <TextInput value={value.clone()} onchange={callback(onchange)} />
+        ),
+        View::Select {
+            options,
+            selected,
+            onselect,
+        } => {
+            let variant = SelectVariant::Single(callback(onselect));
+            html!(
+                <FormSelect<String> {variant}>
+                    { for options.iter().map(|option| {
+                        let selected = selected.as_ref() == Some(option);
+                        html_nested!(
+                            <FormSelectOption<String>
+                                value={option.clone()}
+                                {selected}
+                                id={option.clone()}
+                            />
+                        )
+                    }) }
+                </FormSelect<String>>
+            )
+        }
+        View::File { current, onupload } => {
+            let onupload = onupload.clone();
+            let onchange = Callback::from(move |e: Event| {
+                let input: HtmlInputElement = e.target_unchecked_into();
+                if let Some(file) = input.files().and_then(|files| files.get(0)) {
+                    let onupload = onupload.clone();
+                    spawn_local(async move {
+                        match JsFuture::from(file.array_buffer()).await {
+                            Ok(buffer) => onupload.emit(Upload {
+                                name: file.name(),
+                                data: Uint8Array::new(&buffer).to_vec(),
+                            }),
+                            Err(err) => log::warn!("Failed to read file: {err:?}"),
+                        }
+                    });
+                }
+            });
+            html!(
+                <>
+                    <input type="file" {onchange} />
+                    <p>{ current }</p>
+                </>
+            )
+        }
+
+        View::Track { route, track } => render_track(route, track),
+        View::LedMatrix {
+            display,
+            color,
+            color_off,
+        } => html!(
+            <LedMatrixComponent
+                color={color.clone()}
+                color_off={color_off.clone()}
+                display={display.clone()}
+            />
+        ),
+    }
+}
+
+fn callback<T: 'static>(handler: &Handler<T>) -> Callback<T> {
+    let handler = handler.clone();
+    Callback::from(move |value| handler.emit(value))
+}
+
+fn to_step(step: &Step) -> patternfly_yew::Step {
+    patternfly_yew::Step {
+        value: step.value(),
+        label: step.label(),
+    }
+}
+
+fn render_details(details: &[(String, String)]) -> Html {
+    html!(
+        <Form>
+          { for details.iter().map(|(label, value)|{
+              html!(
+                  <FormGroup
+                    label={format!("{label}:")}
+                  >
+                    <TextInput value={value.clone()} readonly=true />
+                  </FormGroup>
+              )
+          })}
+        </Form>
+    )
+}
+
+/// Render the planned route, the recent track, and the current position.
+fn render_track(route: &[Point], track: &[Point]) -> Html {
+    const SIZE: f64 = 300.0;
+    const PADDING: f64 = 10.0;
+
+    let all = || route.iter().chain(track.iter());
+
+    let min_lat = all().map(|p| p.lat).fold(f64::INFINITY, f64::min);
+    let max_lat = all().map(|p| p.lat).fold(f64::NEG_INFINITY, f64::max);
+    let min_lon = all().map(|p| p.lon).fold(f64::INFINITY, f64::min);
+    let max_lon = all().map(|p| p.lon).fold(f64::NEG_INFINITY, f64::max);
+
+    // compensate for the longitude getting narrower towards the poles
+    let aspect = ((min_lat + max_lat) / 2.0).to_radians().cos();
+    let extent = ((max_lat - min_lat).max((max_lon - min_lon) * aspect)).max(f64::EPSILON);
+    let scale = (SIZE - 2.0 * PADDING) / extent;
+
+    let project = |p: &Point| {
+        (
+            PADDING + (p.lon - min_lon) * aspect * scale,
+            SIZE - PADDING - (p.lat - min_lat) * scale,
+        )
+    };
+    let polyline = |points: &mut dyn Iterator<Item = &Point>| {
+        points
+            .map(|p| {
+                let (x, y) = project(p);
+                format!("{x:.1},{y:.1}")
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let current = track.last().map(project);
+
+    html!(
+        <svg width={SIZE.to_string()} height={SIZE.to_string()} viewBox={format!("0 0 {SIZE} {SIZE}")}>
+            <rect x="0" y="0" width={SIZE.to_string()} height={SIZE.to_string()} fill="#f0f0f0" />
+            <polyline points={polyline(&mut route.iter())} fill="none" stroke="#8a8d90" stroke-width="2" stroke-dasharray="4" />
+            <polyline points={polyline(&mut track.iter())} fill="none" stroke="#06c" stroke-width="2" />
+            if let Some((x, y)) = current {
+                <circle cx={x.to_string()} cy={y.to_string()} r="5" fill="#c9190b" />
+            }
+        </svg>
+    )
+}
@@ -0,0 +1,34 @@
+use drogue_device_simulator::{
+    settings::Settings,
+    simulator::{
+        mock::{self, Harness},
+        State,
+    },
+};
+use serde_json::json;
+use tokio::task::LocalSet;
+
+fn settings() -> Settings {
+    mock::settings(json!({
+        "sine1": {
+            "sine": {"amplitude": 1.0, "length": "1m", "period": "10ms"}
+        }
+    }))
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_publish_after_connect() {
+    LocalSet::new()
+        .run_until(async {
+            let mut harness = Harness::new(settings());
+            harness.engine.start();
+
+            assert!(harness.run_until(|h| h.broker.published().len() >= 3).await);
+            assert_eq!(
+                harness.states[1..],
+                [State::Connecting, State::Subscribing, State::Connected]
+            );
+            assert_eq!(harness.broker.connectors(), 1);
+        })
+        .await;
+}